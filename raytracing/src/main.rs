@@ -1,462 +1,407 @@
-use raytracing::math::color::Color;
-use raytracing::math::shape::{Plane, Shape, Sphere};
+use clap::Parser;
+use image::ImageFormat;
 use raytracing::math::vec3d::Vec3D;
-use raytracing::render::frame::Frame;
-use raytracing::render::hittable::Hittable;
-use raytracing::render::lightning::Light;
-use raytracing::render::material::Material;
-use raytracing::render::scene::Scene;
+use raytracing::render::animation::frame_file_name;
+use raytracing::render::aov::Aov;
+use raytracing::render::description::{SceneDescription, SceneError};
+use raytracing::render::frame::{Frame, OutputFormat, Progressive};
+use raytracing::render::integrator::Integrator;
+use raytracing::render::lightning::Shadows;
+use raytracing::render::sampling::{Filter, Pattern};
+use raytracing::render::tone_mapping::ToneOperator;
+use std::error::Error;
 use std::fs::create_dir_all;
-use std::path::Path;
+use std::iter;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::AtomicBool;
+use std::thread;
+
+/// Renders a TOML scene description into an image.
+#[derive(Parser)]
+#[clap(name = "raytracing", version)]
+struct Options {
+    /// Scene description file
+    scene: PathBuf,
+
+    /// Output image path
+    #[clap(short, long, default_value = "image.png")]
+    output: PathBuf,
+
+    /// Image width in pixels, overrides the scene file
+    #[clap(long)]
+    width: Option<usize>,
+
+    /// Image height in pixels, overrides the scene file
+    #[clap(long)]
+    height: Option<usize>,
+
+    /// Vertical field of view in degrees, overrides the scene file
+    #[clap(long)]
+    fov: Option<f64>,
+
+    /// Camera position as `x,y,z`, overrides the scene file
+    #[clap(long, value_parser = parse_vector, allow_hyphen_values = true)]
+    camera: Option<Vec3D<f64>>,
+
+    /// Point the camera looks at as `x,y,z`, overrides the scene file
+    #[clap(long, value_parser = parse_vector, allow_hyphen_values = true)]
+    look_at: Option<Vec3D<f64>>,
+
+    /// Radius of the camera lens, enables depth of field, overrides the scene file
+    #[clap(long)]
+    aperture: Option<f64>,
+
+    /// Distance to the plane in focus, overrides the scene file
+    #[clap(long)]
+    focus_distance: Option<f64>,
+
+    /// Times within the frame as `open,close`, from 0 to 1, blurring moving objects
+    /// in between, overrides the scene file
+    #[clap(long, value_parser = parse_shutter)]
+    shutter: Option<(f64, f64)>,
+
+    /// Render every pixel with N x N samples, overrides the scene file
+    #[clap(short, long)]
+    samples: Option<usize>,
+
+    /// Placement of the samples inside a pixel: uniform, stratified or random
+    #[clap(long)]
+    pattern: Option<Pattern>,
+
+    /// Reconstruction filter: box, tent, gaussian or mitchell
+    #[clap(long)]
+    filter: Option<Filter>,
+
+    /// Render pixels differing from a neighbour by more than this once again
+    #[clap(long)]
+    adaptive_threshold: Option<f64>,
+
+    /// Render adaptively refined pixels with N x N samples
+    #[clap(long)]
+    adaptive_samples: Option<usize>,
+
+    /// Maximum number of nested reflections and refractions, overrides the scene file
+    #[clap(long)]
+    depth: Option<i32>,
+
+    /// Rendering algorithm: whitted or path_tracing, overrides the scene file
+    #[clap(long)]
+    integrator: Option<Integrator>,
+
+    /// Number of paths traced per pixel by the path tracing integrator
+    #[clap(long)]
+    samples_per_pixel: Option<usize>,
+
+    /// Shadows of transparent objects: opaque, transparent or colored, overrides the scene file
+    #[clap(long)]
+    shadows: Option<Shadows>,
+
+    /// Average the image with N more renders of new random samples
+    #[clap(long, default_value_t = 0)]
+    refinement_passes: usize,
+
+    /// Render a low resolution preview first and report every finished pass
+    #[clap(long)]
+    progress: bool,
+
+    /// Number of render threads, all available cores by default
+    #[clap(short = 'j', long)]
+    threads: Option<usize>,
+
+    /// Seed of the random samples
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Tone mapping operator of 8-bit images: clamp, reinhard or aces, overrides the scene file
+    #[clap(long)]
+    tone_mapping: Option<ToneOperator>,
+
+    /// Exposure of 8-bit images in stops, overrides the scene file
+    #[clap(long, allow_hyphen_values = true)]
+    exposure: Option<f64>,
+
+    /// Encode 8-bit images with the sRGB transfer curve
+    #[clap(long)]
+    srgb: bool,
+
+    /// Also save an extra buffer as `NAME=PATH`: depth, normal, albedo, object_id or
+    /// recursion_depth, in the format guessed from the extension. May be repeated
+    #[clap(long = "aov", value_parser = parse_aov)]
+    aovs: Vec<(Aov, PathBuf)>,
+
+    /// Render every frame of the scene animation into the directory as
+    /// `frame_0001.png` and so on, in the --format given
+    #[clap(long, value_name = "DIRECTORY")]
+    animation: Option<PathBuf>,
+
+    /// Output image format, guessed from the output extension by default. The hdr and pfm
+    /// formats store the linear colors without tone mapping
+    #[clap(long)]
+    format: Option<OutputFormat>,
+}
 
 fn main() {
-    create_dir_all("images/").expect("Failed to create directory");
-    render_box_scene("images/box_scene.png", false);
-    render_box_scene("images/box_scene_antialiasing.png", true);
-    render_tutorial_scene("images/tutorial_scene.png");
+    let options = Options::parse();
+    if let Err(error) = run(&options) {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
 }
 
-fn render_box_scene<Q: AsRef<Path>>(path: Q, antialiasing: bool) {
-    let glass = Material {
-        specular: Color::unit() * 0.5,
-        shininess: 125.0,
-        reflectiveness: 0.1,
-        transparency: 0.9,
-        refractive_index: 1.5,
-        ..Material::default()
-    };
-    let mirror = Material {
-        specular: Color::unit() * 10.0,
-        shininess: 1425.0,
-        reflectiveness: 0.8,
-        ..Material::default()
-    };
-    let basic_matt = Material {
-        specular: Color::unit() * 0.05,
-        shininess: 1.0,
-        ..Material::default()
-    };
-    let basic_shiny = Material {
-        specular: Color::unit() * 0.9,
-        shininess: 100.0,
-        reflectiveness: 0.2,
-        ..Material::default()
-    };
+fn run(options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut description: SceneDescription<f64> =
+        SceneDescription::load(&options.scene).map_err(|error| scene_error(options, error))?;
+    if let Some(ref directory) = options.animation {
+        return render_animation(options, &description, directory);
+    }
+    override_description(options, &mut description)?;
 
-    let pale_yellow: Color<f64> = Color::new(253, 255, 194);
-    let green_tea: Color<f64> = Color::new(201, 255, 194);
-    let fresh_air: Color<f64> = Color::new(175, 228, 254);
-    let vodka: Color<f64> = Color::new(196, 181, 255);
-
-    let royal_purple: Color<f64> = Color::new(113, 78, 179);
-    let united_nations_blue: Color<f64> = Color::new(82, 134, 218);
-    let mantis: Color<f64> = Color::new(109, 187, 91);
-    let minion_yellow: Color<f64> = Color::new(235, 224, 81);
-    let royal_orange: Color<f64> = Color::new(249, 141, 82);
-    let paradise_pink: Color<f64> = Color::new(225, 78, 101);
-
-    let scene = Scene {
-        background_color: Color::zero(),
-        objects: vec![
-            Hittable::Primitive {
-                shape: Shape::Plane(Plane {
-                    origin: Vec3D {
-                        x: 0.0,
-                        y: 20.0,
-                        z: 0.0,
-                    },
-                    normal: Vec3D {
-                        x: 0.0,
-                        y: -1.0,
-                        z: 0.0,
-                    },
-                }),
-                material: Material {
-                    diffuse: pale_yellow,
-                    ..basic_matt
-                },
-            },
-            Hittable::Primitive {
-                shape: Shape::Plane(Plane {
-                    origin: Vec3D {
-                        x: 0.0,
-                        y: -10.0,
-                        z: 0.0,
-                    },
-                    normal: Vec3D {
-                        x: 0.0,
-                        y: 1.0,
-                        z: 0.0,
-                    },
-                }),
-                material: Material {
-                    diffuse: vodka * 0.5,
-                    ..basic_shiny
-                },
-            },
-            Hittable::Primitive {
-                shape: Shape::Plane(Plane {
-                    origin: Vec3D {
-                        x: 15.0,
-                        y: 0.0,
-                        z: 0.0,
-                    },
-                    normal: Vec3D {
-                        x: -1.0,
-                        y: 0.0,
-                        z: 0.0,
-                    },
-                }),
-                material: mirror,
-            },
-            Hittable::Primitive {
-                shape: Shape::Plane(Plane {
-                    origin: Vec3D {
-                        x: -15.0,
-                        y: 0.0,
-                        z: 0.0,
-                    },
-                    normal: Vec3D {
-                        x: 1.0,
-                        y: 0.0,
-                        z: 0.0,
-                    },
-                }),
-                material: Material {
-                    diffuse: green_tea * 0.9,
-                    ..basic_matt
-                },
-            },
-            Hittable::Primitive {
-                shape: Shape::Plane(Plane {
-                    origin: Vec3D {
-                        x: 0.0,
-                        y: 0.0,
-                        z: 5.0,
-                    },
-                    normal: Vec3D {
-                        x: 0.0,
-                        y: 0.0,
-                        z: -1.0,
-                    },
-                }),
-                material: Material {
-                    diffuse: fresh_air,
-                    ..basic_matt
-                },
-            },
-            Hittable::Primitive {
-                shape: Shape::Plane(Plane {
-                    origin: Vec3D {
-                        x: 0.0,
-                        y: 0.0,
-                        z: -45.0,
-                    },
-                    normal: Vec3D {
-                        x: 0.0,
-                        y: 0.0,
-                        z: 1.0,
-                    },
-                }),
-                material: Material {
-                    diffuse: fresh_air * 0.45,
-                    reflectiveness: 0.1,
-                    shininess: 3600.0,
-                    ..basic_shiny
-                },
-            },
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: 9.0,
-                        y: -6.5,
-                        z: -30.0,
-                    },
-                    radius: 3.5,
-                }),
-                material: mirror,
-            },
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: 0.5,
-                        y: -8.5,
-                        z: -36.0,
-                    },
-                    radius: 1.5,
-                }),
-                material: glass,
-            },
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: 2.0,
-                        y: -8.0,
-                        z: -24.0,
-                    },
-                    radius: 2.0,
-                }),
-                material: Material {
-                    diffuse: royal_purple * 0.7,
-                    ..basic_shiny
-                },
-            },
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: -3.0,
-                        y: -8.0,
-                        z: -28.0,
-                    },
-                    radius: 2.0,
-                }),
-                material: Material {
-                    diffuse: royal_orange * 0.7,
-                    ..basic_shiny
-                },
-            },
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: -7.0,
-                        y: -7.5,
-                        z: -23.0,
-                    },
-                    radius: 2.5,
-                }),
-                material: Material {
-                    diffuse: minion_yellow * 0.7,
-                    ..basic_shiny
-                },
-            },
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: -9.0,
-                        y: -6.0,
-                        z: -33.0,
-                    },
-                    radius: 4.0,
-                }),
-                material: Material {
-                    diffuse: united_nations_blue * 0.7,
-                    ..basic_shiny
-                },
-            },
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: 12.0,
-                        y: -8.8,
-                        z: -26.0,
-                    },
-                    radius: 1.2,
-                }),
-                material: Material {
-                    diffuse: mantis * 0.7,
-                    ..basic_shiny
-                },
-            },
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: -0.5,
-                        y: -9.0,
-                        z: -21.5,
-                    },
-                    radius: 1.0,
-                }),
-                material: Material {
-                    ambient: paradise_pink * 0.3,
-                    diffuse: paradise_pink * 0.6,
-                    ..basic_matt
-                },
-            },
-        ],
-        lights: vec![
-            Light {
-                position: Vec3D {
-                    x: 8.0,
-                    y: 8.0,
-                    z: 0.0,
-                },
-                intensity: 0.7,
-            },
-            Light {
-                position: Vec3D {
-                    x: -8.0,
-                    y: 8.0,
-                    z: 0.0,
-                },
-                intensity: 0.7,
-            },
-            Light {
-                position: Vec3D {
-                    x: 0.0,
-                    y: 5.0,
-                    z: -35.0,
-                },
-                intensity: 0.5,
-            },
-        ],
-        recursion_depth: 6,
+    let format = match options.format {
+        Some(format) => format,
+        None => OutputFormat::from_path(&options.output)
+            .ok_or_else(|| format!("cannot guess image format of {}", options.output.display()))?,
     };
+    let mut aov_formats = Vec::new();
+    for (aov, path) in &options.aovs {
+        let format = OutputFormat::from_path(path)
+            .ok_or_else(|| format!("cannot guess image format of {}", path.display()))?;
+        aov_formats.push((*aov, path, format));
+    }
+    for path in iter::once(&options.output).chain(options.aovs.iter().map(|(_, path)| path)) {
+        if let Some(directory) = path.parent() {
+            create_directory(directory)?;
+        }
+    }
 
-    if antialiasing {
-        let mut frame: Frame<f64> = Frame::new(1024 * 2, 768 * 2, 60.0);
-        frame.render(&scene);
-        frame.save_compressed(path).expect("Failed to save image");
-    } else {
-        let mut frame: Frame<f64> = Frame::new(1024, 768, 60.0);
-        frame.render(&scene);
-        frame.save(path).expect("Failed to save image");
+    let mut aovs = Vec::new();
+    for (aov, _, _) in &aov_formats {
+        if !aovs.contains(aov) {
+            aovs.push(*aov);
+        }
+    }
+    let frame = render(options, &description, aovs)?;
+    frame
+        .save_with_format(&options.output, format)
+        .map_err(|error| format!("failed to save {}: {}", options.output.display(), error))?;
+    for (aov, path, format) in aov_formats {
+        frame
+            .save_aov_with_format(aov, path, format)
+            .map_err(|error| format!("failed to save {}: {}", path.display(), error))?;
     }
+    Ok(())
 }
 
-fn render_tutorial_scene<Q: AsRef<Path>>(path: Q) {
-    let ivory = Material {
-        diffuse: Color {
-            r: 0.4,
-            g: 0.4,
-            b: 0.3,
-        } * 0.6,
-        specular: Color::unit() * 0.3,
-        shininess: 50.0,
-        reflectiveness: 0.1,
-        ..Material::default()
-    };
-    let glass = Material {
-        specular: Color::unit() * 0.5,
-        shininess: 125.0,
-        reflectiveness: 0.1,
-        transparency: 0.8,
-        refractive_index: 1.5,
-        ..Material::default()
-    };
-    let red_rubber = Material {
-        diffuse: Color {
-            r: 0.4,
-            g: 0.1,
-            b: 0.1,
-        } * 0.9,
-        specular: Color::unit() * 0.1,
-        shininess: 10.0,
-        ..Material::default()
-    };
-    let mirror = Material {
-        specular: Color::unit() * 10.0,
-        shininess: 1425.0,
-        reflectiveness: 0.8,
-        ..Material::default()
-    };
+/// Renders every frame of the animation of the description into the directory.
+fn render_animation(
+    options: &Options,
+    description: &SceneDescription<f64>,
+    directory: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let animation = description.animation.as_ref().ok_or_else(|| {
+        format!(
+            "{}: --animation requires an [animation] table",
+            options.scene.display()
+        )
+    })?;
+    if !options.aovs.is_empty() {
+        return Err("--aov cannot be combined with --animation".into());
+    }
+    let format = options
+        .format
+        .unwrap_or(OutputFormat::Image(ImageFormat::Png));
+    create_directory(directory)?;
+    for index in 0..animation.frames {
+        let mut frame_description = description
+            .frame(index)
+            .map_err(|error| scene_error(options, error))?;
+        override_description(options, &mut frame_description)?;
+        let frame = render(options, &frame_description, Vec::new())?;
+        let path = directory.join(frame_file_name(index, format.extension()));
+        frame
+            .save_with_format(&path, format)
+            .map_err(|error| format!("failed to save {}: {}", path.display(), error))?;
+        if options.progress {
+            eprintln!(
+                "frame {}/{} saved to {}",
+                index + 1,
+                animation.frames,
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
 
-    let scene = Scene {
-        background_color: Color {
-            r: 0.2,
-            g: 0.7,
-            b: 0.8,
-        },
-        objects: vec![
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: -3.0,
-                        y: 0.0,
-                        z: -16.0,
-                    },
-                    radius: 2.0,
-                }),
-                material: ivory,
-            },
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: -1.0,
-                        y: -1.5,
-                        z: -12.0,
-                    },
-                    radius: 2.0,
-                }),
-                material: glass,
-            },
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: 1.5,
-                        y: -0.5,
-                        z: -18.0,
-                    },
-                    radius: 3.0,
-                }),
-                material: red_rubber,
-            },
-            Hittable::Primitive {
-                shape: Shape::Sphere(Sphere {
-                    center: Vec3D {
-                        x: 7.0,
-                        y: 5.0,
-                        z: -18.0,
-                    },
-                    radius: 4.0,
-                }),
-                material: mirror,
-            },
-            Hittable::CheckerBoard {
-                plane: Plane {
-                    origin: Vec3D {
-                        x: -10.0,
-                        y: -4.0,
-                        z: -30.0,
-                    },
-                    normal: Vec3D {
-                        x: 0.0,
-                        y: 1.0,
-                        z: 0.0,
-                    },
-                },
-                width: 20.0,
-                basic_material: Material {
-                    diffuse: Color::unit() * 0.3,
-                    ..Material::default()
-                },
-                checker_color: Color {
-                    r: 1.0,
-                    g: 0.7,
-                    b: 0.3,
-                } * 0.3,
-                checker_size: 2.0,
-            },
-        ],
-        lights: vec![
-            Light {
-                position: Vec3D {
-                    x: -20.0,
-                    y: 20.0,
-                    z: 20.0,
-                },
-                intensity: 1.5,
-            },
-            Light {
-                position: Vec3D {
-                    x: 30.0,
-                    y: 50.0,
-                    z: -25.0,
-                },
-                intensity: 1.8,
-            },
-            Light {
-                position: Vec3D {
-                    x: 30.0,
-                    y: 20.0,
-                    z: 30.0,
-                },
-                intensity: 1.7,
-            },
-        ],
-        recursion_depth: 4,
+/// Applies the command line options overriding the scene file.
+fn override_description(
+    options: &Options,
+    description: &mut SceneDescription<f64>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(width) = options.width {
+        description.frame.width = width;
+    }
+    if let Some(height) = options.height {
+        description.frame.height = height;
+    }
+    if let Some(field_of_view) = options.fov {
+        description.camera.field_of_view = field_of_view;
+    }
+    if let Some(position) = options.camera {
+        description.camera.position = position;
+    }
+    if let Some(look_at) = options.look_at {
+        description.camera.look_at = look_at;
+    }
+    if let Some(aperture) = options.aperture {
+        description.camera.aperture = aperture;
+    }
+    if let Some(focus_distance) = options.focus_distance {
+        description.camera.focus_distance = Some(focus_distance);
+    }
+    if let Some((open, close)) = options.shutter {
+        description.camera.shutter_open = open;
+        description.camera.shutter_close = close;
+    }
+    let sampling = &mut description.sampling;
+    if let Some(samples) = options.samples {
+        sampling.samples = samples;
+    }
+    if let Some(pattern) = options.pattern {
+        sampling.pattern = pattern;
+    }
+    if let Some(filter) = options.filter {
+        sampling.filter = filter;
+    }
+    if let Some(threshold) = options.adaptive_threshold {
+        sampling.adaptive_threshold = Some(threshold);
+    }
+    if let Some(samples) = options.adaptive_samples {
+        sampling.adaptive_samples = samples;
+    }
+    if let Some(depth) = options.depth {
+        description.recursion_depth = depth;
+    }
+    if let Some(integrator) = options.integrator {
+        description.integrator = integrator;
+    }
+    if let Some(shadows) = options.shadows {
+        description.shadows = shadows;
+    }
+    let tone_mapping = &mut description.tone_mapping;
+    if let Some(operator) = options.tone_mapping {
+        tone_mapping.operator = operator;
+    }
+    if let Some(exposure) = options.exposure {
+        tone_mapping.exposure = exposure;
+    }
+    if options.srgb {
+        tone_mapping.srgb = true;
+    }
+    if let Some(samples) = options.samples_per_pixel {
+        match &mut description.integrator {
+            Integrator::PathTracing {
+                samples_per_pixel, ..
+            } => *samples_per_pixel = samples,
+            Integrator::Whitted => {
+                return Err("--samples-per-pixel requires the path_tracing integrator".into())
+            }
+        }
+    }
+    description
+        .validate()
+        .map_err(|error| scene_error(options, error))?;
+    Ok(())
+}
+
+fn render(
+    options: &Options,
+    description: &SceneDescription<f64>,
+    aovs: Vec<Aov>,
+) -> Result<Frame<f64>, Box<dyn Error>> {
+    let mut scene = description
+        .scene()
+        .map_err(|error| scene_error(options, error))?;
+    scene.seed = options.seed;
+
+    let mut frame: Frame<f64> = Frame::with_camera(
+        description.frame.width,
+        description.frame.height,
+        description.camera(),
+    );
+    frame.sampling = description.sampling;
+    frame.integrator = description.integrator;
+    frame.tone_mapping = description.tone_mapping;
+    frame.aovs = aovs;
+    frame.threads = options.threads.unwrap_or_else(|| {
+        thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(1)
+    });
+    frame.seed = options.seed;
+    let progressive = Progressive {
+        preview_block: if options.progress { 8 } else { 1 },
+        refinement_passes: options.refinement_passes,
     };
+    frame.render_progressive(&scene, &progressive, &AtomicBool::new(false), |progress| {
+        if options.progress {
+            eprintln!(
+                "pass {}/{} done ({:.0}%)",
+                progress.pass,
+                progress.passes,
+                100.0 * progress.fraction()
+            );
+        }
+    });
+    Ok(frame)
+}
+
+fn scene_error(options: &Options, error: SceneError) -> String {
+    match error {
+        SceneError::Io { .. } => error.to_string(),
+        _ => format!("{}: {}", options.scene.display(), error),
+    }
+}
 
-    let mut frame: Frame<f64> = Frame::new(1024, 768, 60.0);
-    frame.render(&scene);
-    frame.save(path).expect("Failed to save image");
+fn create_directory(directory: &Path) -> Result<(), String> {
+    if !directory.as_os_str().is_empty() {
+        create_dir_all(directory)
+            .map_err(|error| format!("failed to create {}: {}", directory.display(), error))?;
+    }
+    Ok(())
+}
+
+fn parse_aov(value: &str) -> Result<(Aov, PathBuf), String> {
+    match value.split_once('=') {
+        Some((name, path)) if !path.is_empty() => Ok((name.trim().parse()?, PathBuf::from(path))),
+        _ => Err(format!("expected `NAME=PATH`, got `{}`", value)),
+    }
+}
+
+fn parse_shutter(value: &str) -> Result<(f64, f64), String> {
+    let times = value
+        .split(',')
+        .map(|time| time.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| error.to_string())?;
+    match times[..] {
+        [open, close] => Ok((open, close)),
+        _ => Err(format!("expected two times `open,close`, got `{}`", value)),
+    }
+}
+
+fn parse_vector(value: &str) -> Result<Vec3D<f64>, String> {
+    let coordinates = value
+        .split(',')
+        .map(|coordinate| coordinate.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| error.to_string())?;
+    match coordinates[..] {
+        [x, y, z] => Ok(Vec3D { x, y, z }),
+        _ => Err(format!(
+            "expected three coordinates `x,y,z`, got `{}`",
+            value
+        )),
+    }
 }
@@ -0,0 +1,103 @@
+use crate::math::ray::Ray;
+use crate::math::vec3d::Vec3D;
+use num_traits::real::Real;
+
+/// Axis-aligned bounding box.
+#[derive(Copy, Clone)]
+pub struct Aabb<T: Real> {
+    pub min: Vec3D<T>,
+    pub max: Vec3D<T>,
+}
+
+impl<T: Real> Aabb<T> {
+    pub fn union(self, other: Aabb<T>) -> Aabb<T> {
+        Aabb {
+            min: Vec3D {
+                x: self.min.x.min(other.min.x),
+                y: self.min.y.min(other.min.y),
+                z: self.min.z.min(other.min.z),
+            },
+            max: Vec3D {
+                x: self.max.x.max(other.max.x),
+                y: self.max.y.max(other.max.y),
+                z: self.max.z.max(other.max.z),
+            },
+        }
+    }
+
+    pub fn center(&self) -> Vec3D<T> {
+        (self.min + self.max) * T::from(0.5).unwrap()
+    }
+
+    /// Index of the axis along which the box is the longest, `0` for X, `1` for Y and `2` for Z.
+    pub fn longest_axis(&self) -> usize {
+        let size = self.max - self.min;
+        if size.x >= size.y && size.x >= size.z {
+            0
+        } else if size.y >= size.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Checks whether the ray enters the box before `max_distance`.
+    pub fn hit(&self, ray: Ray<T>, max_distance: T) -> bool {
+        let mut near = T::zero();
+        let mut far = max_distance;
+        for axis in 0..3 {
+            let origin = axis_value(ray.origin, axis);
+            let direction = axis_value(ray.direction, axis);
+            let (min, max) = (axis_value(self.min, axis), axis_value(self.max, axis));
+            if direction == T::zero() {
+                if origin < min || origin > max {
+                    return false;
+                }
+                continue;
+            }
+            let inverse = T::one() / direction;
+            let (mut t0, mut t1) = ((min - origin) * inverse, (max - origin) * inverse);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            near = near.max(t0);
+            far = far.min(t1);
+            if near > far {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub fn axis_value<T: Real>(vector: Vec3D<T>, axis: usize) -> T {
+    match axis {
+        0 => vector.x,
+        1 => vector.y,
+        _ => vector.z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hit() {
+        let aabb = Aabb {
+            min: Vec3D::from([-1.0, -1.0, -1.0]),
+            max: Vec3D::from([1.0, 1.0, 1.0]),
+        };
+        let ray = |origin: [f64; 3], direction: [f64; 3]| Ray {
+            origin: Vec3D::from(origin),
+            direction: Vec3D::from(direction).normalize(),
+            time: 0.0,
+        };
+        assert!(aabb.hit(ray([0.0, 0.0, 5.0], [0.0, 0.0, -1.0]), f64::MAX));
+        assert!(!aabb.hit(ray([0.0, 0.0, 5.0], [0.0, 0.0, -1.0]), 3.0));
+        assert!(!aabb.hit(ray([0.0, 0.0, 5.0], [0.0, 0.0, 1.0]), f64::MAX));
+        assert!(aabb.hit(ray([0.0, 0.0, 0.0], [1.0, 2.0, 3.0]), f64::MAX));
+        assert!(!aabb.hit(ray([2.0, 0.0, 5.0], [0.0, 0.0, -1.0]), f64::MAX));
+        assert!(aabb.hit(ray([3.0, 3.0, 0.0], [-1.0, -1.0, 0.0]), f64::MAX));
+    }
+}
@@ -1,9 +1,11 @@
 use image::Rgb;
 use num_traits::real::Real;
 use num_traits::{NumCast, ToPrimitive};
+use serde::{Deserialize, Serialize};
 use std::ops;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(from = "[T; 3]", into = "[T; 3]")]
 pub struct Color<T: Real> {
     pub r: T,
     pub g: T,
@@ -27,6 +29,14 @@ impl<T: Real> Color<T> {
         }
     }
 
+    pub fn max(self, other: Color<T>) -> Color<T> {
+        Color {
+            r: self.r.max(other.r),
+            g: self.g.max(other.g),
+            b: self.b.max(other.b),
+        }
+    }
+
     pub fn unit() -> Color<T> {
         Color {
             r: T::one(),
@@ -42,6 +52,18 @@ impl<T: Real> Default for Color<T> {
     }
 }
 
+impl<T: Real> From<[T; 3]> for Color<T> {
+    fn from([r, g, b]: [T; 3]) -> Self {
+        Color { r, g, b }
+    }
+}
+
+impl<T: Real> From<Color<T>> for [T; 3] {
+    fn from(Color { r, g, b }: Color<T>) -> Self {
+        [r, g, b]
+    }
+}
+
 impl<T: Real> From<Color<T>> for Rgb<u8> {
     fn from(color: Color<T>) -> Self {
         let max_value = T::from(255).unwrap();
@@ -77,6 +99,19 @@ impl<T: Real> ops::Mul<T> for Color<T> {
     }
 }
 
+/// Component-wise product, filtering one color by another.
+impl<T: Real> ops::Mul<Self> for Color<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Color {
+            r: self.r * rhs.r,
+            g: self.g * rhs.g,
+            b: self.b * rhs.b,
+        }
+    }
+}
+
 impl<T: Real, R: ToPrimitive> ops::Div<R> for Color<T> {
     type Output = Self;
 
@@ -0,0 +1,222 @@
+use crate::math::vec3d::Vec3D;
+use num_traits::real::Real;
+use std::ops;
+
+/// Row-major 4x4 matrix of an affine transform acting on column vectors.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Matrix4<T: Real> {
+    pub elements: [[T; 4]; 4],
+}
+
+impl<T: Real> Matrix4<T> {
+    pub fn identity() -> Matrix4<T> {
+        let mut elements = [[T::zero(); 4]; 4];
+        for (index, row) in elements.iter_mut().enumerate() {
+            row[index] = T::one();
+        }
+        Matrix4 { elements }
+    }
+
+    pub fn translation(offset: Vec3D<T>) -> Matrix4<T> {
+        let mut matrix = Matrix4::identity();
+        matrix.elements[0][3] = offset.x;
+        matrix.elements[1][3] = offset.y;
+        matrix.elements[2][3] = offset.z;
+        matrix
+    }
+
+    pub fn scaling(factors: Vec3D<T>) -> Matrix4<T> {
+        let mut matrix = Matrix4::identity();
+        matrix.elements[0][0] = factors.x;
+        matrix.elements[1][1] = factors.y;
+        matrix.elements[2][2] = factors.z;
+        matrix
+    }
+
+    /// Counterclockwise rotation by `angle` radians when looking down the X axis.
+    pub fn rotation_x(angle: T) -> Matrix4<T> {
+        let (sin, cos) = angle.sin_cos();
+        let mut matrix = Matrix4::identity();
+        matrix.elements[1][1] = cos;
+        matrix.elements[1][2] = -sin;
+        matrix.elements[2][1] = sin;
+        matrix.elements[2][2] = cos;
+        matrix
+    }
+
+    /// Counterclockwise rotation by `angle` radians when looking down the Y axis.
+    pub fn rotation_y(angle: T) -> Matrix4<T> {
+        let (sin, cos) = angle.sin_cos();
+        let mut matrix = Matrix4::identity();
+        matrix.elements[0][0] = cos;
+        matrix.elements[0][2] = sin;
+        matrix.elements[2][0] = -sin;
+        matrix.elements[2][2] = cos;
+        matrix
+    }
+
+    /// Counterclockwise rotation by `angle` radians when looking down the Z axis.
+    pub fn rotation_z(angle: T) -> Matrix4<T> {
+        let (sin, cos) = angle.sin_cos();
+        let mut matrix = Matrix4::identity();
+        matrix.elements[0][0] = cos;
+        matrix.elements[0][1] = -sin;
+        matrix.elements[1][0] = sin;
+        matrix.elements[1][1] = cos;
+        matrix
+    }
+
+    pub fn transpose(&self) -> Matrix4<T> {
+        let mut elements = [[T::zero(); 4]; 4];
+        for (row_index, row) in elements.iter_mut().enumerate() {
+            for (column_index, element) in row.iter_mut().enumerate() {
+                *element = self.elements[column_index][row_index];
+            }
+        }
+        Matrix4 { elements }
+    }
+
+    /// Inverse by Gauss-Jordan elimination, `None` for singular matrices.
+    pub fn inverse(&self) -> Option<Matrix4<T>> {
+        let mut matrix = self.elements;
+        let mut inverse = Matrix4::identity().elements;
+        for column in 0..4 {
+            let pivot = (column..4)
+                .max_by(|first, second| {
+                    matrix[*first][column]
+                        .abs()
+                        .partial_cmp(&matrix[*second][column].abs())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap();
+            if matrix[pivot][column].abs() <= T::epsilon() {
+                return None;
+            }
+            matrix.swap(column, pivot);
+            inverse.swap(column, pivot);
+
+            let scale = T::one() / matrix[column][column];
+            for index in 0..4 {
+                matrix[column][index] = matrix[column][index] * scale;
+                inverse[column][index] = inverse[column][index] * scale;
+            }
+            for row in 0..4 {
+                let factor = matrix[row][column];
+                if row == column || factor == T::zero() {
+                    continue;
+                }
+                for index in 0..4 {
+                    matrix[row][index] = matrix[row][index] - factor * matrix[column][index];
+                    inverse[row][index] = inverse[row][index] - factor * inverse[column][index];
+                }
+            }
+        }
+        Some(Matrix4 { elements: inverse })
+    }
+
+    pub fn transform_point(&self, point: Vec3D<T>) -> Vec3D<T> {
+        let row = |index: usize| {
+            let [a, b, c, d] = self.elements[index];
+            a * point.x + b * point.y + c * point.z + d
+        };
+        Vec3D {
+            x: row(0),
+            y: row(1),
+            z: row(2),
+        }
+    }
+
+    /// Transforms a direction, ignoring the translation.
+    pub fn transform_vector(&self, vector: Vec3D<T>) -> Vec3D<T> {
+        let row = |index: usize| {
+            let [a, b, c, _] = self.elements[index];
+            a * vector.x + b * vector.y + c * vector.z
+        };
+        Vec3D {
+            x: row(0),
+            y: row(1),
+            z: row(2),
+        }
+    }
+}
+
+impl<T: Real> ops::Mul<Self> for Matrix4<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut elements = [[T::zero(); 4]; 4];
+        for (row_index, row) in elements.iter_mut().enumerate() {
+            for (column_index, element) in row.iter_mut().enumerate() {
+                *element = (0..4).fold(T::zero(), |sum, index| {
+                    sum + self.elements[row_index][index] * rhs.elements[index][column_index]
+                });
+            }
+        }
+        Matrix4 { elements }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn assert_close(first: Vec3D<f64>, second: [f64; 3]) {
+        assert!(
+            (first - Vec3D::from(second)).norm() < 1e-12,
+            "({}, {}, {})",
+            first.x,
+            first.y,
+            first.z
+        );
+    }
+
+    #[test]
+    fn test_transforms() {
+        let point = Vec3D::from([1.0, 2.0, 3.0]);
+        let translation = Matrix4::translation(Vec3D::from([1.0, -1.0, 0.5]));
+        assert_close(translation.transform_point(point), [2.0, 1.0, 3.5]);
+        assert_close(translation.transform_vector(point), [1.0, 2.0, 3.0]);
+        let scaling = Matrix4::scaling(Vec3D::from([2.0, 3.0, -1.0]));
+        assert_close(scaling.transform_point(point), [2.0, 6.0, -3.0]);
+
+        let x = Vec3D::from([1.0, 0.0, 0.0]);
+        let y = Vec3D::from([0.0, 1.0, 0.0]);
+        assert_close(
+            Matrix4::rotation_x(FRAC_PI_2).transform_vector(y),
+            [0.0, 0.0, 1.0],
+        );
+        assert_close(
+            Matrix4::rotation_y(FRAC_PI_2).transform_vector(x),
+            [0.0, 0.0, -1.0],
+        );
+        assert_close(
+            Matrix4::rotation_z(FRAC_PI_2).transform_vector(x),
+            [0.0, 1.0, 0.0],
+        );
+
+        // The rightmost transform is applied first.
+        let combined = translation * Matrix4::rotation_z(FRAC_PI_2) * scaling;
+        assert_close(combined.transform_point(point), [-5.0, 1.0, -2.5]);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let matrix = Matrix4::translation(Vec3D::from([1.0, -2.0, 3.0]))
+            * Matrix4::rotation_y(0.7)
+            * Matrix4::rotation_x(-1.3)
+            * Matrix4::scaling(Vec3D::from([2.0, 0.5, 4.0]));
+        let inverse = matrix.inverse().unwrap();
+        let product = matrix * inverse;
+        for row in 0..4 {
+            for column in 0..4 {
+                let expected = if row == column { 1.0 } else { 0.0 };
+                assert!((product.elements[row][column] - expected).abs() < 1e-12);
+            }
+        }
+        assert_eq!(matrix.transpose().transpose(), matrix);
+        assert!(Matrix4::scaling(Vec3D::from([1.0, 0.0, 1.0]))
+            .inverse()
+            .is_none());
+    }
+}
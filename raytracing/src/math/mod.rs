@@ -1,6 +1,9 @@
 use num_traits::real::Real;
 
+pub mod aabb;
 pub mod color;
+pub mod matrix;
+pub mod polynomial;
 pub mod ray;
 pub mod shape;
 pub mod vec3d;
@@ -0,0 +1,147 @@
+use num_traits::real::Real;
+
+/// Maximum number of refinement steps for a single root.
+const MAX_ITERATIONS: usize = 100;
+
+/// Real roots of the polynomial `coefficients[0] + coefficients[1] * x + ...` in ascending order.
+///
+/// Linear and quadratic polynomials are solved directly. Higher degrees are split into
+/// monotonic intervals by the roots of the derivative, and each interval containing a sign
+/// change is refined by Newton steps kept inside the bracket by bisection.
+pub fn roots<T: Real>(coefficients: &[T]) -> Vec<T> {
+    let degree = match coefficients.iter().rposition(|c| *c != T::zero()) {
+        Some(degree) => degree,
+        None => return Vec::new(),
+    };
+    let coefficients = &coefficients[..=degree];
+    match degree {
+        0 => Vec::new(),
+        1 => vec![-coefficients[0] / coefficients[1]],
+        2 => quadratic_roots(coefficients[2], coefficients[1], coefficients[0]),
+        _ => {
+            let leading = coefficients[degree];
+            let bound = coefficients[..degree]
+                .iter()
+                .map(|c| (*c / leading).abs())
+                .fold(T::zero(), T::max)
+                + T::one();
+            let derivative: Vec<T> = coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(power, c)| *c * T::from(power).unwrap())
+                .collect();
+
+            let mut bounds = vec![-bound];
+            bounds.extend(roots(&derivative).into_iter().filter(|x| x.abs() < bound));
+            bounds.push(bound);
+
+            let mut result = Vec::new();
+            for interval in bounds.windows(2) {
+                if let Some(root) =
+                    bracketed_root(coefficients, &derivative, interval[0], interval[1])
+                {
+                    if result.last().is_none_or(|last| root != *last) {
+                        result.push(root);
+                    }
+                }
+            }
+            result
+        }
+    }
+}
+
+/// Roots of `a * x^2 + b * x + c` avoiding the cancellation of the textbook formula.
+fn quadratic_roots<T: Real>(a: T, b: T, c: T) -> Vec<T> {
+    let two = T::from(2.0).unwrap();
+    let discriminant = b * b - T::from(4.0).unwrap() * a * c;
+    if discriminant < T::zero() {
+        return Vec::new();
+    }
+    if discriminant == T::zero() {
+        return vec![-b / (two * a)];
+    }
+    let q = -(b + b.signum() * discriminant.sqrt()) / two;
+    let (x0, x1) = (q / a, c / q);
+    if x0 < x1 {
+        vec![x0, x1]
+    } else {
+        vec![x1, x0]
+    }
+}
+
+fn evaluate<T: Real>(coefficients: &[T], x: T) -> T {
+    coefficients
+        .iter()
+        .rev()
+        .fold(T::zero(), |value, c| value * x + *c)
+}
+
+/// Root of a polynomial monotonic between `low` and `high`, if its sign changes there.
+fn bracketed_root<T: Real>(coefficients: &[T], derivative: &[T], low: T, high: T) -> Option<T> {
+    let (mut low, mut high) = (low, high);
+    let low_value = evaluate(coefficients, low);
+    let high_value = evaluate(coefficients, high);
+    if low_value == T::zero() {
+        return Some(low);
+    }
+    if high_value == T::zero() {
+        return Some(high);
+    }
+    if low_value.signum() == high_value.signum() {
+        return None;
+    }
+    let increasing = high_value > T::zero();
+    let two = T::from(2.0).unwrap();
+    let mut x = (low + high) / two;
+    for _ in 0..MAX_ITERATIONS {
+        let value = evaluate(coefficients, x);
+        if value == T::zero() {
+            return Some(x);
+        }
+        if (value > T::zero()) == increasing {
+            high = x;
+        } else {
+            low = x;
+        }
+        let slope = evaluate(derivative, x);
+        let newton = x - value / slope;
+        let next = if slope != T::zero() && newton > low && newton < high {
+            newton
+        } else {
+            (low + high) / two
+        };
+        if next == x {
+            break;
+        }
+        x = next;
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(coefficients: &[f64], expected: &[f64]) {
+        let found = roots(coefficients);
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        for (root, expected) in found.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-9, "{:?}", found);
+        }
+    }
+
+    #[test]
+    fn test_roots() {
+        assert_roots(&[1.0], &[]);
+        assert_roots(&[-2.0, 4.0], &[0.5]);
+        assert_roots(&[2.0, -3.0, 1.0], &[1.0, 2.0]);
+        assert_roots(&[1.0, 0.0, 1.0], &[]);
+        assert_roots(&[1.0, -2.0, 1.0], &[1.0]);
+        // (x - 1)(x + 2)(x - 3) = x^3 - 2x^2 - 5x + 6
+        assert_roots(&[6.0, -5.0, -2.0, 1.0], &[-2.0, 1.0, 3.0]);
+        // (x^2 - 1)(x^2 - 4)
+        assert_roots(&[4.0, 0.0, -5.0, 0.0, 1.0], &[-2.0, -1.0, 1.0, 2.0]);
+        assert_roots(&[0.0, 0.0, 0.0, 2.0], &[0.0]);
+    }
+}
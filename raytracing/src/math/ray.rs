@@ -5,6 +5,9 @@ use num_traits::real::Real;
 pub struct Ray<T: Real> {
     pub origin: Vec3D<T>,
     pub direction: Vec3D<T>,
+    /// Time within the frame the ray is traced at, from `0` when the shutter opens to
+    /// `1` when it closes at the latest. Moving objects are placed accordingly.
+    pub time: T,
 }
 
 impl<T: Real> Ray<T> {
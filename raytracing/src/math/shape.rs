@@ -1,16 +1,36 @@
+use crate::math::aabb::{axis_value, Aabb};
+use crate::math::polynomial::roots;
 use crate::math::ray::Ray;
 use crate::math::threshold;
 use crate::math::vec3d::Vec3D;
 use num_traits::real::Real;
+use std::cmp::Ordering;
 
 pub trait Intersect<T: Real> {
     fn intersect(&self, ray: Ray<T>) -> Option<T>;
     fn normal(&self, point: Vec3D<T>) -> Vec3D<T>;
+    /// Texture coordinates of a point on the surface, mostly within `[0, 1]`.
+    fn uv(&self, point: Vec3D<T>) -> (T, T);
+    /// Frame of the surface at a point with its axes along growing texture coordinates.
+    fn tangent_frame(&self, point: Vec3D<T>) -> TangentFrame<T>;
+    /// Sorted and disjoint intervals of distances along the whole line of the ray,
+    /// behind its origin too, for which the ray is inside the shape. Flat shapes
+    /// enclose nothing, and unbounded intervals end at `T::min_value()` or `T::max_value()`.
+    fn intervals(&self, ray: Ray<T>) -> Vec<(T, T)>;
+    /// Box containing the whole shape, `None` for unbounded shapes.
+    fn bounding_box(&self) -> Option<Aabb<T>>;
 }
 
 pub enum Shape<T: Real> {
     Sphere(Sphere<T>),
     Plane(Plane<T>),
+    Triangle(Triangle<T>),
+    Rectangle(Rectangle<T>),
+    Cuboid(Cuboid<T>),
+    Disk(Disk<T>),
+    Cylinder(Cylinder<T>),
+    Cone(Cone<T>),
+    Torus(Torus<T>),
 }
 
 impl<T: Real> Intersect<T> for Shape<T> {
@@ -18,6 +38,13 @@ impl<T: Real> Intersect<T> for Shape<T> {
         match self {
             Shape::Sphere(sphere) => sphere.intersect(ray),
             Shape::Plane(plane) => plane.intersect(ray),
+            Shape::Triangle(triangle) => triangle.intersect(ray),
+            Shape::Rectangle(rectangle) => rectangle.intersect(ray),
+            Shape::Cuboid(cuboid) => cuboid.intersect(ray),
+            Shape::Disk(disk) => disk.intersect(ray),
+            Shape::Cylinder(cylinder) => cylinder.intersect(ray),
+            Shape::Cone(cone) => cone.intersect(ray),
+            Shape::Torus(torus) => torus.intersect(ray),
         }
     }
 
@@ -25,10 +52,113 @@ impl<T: Real> Intersect<T> for Shape<T> {
         match self {
             Shape::Sphere(sphere) => sphere.normal(point),
             Shape::Plane(plane) => plane.normal(point),
+            Shape::Triangle(triangle) => triangle.normal(point),
+            Shape::Rectangle(rectangle) => rectangle.normal(point),
+            Shape::Cuboid(cuboid) => cuboid.normal(point),
+            Shape::Disk(disk) => disk.normal(point),
+            Shape::Cylinder(cylinder) => cylinder.normal(point),
+            Shape::Cone(cone) => cone.normal(point),
+            Shape::Torus(torus) => torus.normal(point),
+        }
+    }
+
+    fn uv(&self, point: Vec3D<T>) -> (T, T) {
+        match self {
+            Shape::Sphere(sphere) => sphere.uv(point),
+            Shape::Plane(plane) => plane.uv(point),
+            Shape::Triangle(triangle) => triangle.uv(point),
+            Shape::Rectangle(rectangle) => rectangle.uv(point),
+            Shape::Cuboid(cuboid) => cuboid.uv(point),
+            Shape::Disk(disk) => disk.uv(point),
+            Shape::Cylinder(cylinder) => cylinder.uv(point),
+            Shape::Cone(cone) => cone.uv(point),
+            Shape::Torus(torus) => torus.uv(point),
+        }
+    }
+
+    fn tangent_frame(&self, point: Vec3D<T>) -> TangentFrame<T> {
+        match self {
+            Shape::Sphere(sphere) => sphere.tangent_frame(point),
+            Shape::Plane(plane) => plane.tangent_frame(point),
+            Shape::Triangle(triangle) => triangle.tangent_frame(point),
+            Shape::Rectangle(rectangle) => rectangle.tangent_frame(point),
+            Shape::Cuboid(cuboid) => cuboid.tangent_frame(point),
+            Shape::Disk(disk) => disk.tangent_frame(point),
+            Shape::Cylinder(cylinder) => cylinder.tangent_frame(point),
+            Shape::Cone(cone) => cone.tangent_frame(point),
+            Shape::Torus(torus) => torus.tangent_frame(point),
+        }
+    }
+
+    fn intervals(&self, ray: Ray<T>) -> Vec<(T, T)> {
+        match self {
+            Shape::Sphere(sphere) => sphere.intervals(ray),
+            Shape::Plane(plane) => plane.intervals(ray),
+            Shape::Triangle(triangle) => triangle.intervals(ray),
+            Shape::Rectangle(rectangle) => rectangle.intervals(ray),
+            Shape::Cuboid(cuboid) => cuboid.intervals(ray),
+            Shape::Disk(disk) => disk.intervals(ray),
+            Shape::Cylinder(cylinder) => cylinder.intervals(ray),
+            Shape::Cone(cone) => cone.intervals(ray),
+            Shape::Torus(torus) => torus.intervals(ray),
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        match self {
+            Shape::Sphere(sphere) => sphere.bounding_box(),
+            Shape::Plane(plane) => plane.bounding_box(),
+            Shape::Triangle(triangle) => triangle.bounding_box(),
+            Shape::Rectangle(rectangle) => rectangle.bounding_box(),
+            Shape::Cuboid(cuboid) => cuboid.bounding_box(),
+            Shape::Disk(disk) => disk.bounding_box(),
+            Shape::Cylinder(cylinder) => cylinder.bounding_box(),
+            Shape::Cone(cone) => cone.bounding_box(),
+            Shape::Torus(torus) => torus.bounding_box(),
         }
     }
 }
 
+/// Orthonormal frame of a surface point, with the tangent along growing `u` and the
+/// bitangent along growing `v` as far as they are perpendicular to the normal.
+#[derive(Copy, Clone)]
+pub struct TangentFrame<T: Real> {
+    pub tangent: Vec3D<T>,
+    pub bitangent: Vec3D<T>,
+    pub normal: Vec3D<T>,
+}
+
+impl<T: Real> TangentFrame<T> {
+    /// Frame of the unit normal from the directions of growing `u` and `v`, which are
+    /// made perpendicular to the normal and to each other. Directions degenerate at the
+    /// point, such as at the poles of a sphere, are replaced by arbitrary ones.
+    pub fn new(normal: Vec3D<T>, u_direction: Vec3D<T>, v_direction: Vec3D<T>) -> TangentFrame<T> {
+        let tangent = u_direction - normal * (u_direction * normal);
+        let tangent = if tangent.norm() > T::epsilon() {
+            tangent.normalize()
+        } else {
+            Basis::new(normal).u
+        };
+        let bitangent = v_direction - normal * (v_direction * normal);
+        let bitangent = bitangent - tangent * (bitangent * tangent);
+        let bitangent = if bitangent.norm() > T::epsilon() {
+            bitangent.normalize()
+        } else {
+            normal.cross(tangent)
+        };
+        TangentFrame {
+            tangent,
+            bitangent,
+            normal,
+        }
+    }
+
+    /// Direction given by its components along the tangent, the bitangent and the normal.
+    pub fn to_world(&self, local: Vec3D<T>) -> Vec3D<T> {
+        self.tangent * local.x + self.bitangent * local.y + self.normal * local.z
+    }
+}
+
 pub struct Sphere<T: Real> {
     pub center: Vec3D<T>,
     pub radius: T,
@@ -36,26 +166,55 @@ pub struct Sphere<T: Real> {
 
 impl<T: Real> Intersect<T> for Sphere<T> {
     fn intersect(&self, ray: Ray<T>) -> Option<T> {
+        nearest_boundary(&self.intervals(ray))
+    }
+
+    fn intervals(&self, ray: Ray<T>) -> Vec<(T, T)> {
         let center_direction = self.center - ray.origin;
         let center_projection = center_direction * ray.direction;
         let distance_square =
             center_direction * center_direction - center_projection * center_projection;
         if distance_square > self.radius * self.radius {
-            return None;
+            return Vec::new();
         }
         let intersection_distance = (self.radius * self.radius - distance_square).sqrt();
-        let t0 = center_projection - intersection_distance;
-        let t1 = center_projection + intersection_distance;
-        if t1 < threshold() {
-            None
-        } else {
-            Some(if t0 < threshold() { t1 } else { t0 })
-        }
+        vec![(
+            center_projection - intersection_distance,
+            center_projection + intersection_distance,
+        )]
     }
 
     fn normal(&self, point: Vec3D<T>) -> Vec3D<T> {
         (point - self.center).normalize()
     }
+
+    /// Longitude and latitude, with `v` growing along the Y axis.
+    fn uv(&self, point: Vec3D<T>) -> (T, T) {
+        let direction = (point - self.center).normalize();
+        (
+            longitude(direction.z, direction.x),
+            T::from(0.5).unwrap() + direction.y.max(-T::one()).min(T::one()).asin() / pi(),
+        )
+    }
+
+    fn tangent_frame(&self, point: Vec3D<T>) -> TangentFrame<T> {
+        let normal = self.normal(point);
+        let up = Vec3D::from([T::zero(), T::one(), T::zero()]);
+        let around = Vec3D::from([-normal.z, T::zero(), normal.x]);
+        TangentFrame::new(normal, around, up)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        let extent = Vec3D {
+            x: self.radius,
+            y: self.radius,
+            z: self.radius,
+        };
+        Some(Aabb {
+            min: self.center - extent,
+            max: self.center + extent,
+        })
+    }
 }
 
 pub struct Plane<T: Real> {
@@ -80,4 +239,1244 @@ impl<T: Real> Intersect<T> for Plane<T> {
     fn normal(&self, _point: Vec3D<T>) -> Vec3D<T> {
         self.normal
     }
+
+    /// Distances from the origin along two axes lying in the plane.
+    fn uv(&self, point: Vec3D<T>) -> (T, T) {
+        let basis = Basis::new(self.normal);
+        let offset = point - self.origin;
+        (offset * basis.u, offset * basis.v)
+    }
+
+    fn tangent_frame(&self, _point: Vec3D<T>) -> TangentFrame<T> {
+        let basis = Basis::new(self.normal);
+        TangentFrame::new(self.normal, basis.u, basis.v)
+    }
+
+    /// The inside of a plane is the half-space behind its normal.
+    fn intervals(&self, ray: Ray<T>) -> Vec<(T, T)> {
+        let speed = ray.direction * self.normal;
+        let height = (ray.origin - self.origin) * self.normal;
+        if speed == T::zero() {
+            return if height <= T::zero() {
+                vec![(T::min_value(), T::max_value())]
+            } else {
+                Vec::new()
+            };
+        }
+        let t = -height / speed;
+        if speed > T::zero() {
+            vec![(T::min_value(), t)]
+        } else {
+            vec![(t, T::max_value())]
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        None
+    }
+}
+
+pub struct Triangle<T: Real> {
+    pub vertices: [Vec3D<T>; 3],
+    /// Vertex normals interpolated across the triangle for smooth shading,
+    /// the normal of the triangle plane is used without them.
+    pub normals: Option<[Vec3D<T>; 3]>,
+    /// Texture coordinates of the vertices.
+    pub uvs: Option<[(T, T); 3]>,
+}
+
+impl<T: Real> Triangle<T> {
+    /// Barycentric coordinates of a point lying in the triangle plane.
+    pub fn barycentric(&self, point: Vec3D<T>) -> [T; 3] {
+        let [a, b, c] = self.vertices;
+        let (ab, ac, ap) = (b - a, c - a, point - a);
+        let (d00, d01, d11) = (ab * ab, ab * ac, ac * ac);
+        let (d20, d21) = (ap * ab, ap * ac);
+        let denominator = d00 * d11 - d01 * d01;
+        let v = (d11 * d20 - d01 * d21) / denominator;
+        let w = (d00 * d21 - d01 * d20) / denominator;
+        [T::one() - v - w, v, w]
+    }
+}
+
+impl<T: Real> Intersect<T> for Triangle<T> {
+    fn intersect(&self, ray: Ray<T>) -> Option<T> {
+        let [a, b, c] = self.vertices;
+        let (ab, ac) = (b - a, c - a);
+        let p = ray.direction.cross(ac);
+        let determinant = ab * p;
+        if determinant.abs() < T::epsilon() {
+            return None;
+        }
+        let inverse = T::one() / determinant;
+        let s = ray.origin - a;
+        let u = s * p * inverse;
+        if u < T::zero() || u > T::one() {
+            return None;
+        }
+        let q = s.cross(ab);
+        let v = ray.direction * q * inverse;
+        if v < T::zero() || u + v > T::one() {
+            return None;
+        }
+        let t = ac * q * inverse;
+        if t < threshold() {
+            None
+        } else {
+            Some(t)
+        }
+    }
+
+    fn normal(&self, point: Vec3D<T>) -> Vec3D<T> {
+        match self.normals {
+            Some([na, nb, nc]) => {
+                let [u, v, w] = self.barycentric(point);
+                (na * u + nb * v + nc * w).normalize()
+            }
+            None => {
+                let [a, b, c] = self.vertices;
+                (b - a).cross(c - a).normalize()
+            }
+        }
+    }
+
+    /// Interpolated vertex texture coordinates, or the barycentric coordinates of
+    /// the second and the third vertex without them.
+    fn uv(&self, point: Vec3D<T>) -> (T, T) {
+        let [a, b, c] = self.barycentric(point);
+        match self.uvs {
+            Some([(ua, va), (ub, vb), (uc, vc)]) => {
+                (ua * a + ub * b + uc * c, va * a + vb * b + vc * c)
+            }
+            None => (b, c),
+        }
+    }
+
+    fn tangent_frame(&self, point: Vec3D<T>) -> TangentFrame<T> {
+        let [a, b, c] = self.vertices;
+        let (ab, ac) = (b - a, c - a);
+        let (u_direction, v_direction) = match self.uvs {
+            Some([(ua, va), (ub, vb), (uc, vc)]) => {
+                let (du1, dv1, du2, dv2) = (ub - ua, vb - va, uc - ua, vc - va);
+                let determinant = du1 * dv2 - du2 * dv1;
+                if determinant == T::zero() {
+                    (ab, ac)
+                } else {
+                    (
+                        (ab * dv2 - ac * dv1) * (T::one() / determinant),
+                        (ac * du1 - ab * du2) * (T::one() / determinant),
+                    )
+                }
+            }
+            None => (ab, ac),
+        };
+        TangentFrame::new(self.normal(point), u_direction, v_direction)
+    }
+
+    fn intervals(&self, _ray: Ray<T>) -> Vec<(T, T)> {
+        Vec::new()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        let [a, b, c] = self.vertices;
+        let point = |vertex| Aabb {
+            min: vertex,
+            max: vertex,
+        };
+        Some(point(a).union(point(b)).union(point(c)))
+    }
+}
+
+/// Parallelogram spanned by two edges from a corner, facing along `u_edge` x `v_edge`.
+pub struct Rectangle<T: Real> {
+    pub corner: Vec3D<T>,
+    pub u_edge: Vec3D<T>,
+    pub v_edge: Vec3D<T>,
+}
+
+impl<T: Real> Rectangle<T> {
+    /// Coordinates of a point of the rectangle plane along the edges.
+    fn coordinates(&self, point: Vec3D<T>) -> (T, T) {
+        let normal = self.u_edge.cross(self.v_edge);
+        let area = normal * normal;
+        let offset = point - self.corner;
+        (
+            offset.cross(self.v_edge) * normal / area,
+            self.u_edge.cross(offset) * normal / area,
+        )
+    }
+}
+
+impl<T: Real> Intersect<T> for Rectangle<T> {
+    fn intersect(&self, ray: Ray<T>) -> Option<T> {
+        let plane = Plane {
+            origin: self.corner,
+            normal: self.normal(self.corner),
+        };
+        let t = plane.intersect(ray)?;
+        let (u, v) = self.coordinates(ray.point_at(t));
+        let inside = |coordinate: T| coordinate >= T::zero() && coordinate <= T::one();
+        if inside(u) && inside(v) {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    fn normal(&self, _point: Vec3D<T>) -> Vec3D<T> {
+        self.u_edge.cross(self.v_edge).normalize()
+    }
+
+    fn uv(&self, point: Vec3D<T>) -> (T, T) {
+        self.coordinates(point)
+    }
+
+    fn tangent_frame(&self, point: Vec3D<T>) -> TangentFrame<T> {
+        TangentFrame::new(self.normal(point), self.u_edge, self.v_edge)
+    }
+
+    fn intervals(&self, _ray: Ray<T>) -> Vec<(T, T)> {
+        Vec::new()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        let point = |point| Aabb {
+            min: point,
+            max: point,
+        };
+        Some(
+            point(self.corner)
+                .union(point(self.corner + self.u_edge))
+                .union(point(self.corner + self.v_edge))
+                .union(point(self.corner + self.u_edge + self.v_edge)),
+        )
+    }
+}
+
+/// Axis-aligned box spanning from `min` to `max`.
+pub struct Cuboid<T: Real> {
+    pub min: Vec3D<T>,
+    pub max: Vec3D<T>,
+}
+
+impl<T: Real> Intersect<T> for Cuboid<T> {
+    fn intersect(&self, ray: Ray<T>) -> Option<T> {
+        nearest_boundary(&self.intervals(ray))
+    }
+
+    fn intervals(&self, ray: Ray<T>) -> Vec<(T, T)> {
+        let mut near = T::min_value();
+        let mut far = T::max_value();
+        for axis in 0..3 {
+            let origin = axis_value(ray.origin, axis);
+            let direction = axis_value(ray.direction, axis);
+            let (min, max) = (axis_value(self.min, axis), axis_value(self.max, axis));
+            if direction == T::zero() {
+                if origin < min || origin > max {
+                    return Vec::new();
+                }
+                continue;
+            }
+            let (t0, t1) = ((min - origin) / direction, (max - origin) / direction);
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+        }
+        if near > far {
+            Vec::new()
+        } else {
+            vec![(near, far)]
+        }
+    }
+
+    fn normal(&self, point: Vec3D<T>) -> Vec3D<T> {
+        let center = (self.min + self.max) * T::from(0.5).unwrap();
+        let half_size = (self.max - self.min) * T::from(0.5).unwrap();
+        let offset = point - center;
+        let relative = |axis| axis_value(offset, axis) / axis_value(half_size, axis);
+        let axis = (0..3)
+            .max_by(|first, second| {
+                relative(*first)
+                    .abs()
+                    .partial_cmp(&relative(*second).abs())
+                    .unwrap_or(Ordering::Equal)
+            })
+            .unwrap();
+        let mut normal = [T::zero(); 3];
+        normal[axis] = relative(axis).signum();
+        Vec3D::from(normal)
+    }
+
+    /// Position within the hit face along its two axes, in the X, Y, Z order.
+    fn uv(&self, point: Vec3D<T>) -> (T, T) {
+        let normal = self.normal(point);
+        let face_axis = (0..3)
+            .find(|axis| axis_value(normal, *axis) != T::zero())
+            .unwrap_or(1);
+        let mut axes = (0..3).filter(|axis| *axis != face_axis).map(|axis| {
+            let min = axis_value(self.min, axis);
+            let size = axis_value(self.max, axis) - min;
+            if size == T::zero() {
+                T::zero()
+            } else {
+                (axis_value(point, axis) - min) / size
+            }
+        });
+        (axes.next().unwrap(), axes.next().unwrap())
+    }
+
+    fn tangent_frame(&self, point: Vec3D<T>) -> TangentFrame<T> {
+        let normal = self.normal(point);
+        let face_axis = (0..3)
+            .find(|axis| axis_value(normal, *axis) != T::zero())
+            .unwrap_or(1);
+        let mut axes = (0..3).filter(|axis| *axis != face_axis).map(|axis| {
+            let mut direction = [T::zero(); 3];
+            direction[axis] = T::one();
+            Vec3D::from(direction)
+        });
+        TangentFrame::new(normal, axes.next().unwrap(), axes.next().unwrap())
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        Some(Aabb {
+            min: self.min,
+            max: self.max,
+        })
+    }
+}
+
+/// Flat disk facing along its unit `normal`.
+pub struct Disk<T: Real> {
+    pub center: Vec3D<T>,
+    pub normal: Vec3D<T>,
+    pub radius: T,
+}
+
+impl<T: Real> Intersect<T> for Disk<T> {
+    fn intersect(&self, ray: Ray<T>) -> Option<T> {
+        let plane = Plane {
+            origin: self.center,
+            normal: self.normal,
+        };
+        let t = plane.intersect(ray)?;
+        let offset = ray.point_at(t) - self.center;
+        if offset * offset > self.radius * self.radius {
+            None
+        } else {
+            Some(t)
+        }
+    }
+
+    fn normal(&self, _point: Vec3D<T>) -> Vec3D<T> {
+        self.normal
+    }
+
+    /// Angle around the center and the distance from it relative to the radius.
+    fn uv(&self, point: Vec3D<T>) -> (T, T) {
+        let local = Basis::new(self.normal).to_local(point - self.center);
+        (longitude(local.z, local.x), local.norm() / self.radius)
+    }
+
+    fn tangent_frame(&self, point: Vec3D<T>) -> TangentFrame<T> {
+        let basis = Basis::new(self.normal);
+        let local = basis.to_local(point - self.center);
+        let outward = basis.u * local.x + basis.v * local.z;
+        TangentFrame::new(self.normal, around(&basis, local), outward)
+    }
+
+    fn intervals(&self, _ray: Ray<T>) -> Vec<(T, T)> {
+        Vec::new()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        Some(disk_bounds(self.center, self.normal, self.radius))
+    }
+}
+
+/// Cylinder closed by disks at `base` and at `height` along the unit `axis`.
+pub struct Cylinder<T: Real> {
+    pub base: Vec3D<T>,
+    pub axis: Vec3D<T>,
+    pub radius: T,
+    pub height: T,
+}
+
+impl<T: Real> Cylinder<T> {
+    /// Distances of all the surface crossings along the line of the ray.
+    fn crossings(&self, ray: Ray<T>) -> Vec<T> {
+        let basis = Basis::new(self.axis);
+        let origin = basis.to_local(ray.origin - self.base);
+        let direction = basis.to_local(ray.direction);
+        let radius_square = self.radius * self.radius;
+        let at = |t: T| origin + direction * t;
+
+        let mut distances = roots(&[
+            origin.x * origin.x + origin.z * origin.z - radius_square,
+            T::from(2.0).unwrap() * (origin.x * direction.x + origin.z * direction.z),
+            direction.x * direction.x + direction.z * direction.z,
+        ]);
+        distances.retain(|t| {
+            let y = at(*t).y;
+            y >= T::zero() && y <= self.height
+        });
+        if direction.y != T::zero() {
+            for cap in [T::zero(), self.height] {
+                let t = (cap - origin.y) / direction.y;
+                let point = at(t);
+                if point.x * point.x + point.z * point.z <= radius_square {
+                    distances.push(t);
+                }
+            }
+        }
+        distances
+    }
+}
+
+impl<T: Real> Intersect<T> for Cylinder<T> {
+    fn intersect(&self, ray: Ray<T>) -> Option<T> {
+        nearest(self.crossings(ray))
+    }
+
+    fn intervals(&self, ray: Ray<T>) -> Vec<(T, T)> {
+        convex_interval(self.crossings(ray))
+    }
+
+    fn normal(&self, point: Vec3D<T>) -> Vec3D<T> {
+        let basis = Basis::new(self.axis);
+        let local = basis.to_local(point - self.base);
+        let radial = (local.x * local.x + local.z * local.z).sqrt();
+        let side_distance = (radial - self.radius).abs();
+        let top_distance = (self.height - local.y).abs();
+        if local.y.abs() < side_distance && local.y.abs() <= top_distance {
+            -self.axis
+        } else if top_distance < side_distance {
+            self.axis
+        } else {
+            (basis.u * local.x + basis.v * local.z).normalize()
+        }
+    }
+
+    /// Angle around the axis and the height relative to the full height.
+    fn uv(&self, point: Vec3D<T>) -> (T, T) {
+        let local = Basis::new(self.axis).to_local(point - self.base);
+        (longitude(local.z, local.x), local.y / self.height)
+    }
+
+    fn tangent_frame(&self, point: Vec3D<T>) -> TangentFrame<T> {
+        let basis = Basis::new(self.axis);
+        let local = basis.to_local(point - self.base);
+        TangentFrame::new(self.normal(point), around(&basis, local), self.axis)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        let top = self.base + self.axis * self.height;
+        Some(
+            disk_bounds(self.base, self.axis, self.radius).union(disk_bounds(
+                top,
+                self.axis,
+                self.radius,
+            )),
+        )
+    }
+}
+
+/// Cone closed by a disk of `radius` at `base`, with its apex at `height` along the unit `axis`.
+pub struct Cone<T: Real> {
+    pub base: Vec3D<T>,
+    pub axis: Vec3D<T>,
+    pub radius: T,
+    pub height: T,
+}
+
+impl<T: Real> Cone<T> {
+    /// Distances of all the surface crossings along the line of the ray.
+    fn crossings(&self, ray: Ray<T>) -> Vec<T> {
+        let basis = Basis::new(self.axis);
+        let origin = basis.to_local(ray.origin - self.base);
+        let direction = basis.to_local(ray.direction);
+        let slope = self.radius / self.height;
+        let slope_square = slope * slope;
+        // Height measured down from the apex, where the radius is `slope * apex_height`.
+        let apex_height = self.height - origin.y;
+        let at = |t: T| origin + direction * t;
+
+        let mut distances = roots(&[
+            origin.x * origin.x + origin.z * origin.z - slope_square * apex_height * apex_height,
+            T::from(2.0).unwrap()
+                * (origin.x * direction.x
+                    + origin.z * direction.z
+                    + slope_square * apex_height * direction.y),
+            direction.x * direction.x + direction.z * direction.z
+                - slope_square * direction.y * direction.y,
+        ]);
+        distances.retain(|t| {
+            let y = at(*t).y;
+            y >= T::zero() && y <= self.height
+        });
+        if direction.y != T::zero() {
+            let t = -origin.y / direction.y;
+            let point = at(t);
+            if point.x * point.x + point.z * point.z <= self.radius * self.radius {
+                distances.push(t);
+            }
+        }
+        distances
+    }
+}
+
+impl<T: Real> Intersect<T> for Cone<T> {
+    fn intersect(&self, ray: Ray<T>) -> Option<T> {
+        nearest(self.crossings(ray))
+    }
+
+    fn intervals(&self, ray: Ray<T>) -> Vec<(T, T)> {
+        convex_interval(self.crossings(ray))
+    }
+
+    fn normal(&self, point: Vec3D<T>) -> Vec3D<T> {
+        let basis = Basis::new(self.axis);
+        let local = basis.to_local(point - self.base);
+        let radial = (local.x * local.x + local.z * local.z).sqrt();
+        let slope = self.radius / self.height;
+        let side_distance = (radial - slope * (self.height - local.y)).abs();
+        if local.y.abs() < side_distance {
+            -self.axis
+        } else if radial == T::zero() {
+            self.axis
+        } else {
+            let outward = (basis.u * local.x + basis.v * local.z) * (T::one() / radial);
+            (outward + self.axis * slope).normalize()
+        }
+    }
+
+    /// Angle around the axis and the height relative to the full height.
+    fn uv(&self, point: Vec3D<T>) -> (T, T) {
+        let local = Basis::new(self.axis).to_local(point - self.base);
+        (longitude(local.z, local.x), local.y / self.height)
+    }
+
+    fn tangent_frame(&self, point: Vec3D<T>) -> TangentFrame<T> {
+        let basis = Basis::new(self.axis);
+        let local = basis.to_local(point - self.base);
+        TangentFrame::new(self.normal(point), around(&basis, local), self.axis)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        let apex = self.base + self.axis * self.height;
+        Some(disk_bounds(self.base, self.axis, self.radius).union(Aabb {
+            min: apex,
+            max: apex,
+        }))
+    }
+}
+
+/// Torus around the unit `axis`, with a tube of `minor_radius` circling the center at `major_radius`.
+pub struct Torus<T: Real> {
+    pub center: Vec3D<T>,
+    pub axis: Vec3D<T>,
+    pub major_radius: T,
+    pub minor_radius: T,
+}
+
+impl<T: Real> Torus<T> {
+    /// Distances of all the surface crossings along the line of the ray in ascending order.
+    fn crossings(&self, ray: Ray<T>) -> Vec<T> {
+        // The quartic is solved from the point of the ray closest to the center
+        // to keep its coefficients small for distant origins.
+        let shift = (self.center - ray.origin) * ray.direction;
+        let basis = Basis::new(self.axis);
+        let origin = basis.to_local(ray.point_at(shift) - self.center);
+        let direction = basis.to_local(ray.direction);
+
+        let (two, four) = (T::from(2.0).unwrap(), T::from(4.0).unwrap());
+        let major_square = self.major_radius * self.major_radius;
+        let minor_square = self.minor_radius * self.minor_radius;
+        let dd = direction * direction;
+        let e = origin * origin - major_square - minor_square;
+        let f = origin * direction;
+        let distances = roots(&[
+            e * e - four * major_square * (minor_square - origin.y * origin.y),
+            four * f * e + two * four * major_square * origin.y * direction.y,
+            four * f * f + two * dd * e + four * major_square * direction.y * direction.y,
+            four * dd * f,
+            dd * dd,
+        ]);
+        distances.into_iter().map(|t| t + shift).collect()
+    }
+
+    /// Whether the point is strictly inside the tube.
+    fn contains(&self, point: Vec3D<T>) -> bool {
+        let offset = point - self.center;
+        let height = offset * self.axis;
+        let radial = (offset * offset - height * height).max(T::zero()).sqrt() - self.major_radius;
+        radial * radial + height * height < self.minor_radius * self.minor_radius
+    }
+}
+
+impl<T: Real> Intersect<T> for Torus<T> {
+    fn intersect(&self, ray: Ray<T>) -> Option<T> {
+        nearest(self.crossings(ray))
+    }
+
+    /// Tangent rays give double roots which may come out split, merged or missing, so
+    /// close crossings are merged and the line is tested at the middle of every gap
+    /// between crossings instead of assuming it enters and exits in turn.
+    fn intervals(&self, ray: Ray<T>) -> Vec<(T, T)> {
+        let epsilon = self.minor_radius * T::from(1e-6).unwrap();
+        let mut crossings = self.crossings(ray);
+        crossings.dedup_by(|next, previous| *next - *previous < epsilon);
+        let mut spans: Vec<(T, T)> = Vec::new();
+        for pair in crossings.windows(2) {
+            let middle = ray.point_at((pair[0] + pair[1]) / T::from(2.0).unwrap());
+            if !self.contains(middle) {
+                continue;
+            }
+            match spans.last_mut() {
+                Some(span) if span.1 == pair[0] => span.1 = pair[1],
+                _ => spans.push((pair[0], pair[1])),
+            }
+        }
+        spans
+    }
+
+    fn normal(&self, point: Vec3D<T>) -> Vec3D<T> {
+        let offset = point - self.center;
+        let radial = offset - self.axis * (offset * self.axis);
+        (offset - radial.normalize() * self.major_radius).normalize()
+    }
+
+    /// Angle around the axis and the angle around the tube, starting from its outer side.
+    fn uv(&self, point: Vec3D<T>) -> (T, T) {
+        let local = Basis::new(self.axis).to_local(point - self.center);
+        let radial = (local.x * local.x + local.z * local.z).sqrt();
+        (
+            longitude(local.z, local.x),
+            longitude(local.y, radial - self.major_radius),
+        )
+    }
+
+    fn tangent_frame(&self, point: Vec3D<T>) -> TangentFrame<T> {
+        let basis = Basis::new(self.axis);
+        let local = basis.to_local(point - self.center);
+        let radial = (local.x * local.x + local.z * local.z).sqrt();
+        let outward = (basis.u * local.x + basis.v * local.z).normalize();
+        let around_tube = self.axis * (radial - self.major_radius) - outward * local.y;
+        TangentFrame::new(self.normal(point), around(&basis, local), around_tube)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        let extent = |component: T| {
+            self.major_radius * (T::one() - component * component).max(T::zero()).sqrt()
+                + self.minor_radius
+        };
+        let extent = Vec3D {
+            x: extent(self.axis.x),
+            y: extent(self.axis.y),
+            z: extent(self.axis.z),
+        };
+        Some(Aabb {
+            min: self.center - extent,
+            max: self.center + extent,
+        })
+    }
+}
+
+/// Orthonormal basis with the `axis` as the local Y axis.
+struct Basis<T: Real> {
+    u: Vec3D<T>,
+    axis: Vec3D<T>,
+    v: Vec3D<T>,
+}
+
+impl<T: Real> Basis<T> {
+    fn new(axis: Vec3D<T>) -> Basis<T> {
+        let helper = if axis.x.abs() < T::from(0.9).unwrap() {
+            Vec3D::from([T::one(), T::zero(), T::zero()])
+        } else {
+            Vec3D::from([T::zero(), T::one(), T::zero()])
+        };
+        let u = axis.cross(helper).normalize();
+        let v = u.cross(axis);
+        Basis { u, axis, v }
+    }
+
+    fn to_local(&self, vector: Vec3D<T>) -> Vec3D<T> {
+        Vec3D {
+            x: vector * self.u,
+            y: vector * self.axis,
+            z: vector * self.v,
+        }
+    }
+}
+
+/// Direction of growing `longitude(local.z, local.x)` around the axis of the basis.
+fn around<T: Real>(basis: &Basis<T>, local: Vec3D<T>) -> Vec3D<T> {
+    basis.v * local.x - basis.u * local.z
+}
+
+/// Angle of the point `(x, y)` around the origin mapped to `[0, 1]`.
+fn longitude<T: Real>(y: T, x: T) -> T {
+    T::from(0.5).unwrap() + y.atan2(x) / (pi::<T>() + pi::<T>())
+}
+
+fn pi<T: Real>() -> T {
+    T::from(std::f64::consts::PI).unwrap()
+}
+
+/// Nearest of the distances lying in front of the ray origin.
+fn nearest<T: Real, I: IntoIterator<Item = T>>(distances: I) -> Option<T> {
+    distances
+        .into_iter()
+        .filter(|t| *t >= threshold())
+        .reduce(T::min)
+}
+
+/// Nearest boundary of the intervals in front of the ray origin.
+fn nearest_boundary<T: Real>(intervals: &[(T, T)]) -> Option<T> {
+    nearest(
+        intervals
+            .iter()
+            .flat_map(|(enter, exit)| [*enter, *exit])
+            .filter(|t| *t != T::max_value()),
+    )
+}
+
+/// Interval between the first and the last crossing of a convex shape.
+fn convex_interval<T: Real>(crossings: Vec<T>) -> Vec<(T, T)> {
+    let enter = crossings.iter().copied().reduce(T::min);
+    let exit = crossings.iter().copied().reduce(T::max);
+    match (enter, exit) {
+        (Some(enter), Some(exit)) if enter < exit => vec![(enter, exit)],
+        _ => Vec::new(),
+    }
+}
+
+fn disk_bounds<T: Real>(center: Vec3D<T>, normal: Vec3D<T>, radius: T) -> Aabb<T> {
+    let extent = |component: T| radius * (T::one() - component * component).max(T::zero()).sqrt();
+    let extent = Vec3D {
+        x: extent(normal.x),
+        y: extent(normal.y),
+        z: extent(normal.z),
+    };
+    Aabb {
+        min: center - extent,
+        max: center + extent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ray(origin: [f64; 3], direction: [f64; 3]) -> Ray<f64> {
+        Ray {
+            origin: Vec3D::from(origin),
+            direction: Vec3D::from(direction).normalize(),
+            time: 0.0,
+        }
+    }
+
+    fn triangle() -> Triangle<f64> {
+        Triangle {
+            vertices: [
+                Vec3D::from([0.0, 0.0, 0.0]),
+                Vec3D::from([2.0, 0.0, 0.0]),
+                Vec3D::from([0.0, 2.0, 0.0]),
+            ],
+            normals: None,
+            uvs: None,
+        }
+    }
+
+    #[test]
+    fn test_triangle_intersect() {
+        let triangle = triangle();
+        assert_eq!(
+            triangle.intersect(ray([0.5, 0.5, 3.0], [0.0, 0.0, -1.0])),
+            Some(3.0)
+        );
+        assert_eq!(
+            triangle.intersect(ray([0.5, 0.5, -3.0], [0.0, 0.0, 1.0])),
+            Some(3.0)
+        );
+        assert_eq!(
+            triangle.intersect(ray([1.5, 1.5, 3.0], [0.0, 0.0, -1.0])),
+            None
+        );
+        assert_eq!(
+            triangle.intersect(ray([0.5, 0.5, 3.0], [0.0, 0.0, 1.0])),
+            None
+        );
+        assert_eq!(
+            triangle.intersect(ray([0.5, 0.5, 3.0], [1.0, 0.0, 0.0])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_triangle_normal() {
+        let flat = triangle();
+        let normal = flat.normal(Vec3D::from([0.5, 0.5, 0.0]));
+        assert_eq!((normal.x, normal.y, normal.z), (0.0, 0.0, 1.0));
+
+        let smooth = Triangle {
+            normals: Some([
+                Vec3D::from([0.0, 0.0, 1.0]),
+                Vec3D::from([1.0, 0.0, 0.0]),
+                Vec3D::from([0.0, 1.0, 0.0]),
+            ]),
+            ..triangle()
+        };
+        let normal = smooth.normal(Vec3D::from([1.0, 0.0, 0.0]));
+        assert!((normal - Vec3D::from([1.0, 0.0, 1.0]).normalize()).norm() < 1e-12);
+        let [u, v, w] = smooth.barycentric(Vec3D::from([0.5, 1.0, 0.0]));
+        assert!((u - 0.25).abs() < 1e-12 && (v - 0.25).abs() < 1e-12 && (w - 0.5).abs() < 1e-12);
+    }
+
+    fn assert_hit<S: Intersect<f64>>(shape: &S, ray: Ray<f64>, distance: f64, normal: [f64; 3]) {
+        let t = shape.intersect(ray).expect("expected a hit");
+        assert!(
+            (t - distance).abs() < 1e-9,
+            "distance {} != {}",
+            t,
+            distance
+        );
+        let found = shape.normal(ray.point_at(t));
+        assert!(
+            (found - Vec3D::from(normal).normalize()).norm() < 1e-9,
+            "normal ({}, {}, {})",
+            found.x,
+            found.y,
+            found.z
+        );
+    }
+
+    fn y_axis() -> Vec3D<f64> {
+        Vec3D::from([0.0, 1.0, 0.0])
+    }
+
+    #[test]
+    fn test_cuboid() {
+        let cuboid = Cuboid {
+            min: Vec3D::from([-1.0, -1.0, -1.0]),
+            max: Vec3D::from([1.0, 1.0, 1.0]),
+        };
+        assert_hit(
+            &cuboid,
+            ray([0.0, 0.0, 5.0], [0.0, 0.0, -1.0]),
+            4.0,
+            [0.0, 0.0, 1.0],
+        );
+        assert_hit(
+            &cuboid,
+            ray([0.5, -4.0, 0.2], [0.0, 1.0, 0.0]),
+            3.0,
+            [0.0, -1.0, 0.0],
+        );
+        // From inside the nearest exit is reported.
+        assert_hit(
+            &cuboid,
+            ray([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]),
+            1.0,
+            [1.0, 0.0, 0.0],
+        );
+        // Grazing along the top face still enters the box.
+        assert_eq!(
+            cuboid.intersect(ray([-5.0, 1.0, 0.0], [1.0, 0.0, 0.0])),
+            Some(4.0)
+        );
+        assert_eq!(
+            cuboid.intersect(ray([-5.0, 1.001, 0.0], [1.0, 0.0, 0.0])),
+            None
+        );
+        assert_eq!(
+            cuboid.intersect(ray([0.0, 0.0, 5.0], [0.0, 0.0, 1.0])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_disk() {
+        let disk = Disk {
+            center: Vec3D::from([0.0, 0.0, 0.0]),
+            normal: y_axis(),
+            radius: 1.0,
+        };
+        assert_hit(
+            &disk,
+            ray([0.5, 3.0, 0.0], [0.0, -1.0, 0.0]),
+            3.0,
+            [0.0, 1.0, 0.0],
+        );
+        assert_hit(
+            &disk,
+            ray([1.0, -3.0, 0.0], [0.0, 1.0, 0.0]),
+            3.0,
+            [0.0, 1.0, 0.0],
+        );
+        assert_eq!(
+            disk.intersect(ray([1.01, 3.0, 0.0], [0.0, -1.0, 0.0])),
+            None
+        );
+        // Rays within the disk plane never hit it.
+        assert_eq!(disk.intersect(ray([-5.0, 0.0, 0.0], [1.0, 0.0, 0.0])), None);
+    }
+
+    #[test]
+    fn test_cylinder() {
+        let cylinder = Cylinder {
+            base: Vec3D::from([0.0, 0.0, 0.0]),
+            axis: y_axis(),
+            radius: 1.0,
+            height: 2.0,
+        };
+        assert_hit(
+            &cylinder,
+            ray([5.0, 1.0, 0.0], [-1.0, 0.0, 0.0]),
+            4.0,
+            [1.0, 0.0, 0.0],
+        );
+        assert_hit(
+            &cylinder,
+            ray([0.5, 5.0, 0.0], [0.0, -1.0, 0.0]),
+            3.0,
+            [0.0, 1.0, 0.0],
+        );
+        assert_hit(
+            &cylinder,
+            ray([0.5, -3.0, 0.0], [0.0, 1.0, 0.0]),
+            3.0,
+            [0.0, -1.0, 0.0],
+        );
+        assert_hit(
+            &cylinder,
+            ray([0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+            1.0,
+            [0.0, 0.0, 1.0],
+        );
+        assert_hit(
+            &cylinder,
+            ray([0.0, 1.0, 0.0], [0.0, 1.0, 0.0]),
+            1.0,
+            [0.0, 1.0, 0.0],
+        );
+        assert_hit(
+            &cylinder,
+            ray([1.0, 1.0, 5.0], [0.0, 0.0, -1.0]),
+            5.0,
+            [1.0, 0.0, 0.0],
+        );
+        assert_eq!(
+            cylinder.intersect(ray([1.001, 1.0, 5.0], [0.0, 0.0, -1.0])),
+            None
+        );
+        assert_eq!(
+            cylinder.intersect(ray([5.0, 2.5, 0.0], [-1.0, 0.0, 0.0])),
+            None
+        );
+        assert_eq!(
+            cylinder.intersect(ray([2.0, -5.0, 0.0], [0.0, 1.0, 0.0])),
+            None
+        );
+
+        let tilted = Cylinder {
+            base: Vec3D::from([0.0, 0.0, 0.0]),
+            axis: Vec3D::from([1.0, 0.0, 0.0]),
+            ..cylinder
+        };
+        assert_hit(
+            &tilted,
+            ray([1.0, 5.0, 0.0], [0.0, -1.0, 0.0]),
+            4.0,
+            [0.0, 1.0, 0.0],
+        );
+        assert_hit(
+            &tilted,
+            ray([-3.0, 0.0, 0.0], [1.0, 0.0, 0.0]),
+            3.0,
+            [-1.0, 0.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn test_cone() {
+        let cone = Cone {
+            base: Vec3D::from([0.0, 0.0, 0.0]),
+            axis: y_axis(),
+            radius: 1.0,
+            height: 1.0,
+        };
+        assert_hit(
+            &cone,
+            ray([5.0, 0.5, 0.0], [-1.0, 0.0, 0.0]),
+            4.5,
+            [1.0, 1.0, 0.0],
+        );
+        assert_hit(
+            &cone,
+            ray([0.2, -3.0, 0.0], [0.0, 1.0, 0.0]),
+            3.0,
+            [0.0, -1.0, 0.0],
+        );
+        assert_hit(
+            &cone,
+            ray([0.0, 5.0, 0.0], [0.0, -1.0, 0.0]),
+            4.0,
+            [0.0, 1.0, 0.0],
+        );
+        assert_hit(
+            &cone,
+            ray([0.0, 0.25, 0.0], [0.0, 0.0, -1.0]),
+            0.75,
+            [0.0, 1.0, -1.0],
+        );
+        // The horizontal circle of radius 0.5 at half height is touched by this ray.
+        assert_eq!(
+            cone.intersect(ray([-5.0, 0.5, 0.5], [1.0, 0.0, 0.0])),
+            Some(5.0)
+        );
+        assert_eq!(
+            cone.intersect(ray([-5.0, 0.5, 0.51], [1.0, 0.0, 0.0])),
+            None
+        );
+        // The mirrored nappe above the apex is not part of the cone.
+        assert_eq!(cone.intersect(ray([5.0, 1.5, 0.0], [-1.0, 0.0, 0.0])), None);
+    }
+
+    #[test]
+    fn test_torus() {
+        let torus = Torus {
+            center: Vec3D::from([0.0, 0.0, 0.0]),
+            axis: y_axis(),
+            major_radius: 2.0,
+            minor_radius: 0.5,
+        };
+        assert_hit(
+            &torus,
+            ray([5.0, 0.0, 0.0], [-1.0, 0.0, 0.0]),
+            2.5,
+            [1.0, 0.0, 0.0],
+        );
+        assert_hit(
+            &torus,
+            ray([2.0, 5.0, 0.0], [0.0, -1.0, 0.0]),
+            4.5,
+            [0.0, 1.0, 0.0],
+        );
+        assert_hit(
+            &torus,
+            ray([2.0, 0.0, 0.0], [1.0, 0.0, 0.0]),
+            0.5,
+            [1.0, 0.0, 0.0],
+        );
+        assert_hit(
+            &torus,
+            ray([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+            1.5,
+            [0.0, 0.0, -1.0],
+        );
+        assert_hit(
+            &torus,
+            ray([0.0, 0.0, 1000.0], [0.0, 0.0, -1.0]),
+            997.5,
+            [0.0, 0.0, 1.0],
+        );
+        assert_eq!(
+            torus.intersect(ray([0.0, 5.0, 0.0], [0.0, -1.0, 0.0])),
+            None
+        );
+        // Rays skimming the top of the tube.
+        let t = torus
+            .intersect(ray([-5.0, 0.499, 0.0], [1.0, 0.0, 0.0]))
+            .unwrap();
+        assert!((t - 3.0).abs() < 0.1);
+        assert_eq!(
+            torus.intersect(ray([-5.0, 0.501, 0.0], [1.0, 0.0, 0.0])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_intervals() {
+        let sphere = Sphere {
+            center: Vec3D::from([0.0, 0.0, 0.0]),
+            radius: 1.0,
+        };
+        assert_eq!(
+            sphere.intervals(ray([0.0, 0.0, 0.0], [1.0, 0.0, 0.0])),
+            vec![(-1.0, 1.0)]
+        );
+        let plane = Plane {
+            origin: Vec3D::from([0.0, 0.0, 0.0]),
+            normal: y_axis(),
+        };
+        assert_eq!(
+            plane.intervals(ray([0.0, 2.0, 0.0], [0.0, -1.0, 0.0])),
+            vec![(2.0, f64::MAX)]
+        );
+        assert_eq!(
+            plane.intervals(ray([0.0, -1.0, 0.0], [1.0, 0.0, 0.0])),
+            vec![(f64::MIN, f64::MAX)]
+        );
+        let torus = Torus {
+            center: Vec3D::from([0.0, 0.0, 0.0]),
+            axis: y_axis(),
+            major_radius: 2.0,
+            minor_radius: 0.5,
+        };
+        let spans = torus.intervals(ray([-5.0, 0.0, 0.0], [1.0, 0.0, 0.0]));
+        let expected = [(2.5, 3.5), (6.5, 7.5)];
+        assert_eq!(spans.len(), 2);
+        for ((enter, exit), (expected_enter, expected_exit)) in spans.into_iter().zip(expected) {
+            assert!((enter - expected_enter).abs() < 1e-9 && (exit - expected_exit).abs() < 1e-9);
+        }
+        // Touching the top of the tube encloses nothing.
+        assert!(torus
+            .intervals(ray([-5.0, 0.5, 0.0], [1.0, 0.0, 0.0]))
+            .is_empty());
+        // Touching the inner side of the tube from inside keeps a single span.
+        let spans = torus.intervals(ray([-5.0, 0.0, 1.5], [1.0, 0.0, 0.0]));
+        assert_eq!(spans.len(), 1);
+        assert!((spans[0].0 - 3.0).abs() < 1e-6 && (spans[0].1 - 7.0).abs() < 1e-6);
+        let cylinder = Cylinder {
+            base: Vec3D::from([0.0, 0.0, 0.0]),
+            axis: y_axis(),
+            radius: 1.0,
+            height: 2.0,
+        };
+        assert_eq!(
+            cylinder.intervals(ray([0.0, 5.0, 0.0], [0.0, -1.0, 0.0])),
+            vec![(3.0, 5.0)]
+        );
+        assert!(cylinder
+            .intervals(ray([1.0, 1.0, 5.0], [0.0, 0.0, -1.0]))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_bounding_boxes() {
+        let torus = Torus {
+            center: Vec3D::from([1.0, 0.0, 0.0]),
+            axis: y_axis(),
+            major_radius: 2.0,
+            minor_radius: 0.5,
+        };
+        let bounds = torus.bounding_box().unwrap();
+        assert_eq!(
+            (bounds.min.x, bounds.min.y, bounds.max.z),
+            (-1.5, -0.5, 2.5)
+        );
+        let cone = Cone {
+            base: Vec3D::from([0.0, 0.0, 0.0]),
+            axis: Vec3D::from([0.0, 0.0, -1.0]),
+            radius: 1.0,
+            height: 3.0,
+        };
+        let bounds = cone.bounding_box().unwrap();
+        assert_eq!(
+            (bounds.min.x, bounds.min.z, bounds.max.z),
+            (-1.0, -3.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_tangent_frames() {
+        let y = Vec3D::from([0.0, 1.0, 0.0]);
+        let shapes = vec![
+            (
+                Shape::Sphere(Sphere {
+                    center: Vec3D::default(),
+                    radius: 2.0,
+                }),
+                [1.2, 1.0, 1.2],
+            ),
+            (
+                Shape::Plane(Plane {
+                    origin: Vec3D::default(),
+                    normal: y,
+                }),
+                [1.0, 0.0, 2.0],
+            ),
+            (
+                Shape::Triangle(Triangle {
+                    uvs: Some([(0.0, 1.0), (0.0, 0.0), (1.0, 1.0)]),
+                    ..triangle()
+                }),
+                [0.5, 0.5, 0.0],
+            ),
+            (Shape::Triangle(triangle()), [0.5, 0.5, 0.0]),
+            (
+                Shape::Rectangle(Rectangle {
+                    corner: Vec3D::default(),
+                    u_edge: Vec3D::from([2.0, 0.0, 0.0]),
+                    v_edge: Vec3D::from([1.0, 0.0, -2.0]),
+                }),
+                [1.5, 0.0, -1.0],
+            ),
+            (
+                Shape::Cuboid(Cuboid {
+                    min: Vec3D::from([-1.0, -1.0, -1.0]),
+                    max: Vec3D::from([1.0, 1.0, 1.0]),
+                }),
+                [0.2, 0.3, 1.0],
+            ),
+            (
+                Shape::Disk(Disk {
+                    center: Vec3D::default(),
+                    normal: y,
+                    radius: 2.0,
+                }),
+                [0.5, 0.0, 1.0],
+            ),
+            (
+                Shape::Cylinder(Cylinder {
+                    base: Vec3D::default(),
+                    axis: y,
+                    radius: 1.0,
+                    height: 2.0,
+                }),
+                [0.6, 1.0, 0.8],
+            ),
+            (
+                Shape::Cone(Cone {
+                    base: Vec3D::default(),
+                    axis: y,
+                    radius: 2.0,
+                    height: 2.0,
+                }),
+                [0.6, 1.0, 0.8],
+            ),
+            (
+                Shape::Torus(Torus {
+                    center: Vec3D::default(),
+                    axis: y,
+                    major_radius: 2.0,
+                    minor_radius: 0.5,
+                }),
+                [1.2, 0.3, 1.6],
+            ),
+        ];
+        for (index, (shape, point)) in shapes.iter().enumerate() {
+            let point = Vec3D::from(*point);
+            let frame = shape.tangent_frame(point);
+            assert!(
+                (frame.normal - shape.normal(point)).norm() < 1e-9,
+                "{}",
+                index
+            );
+            for (first, second) in [
+                (frame.tangent, frame.bitangent),
+                (frame.tangent, frame.normal),
+                (frame.bitangent, frame.normal),
+            ] {
+                assert!((first * second).abs() < 1e-9, "{}", index);
+                assert!((first.norm() - 1.0).abs() < 1e-9, "{}", index);
+            }
+            let (u, v) = shape.uv(point);
+            let (u_along, _) = shape.uv(point + frame.tangent * 1e-4);
+            let (_, v_along) = shape.uv(point + frame.bitangent * 1e-4);
+            assert!(u_along > u, "{}", index);
+            assert!(v_along > v, "{}", index);
+        }
+    }
 }
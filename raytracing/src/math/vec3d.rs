@@ -1,7 +1,9 @@
 use num_traits::real::Real;
+use serde::{Deserialize, Serialize};
 use std::ops;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Deserialize, Serialize)]
+#[serde(from = "[T; 3]", into = "[T; 3]")]
 pub struct Vec3D<T: Real> {
     pub x: T,
     pub y: T,
@@ -18,11 +20,31 @@ impl<T: Real> Default for Vec3D<T> {
     }
 }
 
+impl<T: Real> From<[T; 3]> for Vec3D<T> {
+    fn from([x, y, z]: [T; 3]) -> Self {
+        Vec3D { x, y, z }
+    }
+}
+
+impl<T: Real> From<Vec3D<T>> for [T; 3] {
+    fn from(Vec3D { x, y, z }: Vec3D<T>) -> Self {
+        [x, y, z]
+    }
+}
+
 impl<T: Real> Vec3D<T> {
     pub fn norm(self) -> T {
         (self * self).sqrt()
     }
 
+    pub fn cross(self, rhs: Vec3D<T>) -> Vec3D<T> {
+        Vec3D {
+            x: self.y * rhs.z - self.z * rhs.y,
+            y: self.z * rhs.x - self.x * rhs.z,
+            z: self.x * rhs.y - self.y * rhs.x,
+        }
+    }
+
     pub fn normalize(&self) -> Vec3D<T> {
         let norm = self.norm();
         Vec3D {
@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+
+/// Keyframed values of a scene description, turning it into a sequence of frames.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Animation {
+    /// Number of frames rendered, the first one at time zero.
+    pub frames: usize,
+    /// Frames per second of animation time.
+    #[serde(default = "default_frame_rate")]
+    pub frame_rate: f64,
+    #[serde(default)]
+    pub tracks: Vec<Track>,
+}
+
+/// Value of the scene description changing over time, for example the `camera.position`,
+/// an `objects[2].center` or the `objects[0].material.diffuse` color.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Track {
+    /// Path of the value in the description: table keys separated by dots, array
+    /// elements by their index in brackets.
+    pub target: String,
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    /// Keyframes ordered by time, the value is held before the first and after the last.
+    pub keys: Vec<Keyframe>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Keyframe {
+    /// Time of the keyframe in seconds.
+    pub time: f64,
+    pub value: KeyValue,
+}
+
+/// Number, or list of numbers such as a vector or a color.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum KeyValue {
+    Number(f64),
+    List(Vec<f64>),
+}
+
+/// How values are blended between two keyframes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interpolation {
+    /// Straight line between the keyframes.
+    #[default]
+    Linear,
+    /// Easing in and out of every keyframe, stopping at each of them.
+    Smoothstep,
+    /// Smooth curve through all the keyframes, moving through them without stopping.
+    CatmullRom,
+}
+
+fn default_frame_rate() -> f64 {
+    24.0
+}
+
+impl Animation {
+    /// Time of the frame, counted from zero.
+    pub fn frame_time(&self, index: usize) -> f64 {
+        index as f64 / self.frame_rate
+    }
+
+    /// Sets the value of every track at the time in the description.
+    pub fn apply(&self, description: &mut toml::Value, time: f64) -> Result<(), String> {
+        for track in &self.tracks {
+            let value = match track.value(time) {
+                KeyValue::Number(number) => toml::Value::Float(number),
+                KeyValue::List(list) => {
+                    toml::Value::Array(list.into_iter().map(toml::Value::Float).collect())
+                }
+            };
+            *lookup(description, &track.target)? = value;
+        }
+        Ok(())
+    }
+}
+
+/// Name of the image of the frame in a sequence, numbered from one.
+pub fn frame_file_name(index: usize, extension: &str) -> String {
+    format!("frame_{:04}.{}", index + 1, extension)
+}
+
+impl Track {
+    pub fn value(&self, time: f64) -> KeyValue {
+        let keys = &self.keys;
+        let next = keys.iter().position(|key| key.time > time);
+        let (first, second) = match next {
+            Some(0) => return keys[0].value.clone(),
+            Some(next) => (next - 1, next),
+            None => return keys[keys.len() - 1].value.clone(),
+        };
+        let t = (time - keys[first].time) / (keys[second].time - keys[first].time);
+        let values = |index: usize| keys[index].value.components();
+        let (p1, p2) = (values(first), values(second));
+        let blended: Vec<f64> = match self.interpolation {
+            Interpolation::Linear => lerp(p1, p2, t),
+            Interpolation::Smoothstep => lerp(p1, p2, t * t * (3.0 - 2.0 * t)),
+            Interpolation::CatmullRom => {
+                // Tangents from the neighbouring keyframes by their times, the end
+                // keyframes continue the segment next to them in a straight line.
+                let tangent = |index: usize, i: usize| {
+                    let (before, after) =
+                        (index.saturating_sub(1), (index + 1).min(keys.len() - 1));
+                    (values(after)[i] - values(before)[i]) / (keys[after].time - keys[before].time)
+                };
+                let length = keys[second].time - keys[first].time;
+                let (t2, t3) = (t * t, t * t * t);
+                (0..p1.len())
+                    .map(|i| {
+                        (2.0 * t3 - 3.0 * t2 + 1.0) * p1[i]
+                            + (t3 - 2.0 * t2 + t) * length * tangent(first, i)
+                            + (3.0 * t2 - 2.0 * t3) * p2[i]
+                            + (t3 - t2) * length * tangent(second, i)
+                    })
+                    .collect()
+            }
+        };
+        match keys[first].value {
+            KeyValue::Number(_) => KeyValue::Number(blended[0]),
+            KeyValue::List(_) => KeyValue::List(blended),
+        }
+    }
+}
+
+impl KeyValue {
+    pub fn components(&self) -> &[f64] {
+        match self {
+            KeyValue::Number(number) => std::slice::from_ref(number),
+            KeyValue::List(list) => list,
+        }
+    }
+}
+
+fn lerp(first: &[f64], second: &[f64], t: f64) -> Vec<f64> {
+    first
+        .iter()
+        .zip(second)
+        .map(|(first, second)| first + (second - first) * t)
+        .collect()
+}
+
+/// Value at the path in the description, missing table keys are added.
+fn lookup<'a>(description: &'a mut toml::Value, path: &str) -> Result<&'a mut toml::Value, String> {
+    let mut value = description;
+    for segment in path.split('.') {
+        let (key, mut indices) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+        if key.is_empty() {
+            return Err(format!("invalid path `{}`", path));
+        }
+        value = match value {
+            toml::Value::Table(table) => table
+                .entry(key)
+                .or_insert_with(|| toml::Value::Table(toml::value::Table::new())),
+            _ => return Err(format!("`{}` is not inside a table", key)),
+        };
+        while !indices.is_empty() {
+            let (index, rest) = indices
+                .strip_prefix('[')
+                .and_then(|indices| indices.split_once(']'))
+                .and_then(|(index, rest)| Some((index.parse::<usize>().ok()?, rest)))
+                .ok_or_else(|| format!("invalid path `{}`", path))?;
+            value = value
+                .as_array_mut()
+                .and_then(|array| array.get_mut(index))
+                .ok_or_else(|| format!("`{}` has no element {}", key, index))?;
+            indices = rest;
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(interpolation: Interpolation, values: &[f64]) -> Track {
+        Track {
+            target: String::from("value"),
+            interpolation,
+            keys: values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| Keyframe {
+                    time: index as f64,
+                    value: KeyValue::Number(*value),
+                })
+                .collect(),
+        }
+    }
+
+    fn number(value: KeyValue) -> f64 {
+        value.components()[0]
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let linear = track(Interpolation::Linear, &[0.0, 4.0, 4.0]);
+        assert_eq!(number(linear.value(-1.0)), 0.0);
+        assert_eq!(number(linear.value(0.25)), 1.0);
+        assert_eq!(number(linear.value(1.5)), 4.0);
+        assert_eq!(number(linear.value(7.0)), 4.0);
+
+        let smoothstep = track(Interpolation::Smoothstep, &[0.0, 4.0]);
+        assert_eq!(number(smoothstep.value(0.5)), 2.0);
+        assert!(number(smoothstep.value(0.1)) < 0.1 * 4.0);
+        assert!(number(smoothstep.value(0.9)) > 0.9 * 4.0);
+
+        let catmull_rom = track(Interpolation::CatmullRom, &[0.0, 1.0, 2.0, 3.0]);
+        for time in &[0.0, 0.5, 1.0, 1.25, 2.0, 3.0] {
+            assert!((number(catmull_rom.value(*time)) - time).abs() < 1e-12);
+        }
+        let catmull_rom = track(Interpolation::CatmullRom, &[0.0, 1.0, 1.0]);
+        // Keeps moving through the keyframe, overshooting the plateau after it.
+        assert!(number(catmull_rom.value(1.3)) > 1.0);
+        assert_eq!(number(catmull_rom.value(1.0)), 1.0);
+
+        // Unevenly spaced keyframes on a line stay on it, without jumps in speed.
+        let mut uneven = track(Interpolation::CatmullRom, &[0.0, 1.0, 10.0]);
+        uneven.keys[2].time = 10.0;
+        for time in &[0.5, 1.0, 3.0, 7.5] {
+            assert!((number(uneven.value(*time)) - time).abs() < 1e-12);
+        }
+        uneven.keys[2].value = KeyValue::Number(2.0);
+        let speed =
+            |time: f64| (number(uneven.value(time + 1e-6)) - number(uneven.value(time))) / 1e-6;
+        assert!((speed(1.0 - 1e-5) - speed(1.0 + 1e-5)).abs() < 1e-3);
+        assert!((1..100).all(|step| number(uneven.value(step as f64 * 0.1)) <= 2.0));
+
+        let list = Track {
+            keys: vec![
+                Keyframe {
+                    time: 0.0,
+                    value: KeyValue::List(vec![0.0, 1.0, 2.0]),
+                },
+                Keyframe {
+                    time: 2.0,
+                    value: KeyValue::List(vec![2.0, 1.0, 0.0]),
+                },
+            ],
+            ..track(Interpolation::Linear, &[])
+        };
+        assert_eq!(list.value(1.0), KeyValue::List(vec![1.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn test_apply_tracks() {
+        let mut description: toml::Value = toml::from_str(
+            r#"
+            [camera]
+            position = [0.0, 0.0, 0.0]
+
+            [[objects]]
+            center = [0.0, 0.0, 0.0]
+            material = { diffuse = [1.0, 1.0, 1.0] }
+            "#,
+        )
+        .unwrap();
+        let animation: Animation = toml::from_str(
+            r#"
+            frames = 3
+            frame_rate = 2
+
+            [[tracks]]
+            target = "camera.position"
+            keys = [{ time = 0, value = [0, 0, 0] }, { time = 1, value = [0, 0, -2] }]
+
+            [[tracks]]
+            target = "objects[0].material.shininess"
+            keys = [{ time = 0, value = 10 }, { time = 1, value = 20 }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(animation.frame_time(1), 0.5);
+        animation
+            .apply(&mut description, animation.frame_time(1))
+            .unwrap();
+        assert_eq!(
+            description["camera"]["position"],
+            toml::Value::from(vec![0.0, 0.0, -1.0])
+        );
+        assert_eq!(
+            description["objects"][0]["material"]["shininess"],
+            toml::Value::Float(15.0)
+        );
+
+        let mut value = description.clone();
+        assert!(lookup(&mut value, "objects[1].center").is_err());
+        assert!(lookup(&mut value, "camera.position.x").is_err());
+        assert!(lookup(&mut value, "objects[x]").is_err());
+        assert_eq!(frame_file_name(0, "png"), "frame_0001.png");
+    }
+}
@@ -0,0 +1,193 @@
+use crate::math::color::Color;
+use crate::math::ray::Ray;
+use crate::render::scene::Scene;
+use image::Rgb;
+use num_traits::real::Real;
+use std::str::FromStr;
+
+/// Extra per-pixel buffer rendered along with the colors, from the ray through the
+/// pixel center or recorded while rendering the colors. High dynamic range outputs
+/// store the raw values, 8-bit images a view of them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Aov {
+    /// Distance to the first hit, the largest value where nothing is hit. Shown
+    /// brighter when nearer.
+    Depth,
+    /// Shading normal at the first hit, shown as `0.5 * normal + 0.5`.
+    Normal,
+    /// Diffuse color at the first hit.
+    Albedo,
+    /// Index of the object hit in `Scene::objects`, -1 where nothing is hit. Shown by
+    /// a color for every object.
+    ObjectId,
+    /// Deepest number of nested rays hitting objects among the samples of the pixel,
+    /// recorded while tracing its reflections and refractions, or its paths. Shown
+    /// brighter when deeper.
+    RecursionDepth,
+}
+
+impl Aov {
+    /// Value of the buffer for the ray, the same in all channels for scalar buffers.
+    /// `None` for the buffers recorded while rendering the colors.
+    pub fn value<T: Real>(&self, scene: &Scene<T>, ray: Ray<T>) -> Option<Color<T>> {
+        let gray = |value: T| Color::from([value, value, value]);
+        if *self == Aov::RecursionDepth {
+            return None;
+        }
+        Some(match (self, scene.intersect_object(ray)) {
+            (Aov::Depth, Some((_, result))) => gray((result.point - ray.origin).norm()),
+            (Aov::Depth, None) => gray(T::max_value()),
+            (Aov::Normal, Some((_, result))) => {
+                let normal = result.normal;
+                Color::from([normal.x, normal.y, normal.z])
+            }
+            (Aov::Albedo, Some((_, result))) => result.material.diffuse,
+            (Aov::ObjectId, Some((index, _))) => gray(T::from(index).unwrap()),
+            (Aov::ObjectId, None) => gray(-T::one()),
+            _ => Color::zero(),
+        })
+    }
+
+    /// 8-bit view of all the values of the buffer.
+    pub fn view<T: Real>(&self, buffer: &[Color<T>]) -> Vec<Rgb<u8>> {
+        let byte = |value: T| {
+            (value.max(T::zero()).min(T::one()) * T::from(255.0).unwrap())
+                .round()
+                .to_u8()
+                .unwrap_or(0)
+        };
+        let gray = |value: T| Rgb([byte(value); 3]);
+        let largest = |valid: &dyn Fn(T) -> bool| {
+            buffer
+                .iter()
+                .map(|color| color.r)
+                .filter(|value| valid(*value))
+                .fold(T::zero(), T::max)
+        };
+        match self {
+            Aov::Depth => {
+                let farthest = largest(&|value| value != T::max_value());
+                buffer
+                    .iter()
+                    .map(|value| {
+                        if value.r == T::max_value() {
+                            Rgb([0, 0, 0])
+                        } else {
+                            gray(T::one() - value.r / (farthest + T::one()))
+                        }
+                    })
+                    .collect()
+            }
+            Aov::Normal => {
+                let half = T::from(0.5).unwrap();
+                buffer
+                    .iter()
+                    .map(|value| {
+                        Rgb([value.r, value.g, value.b].map(|value| byte(value * half + half)))
+                    })
+                    .collect()
+            }
+            Aov::Albedo => buffer
+                .iter()
+                .map(|value| Rgb([value.r, value.g, value.b].map(byte)))
+                .collect(),
+            Aov::ObjectId => buffer
+                .iter()
+                .map(|value| match value.r.to_i64() {
+                    Some(index) if index >= 0 => {
+                        // Well spread colors for consecutive indices.
+                        let hash = (index as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+                        let [r, g, b, ..] = hash.to_be_bytes();
+                        Rgb([r | 0x40, g | 0x40, b | 0x40])
+                    }
+                    _ => Rgb([0, 0, 0]),
+                })
+                .collect(),
+            Aov::RecursionDepth => {
+                let deepest = largest(&|_| true).max(T::one());
+                buffer.iter().map(|value| gray(value.r / deepest)).collect()
+            }
+        }
+    }
+}
+
+impl FromStr for Aov {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "depth" => Ok(Aov::Depth),
+            "normal" => Ok(Aov::Normal),
+            "albedo" => Ok(Aov::Albedo),
+            "object_id" => Ok(Aov::ObjectId),
+            "recursion_depth" => Ok(Aov::RecursionDepth),
+            _ => Err(format!(
+                "unknown AOV `{}`, expected `depth`, `normal`, `albedo`, `object_id` or `recursion_depth`",
+                name
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::shape::{Shape, Sphere};
+    use crate::math::vec3d::Vec3D;
+    use crate::render::hittable::Hittable;
+    use crate::render::material::Material;
+
+    #[test]
+    fn test_aov_values() {
+        let sphere = |x: f64, material: Material<f64>| Hittable::Primitive {
+            shape: Shape::Sphere(Sphere {
+                center: Vec3D::from([x, 0.0, 5.0]),
+                radius: 1.0,
+            }),
+            material: material.into(),
+        };
+        let red = Material {
+            diffuse: Color::from([1.0, 0.0, 0.0]),
+            ..Material::default()
+        };
+        let scene = Scene::new(
+            Color::zero(),
+            vec![sphere(-4.0, Material::default()), sphere(0.0, red)],
+            Vec::new(),
+            4,
+        );
+        let hit = Ray {
+            origin: Vec3D::from([0.0, 0.0, 0.0]),
+            direction: Vec3D::from([0.0, 0.0, 1.0]),
+            time: 0.0,
+        };
+        let miss = Ray {
+            origin: Vec3D::from([0.0, 0.0, 0.0]),
+            direction: Vec3D::from([0.0, 1.0, 0.0]),
+            time: 0.0,
+        };
+        let gray = |value: f64| Color::from([value, value, value]);
+        assert!(Aov::Depth.value(&scene, hit) == Some(gray(4.0)));
+        assert!(Aov::Depth.value(&scene, miss) == Some(gray(f64::MAX)));
+        assert!(Aov::Normal.value(&scene, hit) == Some(Color::from([0.0, 0.0, -1.0])));
+        assert!(Aov::Albedo.value(&scene, hit) == Some(Color::from([1.0, 0.0, 0.0])));
+        assert!(Aov::ObjectId.value(&scene, hit) == Some(gray(1.0)));
+        assert!(Aov::ObjectId.value(&scene, miss) == Some(gray(-1.0)));
+        assert!(Aov::RecursionDepth.value(&scene, miss).is_none());
+
+        let depths = [gray(1.0), gray(3.0), gray(f64::MAX)];
+        assert_eq!(
+            Aov::Depth.view(&depths),
+            vec![Rgb([191, 191, 191]), Rgb([64, 64, 64]), Rgb([0, 0, 0])]
+        );
+        let ids = Aov::ObjectId.view(&[gray(0.0), gray(1.0), gray(-1.0)]);
+        assert!(ids[0] != ids[1]);
+        assert_eq!(ids[2], Rgb([0, 0, 0]));
+        assert_eq!(
+            Aov::Normal.view(&[Color::from([1.0, 0.0, -1.0])]),
+            vec![Rgb([255, 128, 0])]
+        );
+        assert_eq!(Aov::from_str("object_id"), Ok(Aov::ObjectId));
+        assert!(Aov::from_str("color").is_err());
+    }
+}
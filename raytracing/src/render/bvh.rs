@@ -0,0 +1,166 @@
+use crate::math::aabb::{axis_value, Aabb};
+use crate::math::ray::Ray;
+use num_traits::real::Real;
+use std::cmp::Ordering;
+
+/// Maximum number of items in a leaf node.
+const LEAF_SIZE: usize = 4;
+
+/// Bounding volume hierarchy over items identified by their indices.
+pub struct Bvh<T: Real> {
+    nodes: Vec<Node<T>>,
+    items: Vec<usize>,
+}
+
+enum Node<T: Real> {
+    Leaf {
+        bounds: Aabb<T>,
+        first: usize,
+        count: usize,
+    },
+    Branch {
+        bounds: Aabb<T>,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl<T: Real> Node<T> {
+    fn bounds(&self) -> &Aabb<T> {
+        match self {
+            Node::Leaf { bounds, .. } => bounds,
+            Node::Branch { bounds, .. } => bounds,
+        }
+    }
+}
+
+impl<T: Real> Bvh<T> {
+    /// Builds the hierarchy over items given by their indices and bounding boxes,
+    /// splitting the nodes in the median along the longest axis.
+    pub fn new(mut items: Vec<(usize, Aabb<T>)>) -> Bvh<T> {
+        let mut bvh = Bvh {
+            nodes: Vec::new(),
+            items: Vec::with_capacity(items.len()),
+        };
+        if !items.is_empty() {
+            bvh.build(&mut items);
+        }
+        bvh
+    }
+
+    fn build(&mut self, items: &mut [(usize, Aabb<T>)]) -> usize {
+        let bounds = items
+            .iter()
+            .map(|(_, bounds)| *bounds)
+            .reduce(Aabb::union)
+            .unwrap();
+        let node_index = self.nodes.len();
+        if items.len() <= LEAF_SIZE {
+            self.nodes.push(Node::Leaf {
+                bounds,
+                first: self.items.len(),
+                count: items.len(),
+            });
+            self.items.extend(items.iter().map(|(index, _)| *index));
+            return node_index;
+        }
+
+        let centers = items
+            .iter()
+            .map(|(_, bounds)| {
+                let center = bounds.center();
+                Aabb {
+                    min: center,
+                    max: center,
+                }
+            })
+            .reduce(Aabb::union)
+            .unwrap();
+        let axis = centers.longest_axis();
+        let middle = items.len() / 2;
+        items.select_nth_unstable_by(middle, |(_, first), (_, second)| {
+            axis_value(first.center(), axis)
+                .partial_cmp(&axis_value(second.center(), axis))
+                .unwrap_or(Ordering::Equal)
+        });
+
+        self.nodes.push(Node::Leaf {
+            bounds,
+            first: 0,
+            count: 0,
+        });
+        let (left_items, right_items) = items.split_at_mut(middle);
+        let left = self.build(left_items);
+        let right = self.build(right_items);
+        self.nodes[node_index] = Node::Branch {
+            bounds,
+            left,
+            right,
+        };
+        node_index
+    }
+
+    /// Finds the nearest item hit by the ray, `intersect` gives the distance to an item
+    /// along with any details of the hit.
+    pub fn nearest<H, F>(&self, ray: Ray<T>, mut intersect: F) -> Option<(usize, H)>
+    where
+        F: FnMut(usize) -> Option<(T, H)>,
+    {
+        let mut nearest = None;
+        let mut max_distance = T::max_value();
+        self.traverse(ray, &mut max_distance, |item, max_distance| {
+            if let Some((distance, hit)) = intersect(item) {
+                if distance < *max_distance {
+                    *max_distance = distance;
+                    nearest = Some((item, hit));
+                }
+            }
+            false
+        });
+        nearest
+    }
+
+    /// Checks whether `intersect` reports a hit closer than `max_distance` for any item
+    /// whose bounding box the ray enters.
+    pub fn any<F>(&self, ray: Ray<T>, max_distance: T, mut intersect: F) -> bool
+    where
+        F: FnMut(usize) -> Option<T>,
+    {
+        let mut max_distance = max_distance;
+        self.traverse(ray, &mut max_distance, |item, max_distance| {
+            intersect(item).is_some_and(|distance| distance < *max_distance)
+        })
+    }
+
+    /// Visits the items whose bounding boxes the ray enters before `max_distance`,
+    /// which the visitor may shrink, until the visitor returns `true`.
+    fn traverse<F>(&self, ray: Ray<T>, max_distance: &mut T, mut visit: F) -> bool
+    where
+        F: FnMut(usize, &mut T) -> bool,
+    {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !node.bounds().hit(ray, *max_distance) {
+                continue;
+            }
+            match node {
+                Node::Leaf { first, count, .. } => {
+                    for item in &self.items[*first..*first + *count] {
+                        if visit(*item, max_distance) {
+                            return true;
+                        }
+                    }
+                }
+                Node::Branch { left, right, .. } => {
+                    stack.push(*right);
+                    stack.push(*left);
+                }
+            }
+        }
+        false
+    }
+}
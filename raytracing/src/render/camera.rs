@@ -0,0 +1,174 @@
+use crate::math::ray::Ray;
+use crate::math::vec3d::Vec3D;
+use num_traits::real::Real;
+
+#[derive(Copy, Clone)]
+pub struct Camera<T: Real> {
+    pub position: Vec3D<T>,
+    pub look_at: Vec3D<T>,
+    pub up: Vec3D<T>,
+    /// Vertical field of view in radians.
+    pub field_of_view: T,
+    /// Width of the image plane divided by its height.
+    pub aspect_ratio: T,
+    /// Radius of the thin lens, `0` gives a pinhole camera with everything in focus.
+    pub aperture: T,
+    /// Distance from the camera to the plane in perfect focus.
+    pub focus_distance: T,
+    /// Times within the frame the shutter is open between, rays are spread over them
+    /// when they differ, blurring moving objects.
+    pub shutter_open: T,
+    pub shutter_close: T,
+}
+
+impl<T: Real> Camera<T> {
+    /// Camera in the origin looking down the negative Z axis.
+    pub fn new(field_of_view_degrees: T, aspect_ratio: T) -> Camera<T> {
+        Camera {
+            position: Vec3D::default(),
+            look_at: Vec3D {
+                x: T::zero(),
+                y: T::zero(),
+                z: -T::one(),
+            },
+            up: Vec3D {
+                x: T::zero(),
+                y: T::one(),
+                z: T::zero(),
+            },
+            field_of_view: field_of_view_degrees.to_radians(),
+            aspect_ratio,
+            aperture: T::zero(),
+            focus_distance: T::one(),
+            shutter_open: T::zero(),
+            shutter_close: T::zero(),
+        }
+    }
+
+    /// Primary ray through the image plane point `(x, y)`, where `(0, 0)` is the top left
+    /// corner of the image and `(1, 1)` is the bottom right one.
+    pub fn ray(&self, x: T, y: T) -> Ray<T> {
+        let half = T::from(0.5).unwrap();
+        self.lens_ray(x, y, half, half)
+    }
+
+    /// Primary ray through the image plane point `(x, y)` starting at the lens point
+    /// `(lens_x, lens_y)` from the unit square, which is mapped onto the lens disk.
+    pub fn lens_ray(&self, x: T, y: T, lens_x: T, lens_y: T) -> Ray<T> {
+        let two = T::one() + T::one();
+        let half_height = (self.field_of_view / two).tan();
+        let half_width = half_height * self.aspect_ratio;
+
+        let forward = (self.look_at - self.position).normalize();
+        let right = forward.cross(self.up).normalize();
+        let up = right.cross(forward);
+
+        let direction = forward
+            + right * ((x * two - T::one()) * half_width)
+            + up * ((T::one() - y * two) * half_height);
+        if self.aperture == T::zero() {
+            return Ray {
+                origin: self.position,
+                direction: direction.normalize(),
+                time: self.shutter_open,
+            };
+        }
+
+        let focus_point = self.position + direction * self.focus_distance;
+        let (disk_x, disk_y) = concentric_disk(lens_x, lens_y);
+        let origin =
+            self.position + right * (disk_x * self.aperture) + up * (disk_y * self.aperture);
+        Ray {
+            origin,
+            direction: (focus_point - origin).normalize(),
+            time: self.shutter_open,
+        }
+    }
+}
+
+/// Maps the unit square onto the unit disk preserving relative areas.
+fn concentric_disk<T: Real>(x: T, y: T) -> (T, T) {
+    let two = T::one() + T::one();
+    let a = x * two - T::one();
+    let b = y * two - T::one();
+    if a == T::zero() && b == T::zero() {
+        return (T::zero(), T::zero());
+    }
+    let quarter_pi = T::from(std::f64::consts::FRAC_PI_4).unwrap();
+    let (radius, angle) = if a.abs() > b.abs() {
+        (a, quarter_pi * (b / a))
+    } else {
+        (b, quarter_pi * (two - a / b))
+    };
+    (radius * angle.cos(), radius * angle.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vec3D<f64>, expected: Vec3D<f64>) {
+        assert!(
+            (actual - expected).norm() < 1e-9,
+            "expected ({}, {}, {}), got ({}, {}, {})",
+            expected.x,
+            expected.y,
+            expected.z,
+            actual.x,
+            actual.y,
+            actual.z
+        );
+    }
+
+    #[test]
+    fn test_default_camera() {
+        let camera = Camera::new(90.0, 2.0);
+        assert_close(
+            camera.ray(0.5, 0.5).direction,
+            Vec3D::from([0.0, 0.0, -1.0]),
+        );
+        assert_close(
+            camera.ray(0.5, 0.0).direction,
+            Vec3D::from([0.0, 1.0, -1.0]).normalize(),
+        );
+        assert_close(
+            camera.ray(1.0, 0.5).direction,
+            Vec3D::from([2.0, 0.0, -1.0]).normalize(),
+        );
+    }
+
+    #[test]
+    fn test_lens_rays_meet_in_focus() {
+        let camera = Camera {
+            aperture: 0.5,
+            focus_distance: 10.0,
+            ..Camera::new(60.0, 1.5)
+        };
+        let pinhole = camera.ray(0.3, 0.8);
+        let focus_point =
+            pinhole.point_at(10.0 / (pinhole.direction * Vec3D::from([0.0, 0.0, -1.0])));
+        for (lens_x, lens_y) in &[(0.0, 0.0), (1.0, 0.25), (0.7, 0.9)] {
+            let ray = camera.lens_ray(0.3, 0.8, *lens_x, *lens_y);
+            assert!((ray.origin - camera.position).norm() <= 0.5 + 1e-9);
+            assert!(ray.origin.z.abs() < 1e-9);
+            let distance = (focus_point - ray.origin).norm();
+            assert_close(ray.point_at(distance), focus_point);
+        }
+    }
+
+    #[test]
+    fn test_look_at() {
+        let camera = Camera {
+            position: Vec3D::from([10.0, 0.0, 0.0]),
+            look_at: Vec3D::from([0.0, 0.0, 0.0]),
+            ..Camera::new(90.0, 1.0)
+        };
+        let center = camera.ray(0.5, 0.5);
+        assert_close(center.origin, Vec3D::from([10.0, 0.0, 0.0]));
+        assert_close(center.direction, Vec3D::from([-1.0, 0.0, 0.0]));
+        assert_close(
+            camera.ray(1.0, 0.5).direction,
+            Vec3D::from([-1.0, 0.0, -1.0]).normalize(),
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+use crate::math::threshold;
+use crate::math::vec3d::Vec3D;
+use crate::render::hittable::{Hit, Hittable, Span};
+use crate::render::scene::IntersectResult;
+use num_traits::real::Real;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Union,
+    Intersection,
+    /// The left object with the inside of the right one carved out.
+    Difference,
+}
+
+impl Operation {
+    fn contains(self, left: bool, right: bool) -> bool {
+        match self {
+            Operation::Union => left || right,
+            Operation::Intersection => left && right,
+            Operation::Difference => left && !right,
+        }
+    }
+}
+
+/// Solid combining the insides of two objects by a boolean operation.
+///
+/// The lowest bit of the element of a hit tells whether it lies on the left or on the
+/// right object, the remaining bits hold the element of the hit of that object.
+pub struct Csg<T: Real> {
+    pub operation: Operation,
+    pub left: Arc<Hittable<T>>,
+    pub right: Arc<Hittable<T>>,
+}
+
+impl<T: Real> Csg<T> {
+    pub fn intersect(&self, ray: Ray<T>) -> Option<Hit<T>> {
+        self.spans(ray)
+            .into_iter()
+            .flat_map(|span| [span.enter, span.exit])
+            .find(|hit| hit.distance >= threshold() && hit.distance != T::max_value())
+    }
+
+    pub fn spans(&self, ray: Ray<T>) -> Vec<Span<T>> {
+        let mut boundaries = Vec::new();
+        for (side, object) in [&self.left, &self.right].iter().enumerate() {
+            for span in object.spans(ray) {
+                for hit in [span.enter, span.exit] {
+                    let hit = Hit {
+                        element: hit.element * 2 + side,
+                        ..hit
+                    };
+                    boundaries.push((hit, side));
+                }
+            }
+        }
+        boundaries.sort_by(|(first, _), (second, _)| {
+            first
+                .distance
+                .partial_cmp(&second.distance)
+                .unwrap_or(Ordering::Equal)
+        });
+
+        let mut inside = [false, false];
+        let mut enter = None;
+        let mut spans = Vec::new();
+        for (hit, side) in boundaries {
+            inside[side] = !inside[side];
+            match (enter, self.operation.contains(inside[0], inside[1])) {
+                (None, true) => enter = Some(hit),
+                (Some(enter_hit), false) => {
+                    spans.push(Span {
+                        enter: enter_hit,
+                        exit: hit,
+                    });
+                    enter = None;
+                }
+                _ => {}
+            }
+        }
+        spans
+    }
+
+    pub fn intersection_result(&self, ray: Ray<T>, hit: Hit<T>) -> IntersectResult<T> {
+        let side = hit.element % 2;
+        let object = if side == 0 { &self.left } else { &self.right };
+        let object_hit = Hit {
+            element: hit.element / 2,
+            ..hit
+        };
+        let mut result = object.intersection_result(ray, object_hit);
+        if self.operation == Operation::Difference && side == 1 {
+            result.normal = -result.normal;
+        }
+        result
+    }
+
+    pub fn bounding_box(&self) -> Option<Aabb<T>> {
+        let left = self.left.bounding_box();
+        let right = self.right.bounding_box();
+        match self.operation {
+            Operation::Union => Some(left?.union(right?)),
+            Operation::Intersection => match (left, right) {
+                (Some(left), Some(right)) => Some(Aabb {
+                    min: Vec3D {
+                        x: left.min.x.max(right.min.x),
+                        y: left.min.y.max(right.min.y),
+                        z: left.min.z.max(right.min.z),
+                    },
+                    max: Vec3D {
+                        x: left.max.x.min(right.max.x),
+                        y: left.max.y.min(right.max.y),
+                        z: left.max.z.min(right.max.z),
+                    },
+                }),
+                (bounds, None) | (None, bounds) => bounds,
+            },
+            Operation::Difference => left,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::shape::{Plane, Shape, Sphere};
+    use crate::render::material::Material;
+
+    fn sphere(center: [f64; 3], radius: f64) -> Arc<Hittable<f64>> {
+        Arc::new(Hittable::Primitive {
+            shape: Shape::Sphere(Sphere {
+                center: Vec3D::from(center),
+                radius,
+            }),
+            material: Material::default().into(),
+        })
+    }
+
+    fn csg(operation: Operation, left: Arc<Hittable<f64>>, right: Arc<Hittable<f64>>) -> Csg<f64> {
+        Csg {
+            operation,
+            left,
+            right,
+        }
+    }
+
+    fn ray(origin: [f64; 3], direction: [f64; 3]) -> Ray<f64> {
+        Ray {
+            origin: Vec3D::from(origin),
+            direction: Vec3D::from(direction).normalize(),
+            time: 0.0,
+        }
+    }
+
+    fn assert_hit(csg: &Csg<f64>, ray: Ray<f64>, distance: f64, normal: [f64; 3]) {
+        let hit = csg.intersect(ray).expect("expected a hit");
+        assert!((hit.distance - distance).abs() < 1e-9, "{}", hit.distance);
+        let result = csg.intersection_result(ray, hit);
+        assert!((result.normal - Vec3D::from(normal)).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_union() {
+        let union = csg(
+            Operation::Union,
+            sphere([-1.0, 0.0, 0.0], 2.0),
+            sphere([1.0, 0.0, 0.0], 2.0),
+        );
+        let along = ray([-5.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        assert_eq!(union.spans(along).len(), 1);
+        assert_hit(&union, along, 2.0, [-1.0, 0.0, 0.0]);
+        // The boundaries inside the other sphere are skipped.
+        assert_hit(
+            &union,
+            ray([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]),
+            3.0,
+            [1.0, 0.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn test_intersection() {
+        // A lens between two overlapping spheres.
+        let lens = csg(
+            Operation::Intersection,
+            sphere([0.0, 0.0, -3.0], 4.0),
+            sphere([0.0, 0.0, 3.0], 4.0),
+        );
+        assert_hit(
+            &lens,
+            ray([0.0, 0.0, 5.0], [0.0, 0.0, -1.0]),
+            4.0,
+            [0.0, 0.0, 1.0],
+        );
+        assert_hit(
+            &lens,
+            ray([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]),
+            4.0,
+            [0.0, 0.0, -1.0],
+        );
+        assert!(lens
+            .intersect(ray([3.0, 0.0, 5.0], [0.0, 0.0, -1.0]))
+            .is_none());
+        let bounds = lens.bounding_box().unwrap();
+        assert_eq!((bounds.min.z, bounds.max.z), (-1.0, 1.0));
+    }
+
+    #[test]
+    fn test_difference() {
+        // A hollow sphere cut open by a plane.
+        let hollow = Arc::new(Hittable::Csg(csg(
+            Operation::Difference,
+            sphere([0.0, 0.0, 0.0], 2.0),
+            sphere([0.0, 0.0, 0.0], 1.0),
+        )));
+        let through = ray([0.0, 0.0, 5.0], [0.0, 0.0, -1.0]);
+        assert_eq!(hollow.spans(through).len(), 2);
+        let plane = Arc::new(Hittable::Primitive {
+            shape: Shape::Plane(Plane {
+                origin: Vec3D::from([0.0, 0.0, 0.5]),
+                normal: Vec3D::from([0.0, 0.0, -1.0]),
+            }),
+            material: Material::default().into(),
+        });
+        let bowl = csg(Operation::Difference, hollow, plane);
+        // The cut removes the near half, so the ray passes the hole to the inner wall.
+        assert_hit(&bowl, through, 6.0, [0.0, 0.0, 1.0]);
+        assert_hit(
+            &bowl,
+            ray([1.5, 0.0, 5.0], [0.0, 0.0, -1.0]),
+            4.5,
+            [0.0, 0.0, 1.0],
+        );
+        assert_hit(
+            &bowl,
+            ray([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]),
+            3.0,
+            [0.0, 0.0, -1.0],
+        );
+        assert!(bowl.bounding_box().is_some());
+    }
+}
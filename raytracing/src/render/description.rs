@@ -0,0 +1,2285 @@
+use crate::math::color::Color;
+use crate::math::matrix::Matrix4;
+use crate::math::shape::{
+    Cone, Cuboid, Cylinder, Disk, Plane, Rectangle, Shape, Sphere, Torus, Triangle,
+};
+use crate::math::vec3d::Vec3D;
+use crate::render::animation::{Animation, KeyValue};
+use crate::render::camera::Camera;
+use crate::render::csg::{Csg, Operation};
+use crate::render::environment::{Environment, EnvironmentImage, Sky};
+use crate::render::hittable::Hittable;
+use crate::render::instance::Instance;
+use crate::render::integrator::Integrator;
+use crate::render::lightning::{Attenuation, Light, LightShape, Shadows};
+use crate::render::material::{Fresnel, Material, NormalMap, TexturedMaterial};
+use crate::render::mesh::Mesh;
+use crate::render::motion::Motion;
+use crate::render::obj::{self, ObjError};
+use crate::render::sampling::Sampling;
+use crate::render::scene::Scene;
+use crate::render::texture::{
+    Checker, Gradient, ImageTexture, Noise, Perlin, Solid, Stripes, Texture,
+};
+use crate::render::tone_mapping::ToneMapping;
+use crate::render::volume::{Fog, Volume};
+use num_traits::real::Real;
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{self, DeserializeOwned, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::error::Error;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{fmt, fs, io};
+
+/// Contents of a TOML scene file: the scene itself plus the frame it is meant to be rendered to.
+#[derive(Deserialize, Serialize)]
+#[serde(
+    deny_unknown_fields,
+    bound(
+        deserialize = "T: Real + Deserialize<'de>",
+        serialize = "T: Real + Serialize"
+    )
+)]
+pub struct SceneDescription<T: Real> {
+    #[serde(default)]
+    pub frame: FrameDescription,
+    #[serde(default)]
+    pub camera: CameraDescription<T>,
+    #[serde(default)]
+    pub sampling: Sampling,
+    #[serde(default)]
+    pub integrator: Integrator,
+    #[serde(default)]
+    pub shadows: Shadows,
+    #[serde(default)]
+    pub tone_mapping: ToneMapping,
+    #[serde(default = "Color::zero")]
+    pub background_color: Color<T>,
+    /// Light around the scene, replacing the `background_color` when given.
+    pub environment: Option<EnvironmentDescription<T>>,
+    #[serde(default = "default_recursion_depth")]
+    pub recursion_depth: i32,
+    /// Material presets, which cannot refer to other presets.
+    #[serde(default)]
+    pub materials: HashMap<String, MaterialDescription<T>>,
+    /// Objects placed in the scene only by instances referring to them by name.
+    #[serde(default)]
+    pub prototypes: HashMap<String, ObjectDescription<T>>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDescription<T>>,
+    #[serde(default)]
+    pub lights: Vec<Light<T>>,
+    pub fog: Option<Fog<T>>,
+    #[serde(default)]
+    pub volumes: Vec<VolumeDescription<T>>,
+    /// Keyframes turning the description into a sequence of frames.
+    pub animation: Option<Animation>,
+    /// Directory relative paths in the description are resolved against,
+    /// the directory of the scene file when loaded from one.
+    #[serde(skip)]
+    pub base_directory: PathBuf,
+}
+
+#[derive(Copy, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrameDescription {
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Copy, Clone, Deserialize, Serialize)]
+#[serde(
+    default,
+    deny_unknown_fields,
+    bound(
+        deserialize = "T: Real + Deserialize<'de>",
+        serialize = "T: Real + Serialize"
+    )
+)]
+pub struct CameraDescription<T: Real> {
+    pub position: Vec3D<T>,
+    pub look_at: Vec3D<T>,
+    pub up: Vec3D<T>,
+    /// Vertical field of view in degrees.
+    pub field_of_view: T,
+    /// Defaults to the aspect ratio of the frame.
+    pub aspect_ratio: Option<T>,
+    pub aperture: T,
+    /// Defaults to the distance to the `look_at` point.
+    pub focus_distance: Option<T>,
+    /// Times within the frame, from `0` to `1`, the shutter opens and closes at.
+    pub shutter_open: T,
+    pub shutter_close: T,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    deny_unknown_fields,
+    bound(
+        deserialize = "T: Real + Deserialize<'de>",
+        serialize = "T: Real + Serialize"
+    )
+)]
+pub enum ObjectDescription<T: Real> {
+    Sphere {
+        center: Vec3D<T>,
+        radius: T,
+        #[serde(deserialize_with = "material_reference")]
+        material: MaterialDescription<T>,
+    },
+    Plane {
+        origin: Vec3D<T>,
+        normal: Vec3D<T>,
+        #[serde(deserialize_with = "material_reference")]
+        material: MaterialDescription<T>,
+    },
+    /// Square of `width` spanned from `origin` along the X axis and the direction
+    /// perpendicular to it within the plane, with squares of the checker color
+    /// alternating with the material.
+    CheckerBoard {
+        origin: Vec3D<T>,
+        normal: Vec3D<T>,
+        width: T,
+        #[serde(deserialize_with = "material_reference")]
+        material: MaterialDescription<T>,
+        checker_color: Color<T>,
+        checker_size: T,
+    },
+    Triangle {
+        vertices: [Vec3D<T>; 3],
+        /// Vertex normals interpolated across the triangle.
+        normals: Option<[Vec3D<T>; 3]>,
+        /// Texture coordinates of the vertices, barycentric coordinates otherwise.
+        uvs: Option<[(T, T); 3]>,
+        #[serde(deserialize_with = "material_reference")]
+        material: MaterialDescription<T>,
+    },
+    /// Parallelogram spanned by two edges from a corner.
+    Rectangle {
+        corner: Vec3D<T>,
+        u_edge: Vec3D<T>,
+        v_edge: Vec3D<T>,
+        #[serde(deserialize_with = "material_reference")]
+        material: MaterialDescription<T>,
+    },
+    #[serde(rename = "box")]
+    Cuboid {
+        min: Vec3D<T>,
+        max: Vec3D<T>,
+        #[serde(deserialize_with = "material_reference")]
+        material: MaterialDescription<T>,
+    },
+    Disk {
+        center: Vec3D<T>,
+        normal: Vec3D<T>,
+        radius: T,
+        #[serde(deserialize_with = "material_reference")]
+        material: MaterialDescription<T>,
+    },
+    Cylinder {
+        base: Vec3D<T>,
+        axis: Vec3D<T>,
+        radius: T,
+        height: T,
+        #[serde(deserialize_with = "material_reference")]
+        material: MaterialDescription<T>,
+    },
+    Cone {
+        base: Vec3D<T>,
+        axis: Vec3D<T>,
+        radius: T,
+        height: T,
+        #[serde(deserialize_with = "material_reference")]
+        material: MaterialDescription<T>,
+    },
+    Torus {
+        center: Vec3D<T>,
+        axis: Vec3D<T>,
+        major_radius: T,
+        minor_radius: T,
+        #[serde(deserialize_with = "material_reference")]
+        material: MaterialDescription<T>,
+    },
+    /// Object placed by scaling, rotating and then translating it.
+    Instance {
+        #[serde(deserialize_with = "object_reference")]
+        object: ObjectReference<T>,
+        #[serde(default)]
+        translate: Vec3D<T>,
+        /// Rotation in degrees around the X, then Y and then Z axis.
+        #[serde(default)]
+        rotate: Vec3D<T>,
+        #[serde(default = "default_scale")]
+        scale: Vec3D<T>,
+    },
+    /// Object moving in a straight line during the frame, translated by `start` when
+    /// it begins and by `end` when it ends.
+    Moving {
+        #[serde(deserialize_with = "object_reference")]
+        object: ObjectReference<T>,
+        #[serde(default)]
+        start: Vec3D<T>,
+        end: Vec3D<T>,
+    },
+    /// Solid combining the insides of two objects, only primitives enclose any.
+    Csg {
+        operation: Operation,
+        #[serde(deserialize_with = "object_reference")]
+        left: ObjectReference<T>,
+        #[serde(deserialize_with = "object_reference")]
+        right: ObjectReference<T>,
+    },
+    Mesh {
+        /// Wavefront OBJ file, relative to the scene file.
+        path: PathBuf,
+        /// Whether vertex normals of the file are used, flat faces otherwise.
+        #[serde(default = "default_smooth")]
+        smooth: bool,
+        #[serde(deserialize_with = "material_reference")]
+        material: MaterialDescription<T>,
+    },
+}
+
+/// Participating medium filling a sphere or a box, see `Volume`.
+#[derive(Deserialize, Serialize)]
+#[serde(
+    deny_unknown_fields,
+    bound(
+        deserialize = "T: Real + Deserialize<'de>",
+        serialize = "T: Real + Serialize"
+    )
+)]
+pub struct VolumeDescription<T: Real> {
+    pub boundary: BoundaryDescription<T>,
+    #[serde(default = "Color::zero")]
+    pub absorption: Color<T>,
+    #[serde(default = "Color::zero")]
+    pub scattering: Color<T>,
+    #[serde(default = "T::zero")]
+    pub anisotropy: T,
+    #[serde(default = "default_volume_steps")]
+    pub steps: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    deny_unknown_fields,
+    bound(
+        deserialize = "T: Real + Deserialize<'de>",
+        serialize = "T: Real + Serialize"
+    )
+)]
+pub enum BoundaryDescription<T: Real> {
+    Sphere {
+        center: Vec3D<T>,
+        radius: T,
+    },
+    #[serde(rename = "box")]
+    Cuboid {
+        min: Vec3D<T>,
+        max: Vec3D<T>,
+    },
+}
+
+/// Light around the scene, see `Environment`. Image paths are relative to the scene
+/// file, Radiance `.hdr` images keep their linear colors.
+#[derive(Deserialize, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    deny_unknown_fields,
+    bound(
+        deserialize = "T: Real + Deserialize<'de>",
+        serialize = "T: Real + Serialize"
+    )
+)]
+pub enum EnvironmentDescription<T: Real> {
+    Solid {
+        color: Color<T>,
+    },
+    Gradient {
+        top: Color<T>,
+        bottom: Color<T>,
+    },
+    Sky {
+        /// Direction towards the sun, above the horizon.
+        sun_direction: Vec3D<T>,
+        /// Haziness of the air, from `2` for a very clear to `10` for a hazy sky.
+        #[serde(default = "default_turbidity")]
+        turbidity: T,
+        /// Luminance of the sky at the zenith.
+        #[serde(default = "default_strength")]
+        intensity: T,
+    },
+    Equirectangular {
+        path: PathBuf,
+        #[serde(default = "default_strength")]
+        intensity: T,
+        /// Angle in degrees the image is turned by around the `y` axis.
+        #[serde(default = "T::zero")]
+        rotation: T,
+    },
+    CubeMap {
+        positive_x: PathBuf,
+        negative_x: PathBuf,
+        positive_y: PathBuf,
+        negative_y: PathBuf,
+        positive_z: PathBuf,
+        negative_z: PathBuf,
+        #[serde(default = "default_strength")]
+        intensity: T,
+    },
+}
+
+/// Object used by an instance or a CSG solid: either a name from the `prototypes` table or an inline object.
+pub enum ObjectReference<T: Real> {
+    Prototype(String),
+    Inline(Box<ObjectDescription<T>>),
+}
+
+/// Material of an object: either a bare preset name or a table with an optional
+/// `preset` and any fields overriding it.
+#[derive(Deserialize, Serialize)]
+#[serde(
+    deny_unknown_fields,
+    bound(
+        deserialize = "T: Real + Deserialize<'de>",
+        serialize = "T: Real + Serialize"
+    )
+)]
+pub struct MaterialDescription<T: Real> {
+    pub preset: Option<String>,
+    pub ambient: Option<ColorSource<T>>,
+    pub diffuse: Option<ColorSource<T>>,
+    pub specular: Option<ColorSource<T>>,
+    pub shininess: Option<T>,
+    pub reflectiveness: Option<T>,
+    pub transparency: Option<T>,
+    pub refractive_index: Option<T>,
+    pub absorption: Option<Color<T>>,
+    pub fresnel: Option<Fresnel>,
+    pub normal_map: Option<NormalMapDescription<T>>,
+}
+
+/// Color channel of a material: either a constant color or a texture table.
+pub enum ColorSource<T: Real> {
+    Color(Color<T>),
+    Texture(Box<TextureDescription<T>>),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    deny_unknown_fields,
+    bound(
+        deserialize = "T: Real + Deserialize<'de>",
+        serialize = "T: Real + Serialize"
+    )
+)]
+pub enum TextureDescription<T: Real> {
+    Checker {
+        even: ColorSource<T>,
+        odd: ColorSource<T>,
+        /// Squares per unit of the texture coordinates.
+        #[serde(default = "default_texture_scale")]
+        scale: T,
+    },
+    Stripes {
+        even: ColorSource<T>,
+        odd: ColorSource<T>,
+        /// Stripes per unit of the `u` coordinate.
+        #[serde(default = "default_texture_scale")]
+        scale: T,
+    },
+    Gradient {
+        start: Color<T>,
+        end: Color<T>,
+    },
+    /// Perlin noise over the position of the point rather than its texture coordinates.
+    Noise {
+        low: Color<T>,
+        high: Color<T>,
+        #[serde(default = "default_texture_scale")]
+        scale: T,
+        #[serde(default = "default_octaves")]
+        octaves: usize,
+        #[serde(default)]
+        seed: u64,
+    },
+    Image {
+        /// Image file, relative to the scene file.
+        path: PathBuf,
+    },
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    deny_unknown_fields,
+    bound(
+        deserialize = "T: Real + Deserialize<'de>",
+        serialize = "T: Real + Serialize"
+    )
+)]
+pub enum NormalMapDescription<T: Real> {
+    /// Tangent-space normal map image, relative to the scene file.
+    Image {
+        path: PathBuf,
+        #[serde(default = "default_strength")]
+        strength: T,
+    },
+    /// Bump map with the height given by the brightness of a texture.
+    Bump {
+        height: TextureDescription<T>,
+        #[serde(default = "default_strength")]
+        strength: T,
+    },
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse(toml::de::Error),
+    UnknownMaterial {
+        field: String,
+        name: String,
+    },
+    UnknownPrototype {
+        field: String,
+        name: String,
+    },
+    InvalidValue {
+        field: String,
+        message: String,
+    },
+    Mesh {
+        field: String,
+        path: PathBuf,
+        source: ObjError,
+    },
+    Texture {
+        field: String,
+        path: PathBuf,
+        source: image::ImageError,
+    },
+}
+
+/// Presets and prototypes built once and shared by the objects referring to them.
+struct Resources<'a, T: Real> {
+    base_directory: &'a Path,
+    presets: HashMap<String, TexturedMaterial<T>>,
+    prototypes: HashMap<String, Arc<Hittable<T>>>,
+}
+
+impl Default for FrameDescription {
+    fn default() -> Self {
+        FrameDescription {
+            width: 1024,
+            height: 768,
+        }
+    }
+}
+
+impl<T: Real> Default for CameraDescription<T> {
+    fn default() -> Self {
+        let camera = Camera::new(T::from(60.0).unwrap(), T::one());
+        CameraDescription {
+            position: camera.position,
+            look_at: camera.look_at,
+            up: camera.up,
+            field_of_view: camera.field_of_view.to_degrees(),
+            aspect_ratio: None,
+            aperture: camera.aperture,
+            focus_distance: None,
+            shutter_open: camera.shutter_open,
+            shutter_close: camera.shutter_close,
+        }
+    }
+}
+
+fn default_recursion_depth() -> i32 {
+    4
+}
+
+fn default_volume_steps() -> usize {
+    16
+}
+
+fn default_turbidity<T: Real>() -> T {
+    T::from(3).unwrap()
+}
+
+fn default_smooth() -> bool {
+    true
+}
+
+fn default_scale<T: Real>() -> Vec3D<T> {
+    Vec3D::from([T::one(); 3])
+}
+
+fn default_texture_scale<T: Real>() -> T {
+    T::one()
+}
+
+fn default_octaves() -> usize {
+    4
+}
+
+fn default_strength<T: Real>() -> T {
+    T::one()
+}
+
+impl<T: Real + Serialize + DeserializeOwned> SceneDescription<T> {
+    pub fn load<Q: AsRef<Path>>(path: Q) -> Result<SceneDescription<T>, SceneError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|source| SceneError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        let mut description = SceneDescription::parse(&source)?;
+        description.base_directory = path.parent().map(Path::to_owned).unwrap_or_default();
+        Ok(description)
+    }
+
+    pub fn parse(source: &str) -> Result<SceneDescription<T>, SceneError> {
+        let description: SceneDescription<T> = toml::from_str(source)?;
+        description.validate()?;
+        if let Some(ref animation) = description.animation {
+            // Every track has to land on a valid value of the description.
+            for time in [0.0, animation.frame_time(animation.frames - 1)] {
+                description.at_time(time)?;
+            }
+        }
+        Ok(description)
+    }
+
+    /// Copy of the description at the time of the animation, tracks targeting the
+    /// values of its TOML form.
+    pub fn at_time(&self, time: f64) -> Result<SceneDescription<T>, SceneError> {
+        let mut value = toml::Value::try_from(self)
+            .map_err(|error| invalid_value("animation", &error.to_string()))?;
+        if let Some(ref animation) = self.animation {
+            animation
+                .apply(&mut value, time)
+                .map_err(|message| invalid_value("animation.tracks", &message))?;
+        }
+        let mut description: SceneDescription<T> = value.try_into()?;
+        description.validate()?;
+        description.base_directory = self.base_directory.clone();
+        Ok(description)
+    }
+
+    /// Description of the frame of the animation, numbered from zero.
+    pub fn frame(&self, index: usize) -> Result<SceneDescription<T>, SceneError> {
+        let time = match self.animation {
+            Some(ref animation) => animation.frame_time(index),
+            None => 0.0,
+        };
+        self.at_time(time)
+    }
+}
+
+impl<T: Real + Send + Sync + 'static> SceneDescription<T> {
+    pub fn scene(&self) -> Result<Scene<T>, SceneError> {
+        let mut resources = Resources {
+            base_directory: &self.base_directory,
+            presets: HashMap::new(),
+            prototypes: HashMap::new(),
+        };
+        for (name, material) in &self.materials {
+            let material = material.resolve(&resources, &format!("materials.{}", name))?;
+            resources.presets.insert(name.clone(), material);
+        }
+        // Prototypes are built once and shared by all their instances, they cannot
+        // refer to other prototypes.
+        let prototypes = self
+            .prototypes
+            .iter()
+            .map(|(name, object)| {
+                let hittable = object.hittable(&resources, &format!("prototypes.{}", name))?;
+                Ok((name.clone(), Arc::new(hittable)))
+            })
+            .collect::<Result<HashMap<_, _>, SceneError>>()?;
+        resources.prototypes = prototypes;
+        let objects = self
+            .objects
+            .iter()
+            .enumerate()
+            .map(|(index, object)| object.hittable(&resources, &format!("objects[{}]", index)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut scene = Scene::new(
+            self.background_color,
+            objects,
+            self.lights.clone(),
+            self.recursion_depth,
+        );
+        scene.shadows = self.shadows;
+        scene.fog = self.fog;
+        scene.volumes = self.volumes.iter().map(VolumeDescription::volume).collect();
+        if let Some(ref environment) = self.environment {
+            scene.environment = environment.environment(&self.base_directory, "environment")?;
+        }
+        Ok(scene)
+    }
+}
+
+impl<T: Real> SceneDescription<T> {
+    pub fn camera(&self) -> Camera<T> {
+        let CameraDescription {
+            position,
+            look_at,
+            up,
+            field_of_view,
+            aspect_ratio,
+            aperture,
+            focus_distance,
+            shutter_open,
+            shutter_close,
+        } = self.camera;
+        let frame_aspect_ratio = self.frame.width as f64 / self.frame.height as f64;
+        Camera {
+            position,
+            look_at,
+            up,
+            field_of_view: field_of_view.to_radians(),
+            aspect_ratio: aspect_ratio.unwrap_or_else(|| T::from(frame_aspect_ratio).unwrap()),
+            aperture,
+            focus_distance: focus_distance.unwrap_or_else(|| (look_at - position).norm()),
+            shutter_open,
+            shutter_close,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), SceneError> {
+        if self.frame.width == 0 || self.frame.height == 0 {
+            return Err(invalid_value("frame", "width and height must be positive"));
+        }
+        let camera = &self.camera;
+        if !(camera.field_of_view > T::zero() && camera.field_of_view < T::from(180.0).unwrap()) {
+            return Err(invalid_value(
+                "camera.field_of_view",
+                "must be between 0 and 180 degrees",
+            ));
+        }
+        if let Some(aspect_ratio) = camera.aspect_ratio {
+            if aspect_ratio <= T::zero() {
+                return Err(invalid_value("camera.aspect_ratio", "must be positive"));
+            }
+        }
+        if camera.aperture < T::zero() {
+            return Err(invalid_value("camera.aperture", "must not be negative"));
+        }
+        if let Some(focus_distance) = camera.focus_distance {
+            if focus_distance <= T::zero() {
+                return Err(invalid_value("camera.focus_distance", "must be positive"));
+            }
+        }
+        if !(camera.shutter_open >= T::zero()
+            && camera.shutter_open <= camera.shutter_close
+            && camera.shutter_close <= T::one())
+        {
+            return Err(invalid_value(
+                "camera",
+                "shutter_open and shutter_close must be ordered times between 0 and 1",
+            ));
+        }
+        let forward = camera.look_at - camera.position;
+        if forward.norm() == T::zero() {
+            return Err(invalid_value(
+                "camera.look_at",
+                "must differ from the camera position",
+            ));
+        }
+        if forward.cross(camera.up).norm() == T::zero() {
+            return Err(invalid_value(
+                "camera.up",
+                "must not be parallel to the view direction",
+            ));
+        }
+        if self.sampling.samples == 0 || self.sampling.adaptive_samples == 0 {
+            return Err(invalid_value(
+                "sampling",
+                "samples and adaptive_samples must be positive",
+            ));
+        }
+        if let Some(threshold) = self.sampling.adaptive_threshold {
+            if threshold < 0.0 {
+                return Err(invalid_value(
+                    "sampling.adaptive_threshold",
+                    "must not be negative",
+                ));
+            }
+        }
+        if let Integrator::PathTracing {
+            samples_per_pixel, ..
+        } = self.integrator
+        {
+            if samples_per_pixel == 0 {
+                return Err(invalid_value(
+                    "integrator.samples_per_pixel",
+                    "must be positive",
+                ));
+            }
+        }
+        if !self.tone_mapping.exposure.is_finite() {
+            return Err(invalid_value("tone_mapping.exposure", "must be finite"));
+        }
+        if self.recursion_depth < 0 {
+            return Err(invalid_value("recursion_depth", "must not be negative"));
+        }
+        for (name, material) in &self.materials {
+            if material.preset.is_some() {
+                return Err(invalid_value(
+                    &format!("materials.{}.preset", name),
+                    "presets cannot refer to other presets",
+                ));
+            }
+        }
+        for (index, light) in self.lights.iter().enumerate() {
+            validate_light(light, &format!("lights[{}]", index))?;
+        }
+        if let Some(fog) = self.fog {
+            if fog.density < T::zero() {
+                return Err(invalid_value("fog.density", "must not be negative"));
+            }
+        }
+        for (index, volume) in self.volumes.iter().enumerate() {
+            volume.validate(&format!("volumes[{}]", index))?;
+        }
+        if let Some(ref environment) = self.environment {
+            environment.validate("environment")?;
+        }
+        if let Some(ref animation) = self.animation {
+            validate_animation(animation)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Real + Send + Sync + 'static> ObjectDescription<T> {
+    fn hittable(&self, resources: &Resources<T>, field: &str) -> Result<Hittable<T>, SceneError> {
+        Ok(match self {
+            ObjectDescription::Sphere {
+                center,
+                radius,
+                material,
+            } => Hittable::Primitive {
+                shape: Shape::Sphere(Sphere {
+                    center: *center,
+                    radius: positive(*radius, field, "radius")?,
+                }),
+                material: material.resolve(resources, field)?,
+            },
+            ObjectDescription::Plane {
+                origin,
+                normal,
+                material,
+            } => Hittable::Primitive {
+                shape: Shape::Plane(Plane {
+                    origin: *origin,
+                    normal: unit_vector(*normal, field, "normal")?,
+                }),
+                material: material.resolve(resources, field)?,
+            },
+            ObjectDescription::CheckerBoard {
+                origin,
+                normal,
+                width,
+                material,
+                checker_color,
+                checker_size,
+            } => {
+                let normal = unit_vector(*normal, field, "normal")?;
+                let width = positive(*width, field, "width")?;
+                let checker_size = positive(*checker_size, field, "checker_size")?;
+                let x_axis = Vec3D::from([T::one(), T::zero(), T::zero()]);
+                let mut direction = x_axis - normal * (x_axis * normal);
+                if direction.norm() <= T::epsilon() {
+                    direction = Vec3D::from([T::zero(), T::zero(), T::one()]);
+                }
+                let v_edge = direction.normalize() * width;
+                let mut material = material.resolve(resources, field)?;
+                let base_diffuse = material.diffuse.take().unwrap_or_else(|| {
+                    Arc::new(Solid {
+                        color: material.base.diffuse,
+                    })
+                });
+                material.diffuse = Some(Arc::new(Checker {
+                    even: Arc::new(Solid {
+                        color: *checker_color,
+                    }),
+                    odd: base_diffuse,
+                    scale: width / checker_size,
+                }));
+                Hittable::Primitive {
+                    shape: Shape::Rectangle(Rectangle {
+                        corner: *origin,
+                        u_edge: v_edge.cross(normal),
+                        v_edge,
+                    }),
+                    material,
+                }
+            }
+            ObjectDescription::Triangle {
+                vertices,
+                normals,
+                uvs,
+                material,
+            } => {
+                let [a, b, c] = *vertices;
+                if (b - a).cross(c - a).norm() == T::zero() {
+                    return Err(invalid_value(
+                        &format!("{}.vertices", field),
+                        "must not be collinear",
+                    ));
+                }
+                let normals = match normals {
+                    Some(normals) => Some([
+                        unit_vector(normals[0], field, "normals")?,
+                        unit_vector(normals[1], field, "normals")?,
+                        unit_vector(normals[2], field, "normals")?,
+                    ]),
+                    None => None,
+                };
+                Hittable::Primitive {
+                    shape: Shape::Triangle(Triangle {
+                        vertices: *vertices,
+                        normals,
+                        uvs: *uvs,
+                    }),
+                    material: material.resolve(resources, field)?,
+                }
+            }
+            ObjectDescription::Rectangle {
+                corner,
+                u_edge,
+                v_edge,
+                material,
+            } => {
+                if u_edge.cross(*v_edge).norm() == T::zero() {
+                    return Err(invalid_value(
+                        &format!("{}.v_edge", field),
+                        "must not be parallel to `u_edge`",
+                    ));
+                }
+                Hittable::Primitive {
+                    shape: Shape::Rectangle(Rectangle {
+                        corner: *corner,
+                        u_edge: *u_edge,
+                        v_edge: *v_edge,
+                    }),
+                    material: material.resolve(resources, field)?,
+                }
+            }
+            ObjectDescription::Cuboid { min, max, material } => {
+                if min.x > max.x || min.y > max.y || min.z > max.z {
+                    return Err(invalid_value(
+                        &format!("{}.max", field),
+                        "must not be below `min` on any axis",
+                    ));
+                }
+                Hittable::Primitive {
+                    shape: Shape::Cuboid(Cuboid {
+                        min: *min,
+                        max: *max,
+                    }),
+                    material: material.resolve(resources, field)?,
+                }
+            }
+            ObjectDescription::Disk {
+                center,
+                normal,
+                radius,
+                material,
+            } => Hittable::Primitive {
+                shape: Shape::Disk(Disk {
+                    center: *center,
+                    normal: unit_vector(*normal, field, "normal")?,
+                    radius: positive(*radius, field, "radius")?,
+                }),
+                material: material.resolve(resources, field)?,
+            },
+            ObjectDescription::Cylinder {
+                base,
+                axis,
+                radius,
+                height,
+                material,
+            } => Hittable::Primitive {
+                shape: Shape::Cylinder(Cylinder {
+                    base: *base,
+                    axis: unit_vector(*axis, field, "axis")?,
+                    radius: positive(*radius, field, "radius")?,
+                    height: positive(*height, field, "height")?,
+                }),
+                material: material.resolve(resources, field)?,
+            },
+            ObjectDescription::Cone {
+                base,
+                axis,
+                radius,
+                height,
+                material,
+            } => Hittable::Primitive {
+                shape: Shape::Cone(Cone {
+                    base: *base,
+                    axis: unit_vector(*axis, field, "axis")?,
+                    radius: positive(*radius, field, "radius")?,
+                    height: positive(*height, field, "height")?,
+                }),
+                material: material.resolve(resources, field)?,
+            },
+            ObjectDescription::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+                material,
+            } => Hittable::Primitive {
+                shape: Shape::Torus(Torus {
+                    center: *center,
+                    axis: unit_vector(*axis, field, "axis")?,
+                    major_radius: positive(*major_radius, field, "major_radius")?,
+                    minor_radius: positive(*minor_radius, field, "minor_radius")?,
+                }),
+                material: material.resolve(resources, field)?,
+            },
+            ObjectDescription::Instance {
+                object,
+                translate,
+                rotate,
+                scale,
+            } => {
+                let object = object.hittable(resources, &format!("{}.object", field))?;
+                let transform = Matrix4::translation(*translate)
+                    * Matrix4::rotation_z(rotate.z.to_radians())
+                    * Matrix4::rotation_y(rotate.y.to_radians())
+                    * Matrix4::rotation_x(rotate.x.to_radians())
+                    * Matrix4::scaling(*scale);
+                let instance = Instance::new(object, transform).ok_or_else(|| {
+                    invalid_value(&format!("{}.scale", field), "must not be zero on any axis")
+                })?;
+                Hittable::Instance(instance)
+            }
+            ObjectDescription::Moving { object, start, end } => Hittable::Moving(Motion::new(
+                object.hittable(resources, &format!("{}.object", field))?,
+                *start,
+                *end,
+            )),
+            ObjectDescription::Csg {
+                operation,
+                left,
+                right,
+            } => Hittable::Csg(Csg {
+                operation: *operation,
+                left: left.hittable(resources, &format!("{}.left", field))?,
+                right: right.hittable(resources, &format!("{}.right", field))?,
+            }),
+            ObjectDescription::Mesh {
+                path,
+                smooth,
+                material,
+            } => {
+                let path = resources.base_directory.join(path);
+                let mut triangles = obj::load(&path).map_err(|source| SceneError::Mesh {
+                    field: field.to_owned(),
+                    path: path.clone(),
+                    source,
+                })?;
+                if !smooth {
+                    for triangle in &mut triangles {
+                        triangle.normals = None;
+                    }
+                }
+                Hittable::Mesh {
+                    mesh: Arc::new(Mesh::new(triangles)),
+                    material: material.resolve(resources, field)?,
+                }
+            }
+        })
+    }
+}
+
+impl<T: Real + Send + Sync + 'static> ObjectReference<T> {
+    fn hittable(
+        &self,
+        resources: &Resources<T>,
+        field: &str,
+    ) -> Result<Arc<Hittable<T>>, SceneError> {
+        match self {
+            ObjectReference::Prototype(name) => {
+                resources.prototypes.get(name).cloned().ok_or_else(|| {
+                    SceneError::UnknownPrototype {
+                        field: field.to_owned(),
+                        name: name.clone(),
+                    }
+                })
+            }
+            ObjectReference::Inline(object) => Ok(Arc::new(object.hittable(resources, field)?)),
+        }
+    }
+}
+
+impl<T: Real> VolumeDescription<T> {
+    fn volume(&self) -> Volume<T> {
+        let shape = match self.boundary {
+            BoundaryDescription::Sphere { center, radius } => {
+                Shape::Sphere(Sphere { center, radius })
+            }
+            BoundaryDescription::Cuboid { min, max } => Shape::Cuboid(Cuboid { min, max }),
+        };
+        Volume {
+            boundary: Hittable::Primitive {
+                shape,
+                material: Material::default().into(),
+            },
+            absorption: self.absorption,
+            scattering: self.scattering,
+            anisotropy: self.anisotropy,
+            steps: self.steps,
+        }
+    }
+
+    fn validate(&self, field: &str) -> Result<(), SceneError> {
+        let at = |name: &str| format!("{}.{}", field, name);
+        match self.boundary {
+            BoundaryDescription::Sphere { radius, .. } => {
+                positive(radius, field, "boundary.radius")?;
+            }
+            BoundaryDescription::Cuboid { min, max } => {
+                if !(min.x < max.x && min.y < max.y && min.z < max.z) {
+                    return Err(invalid_value(
+                        &at("boundary.max"),
+                        "must be above `min` on every axis",
+                    ));
+                }
+            }
+        }
+        let non_negative =
+            |color: Color<T>| color.r >= T::zero() && color.g >= T::zero() && color.b >= T::zero();
+        if !non_negative(self.absorption) || !non_negative(self.scattering) {
+            return Err(invalid_value(
+                field,
+                "absorption and scattering must not be negative",
+            ));
+        }
+        if !(self.anisotropy > -T::one() && self.anisotropy < T::one()) {
+            return Err(invalid_value(&at("anisotropy"), "must be between -1 and 1"));
+        }
+        if self.steps == 0 {
+            return Err(invalid_value(&at("steps"), "must be positive"));
+        }
+        Ok(())
+    }
+}
+
+impl<T: Real> EnvironmentDescription<T> {
+    fn environment(
+        &self,
+        base_directory: &Path,
+        field: &str,
+    ) -> Result<Environment<T>, SceneError> {
+        let image = |path: &Path, intensity: T, name: &str| {
+            let path = base_directory.join(path);
+            EnvironmentImage::open(&path, intensity.to_f32().unwrap()).map_err(|source| {
+                SceneError::Texture {
+                    field: format!("{}.{}", field, name),
+                    path: path.clone(),
+                    source,
+                }
+            })
+        };
+        Ok(match self {
+            EnvironmentDescription::Solid { color } => Environment::Solid(*color),
+            EnvironmentDescription::Gradient { top, bottom } => Environment::Gradient {
+                top: *top,
+                bottom: *bottom,
+            },
+            EnvironmentDescription::Sky {
+                sun_direction,
+                turbidity,
+                intensity,
+            } => {
+                let sun_direction = Vec3D {
+                    x: sun_direction.x.to_f64().unwrap(),
+                    y: sun_direction.y.to_f64().unwrap(),
+                    z: sun_direction.z.to_f64().unwrap(),
+                };
+                Environment::Sky(Sky::new(
+                    sun_direction,
+                    turbidity.to_f64().unwrap(),
+                    intensity.to_f64().unwrap(),
+                ))
+            }
+            EnvironmentDescription::Equirectangular {
+                path,
+                intensity,
+                rotation,
+            } => Environment::Equirectangular {
+                image: Arc::new(image(path, *intensity, "path")?),
+                rotation: rotation.to_radians(),
+            },
+            EnvironmentDescription::CubeMap {
+                positive_x,
+                negative_x,
+                positive_y,
+                negative_y,
+                positive_z,
+                negative_z,
+                intensity,
+            } => Environment::CubeMap(Arc::new([
+                image(positive_x, *intensity, "positive_x")?,
+                image(negative_x, *intensity, "negative_x")?,
+                image(positive_y, *intensity, "positive_y")?,
+                image(negative_y, *intensity, "negative_y")?,
+                image(positive_z, *intensity, "positive_z")?,
+                image(negative_z, *intensity, "negative_z")?,
+            ])),
+        })
+    }
+
+    fn validate(&self, field: &str) -> Result<(), SceneError> {
+        let at = |name: &str| format!("{}.{}", field, name);
+        let intensity = match self {
+            EnvironmentDescription::Solid { .. } | EnvironmentDescription::Gradient { .. } => {
+                return Ok(())
+            }
+            EnvironmentDescription::Sky {
+                sun_direction,
+                turbidity,
+                intensity,
+            } => {
+                if unit_vector(*sun_direction, field, "sun_direction")?.y <= T::zero() {
+                    return Err(invalid_value(
+                        &at("sun_direction"),
+                        "must point above the horizon",
+                    ));
+                }
+                let range = T::from(2).unwrap()..=T::from(10).unwrap();
+                if !range.contains(turbidity) {
+                    return Err(invalid_value(&at("turbidity"), "must be between 2 and 10"));
+                }
+                intensity
+            }
+            EnvironmentDescription::Equirectangular { intensity, .. }
+            | EnvironmentDescription::CubeMap { intensity, .. } => intensity,
+        };
+        if *intensity < T::zero() {
+            return Err(invalid_value(&at("intensity"), "must not be negative"));
+        }
+        Ok(())
+    }
+}
+
+impl<T: Real> MaterialDescription<T> {
+    fn preset(name: &str) -> MaterialDescription<T> {
+        MaterialDescription {
+            preset: Some(name.to_owned()),
+            ambient: None,
+            diffuse: None,
+            specular: None,
+            shininess: None,
+            reflectiveness: None,
+            transparency: None,
+            refractive_index: None,
+            absorption: None,
+            fresnel: None,
+            normal_map: None,
+        }
+    }
+}
+
+impl<T: Real + Send + Sync + 'static> MaterialDescription<T> {
+    fn resolve(
+        &self,
+        resources: &Resources<T>,
+        field: &str,
+    ) -> Result<TexturedMaterial<T>, SceneError> {
+        let mut material =
+            match &self.preset {
+                Some(name) => resources.presets.get(name).cloned().ok_or_else(|| {
+                    SceneError::UnknownMaterial {
+                        field: format!("{}.material", field),
+                        name: name.clone(),
+                    }
+                })?,
+                None => Material::default().into(),
+            };
+        let channels = [
+            (
+                &self.ambient,
+                &mut material.base.ambient,
+                &mut material.ambient,
+                "ambient",
+            ),
+            (
+                &self.diffuse,
+                &mut material.base.diffuse,
+                &mut material.diffuse,
+                "diffuse",
+            ),
+            (
+                &self.specular,
+                &mut material.base.specular,
+                &mut material.specular,
+                "specular",
+            ),
+        ];
+        for (source, color, texture, name) in channels {
+            match source {
+                Some(ColorSource::Color(source)) => {
+                    *color = *source;
+                    *texture = None;
+                }
+                Some(ColorSource::Texture(source)) => {
+                    let field = format!("{}.material.{}", field, name);
+                    *texture = Some(source.texture(resources.base_directory, &field)?);
+                }
+                None => {}
+            }
+        }
+        let base = &mut material.base;
+        base.shininess = self.shininess.unwrap_or(base.shininess);
+        base.reflectiveness = self.reflectiveness.unwrap_or(base.reflectiveness);
+        base.transparency = self.transparency.unwrap_or(base.transparency);
+        base.refractive_index = self.refractive_index.unwrap_or(base.refractive_index);
+        base.absorption = self.absorption.unwrap_or(base.absorption);
+        base.fresnel = self.fresnel.unwrap_or(base.fresnel);
+        if let Some(normal_map) = &self.normal_map {
+            let field = format!("{}.material.normal_map", field);
+            material.normal_map = Some(normal_map.normal_map(resources.base_directory, &field)?);
+        }
+        Ok(material)
+    }
+}
+
+impl<T: Real + Send + Sync + 'static> ColorSource<T> {
+    fn texture(
+        &self,
+        base_directory: &Path,
+        field: &str,
+    ) -> Result<Arc<dyn Texture<T>>, SceneError> {
+        match self {
+            ColorSource::Color(color) => Ok(Arc::new(Solid { color: *color })),
+            ColorSource::Texture(texture) => texture.texture(base_directory, field),
+        }
+    }
+}
+
+impl<T: Real + Send + Sync + 'static> TextureDescription<T> {
+    fn texture(
+        &self,
+        base_directory: &Path,
+        field: &str,
+    ) -> Result<Arc<dyn Texture<T>>, SceneError> {
+        Ok(match self {
+            TextureDescription::Checker { even, odd, scale } => Arc::new(Checker {
+                even: even.texture(base_directory, &format!("{}.even", field))?,
+                odd: odd.texture(base_directory, &format!("{}.odd", field))?,
+                scale: positive(*scale, field, "scale")?,
+            }),
+            TextureDescription::Stripes { even, odd, scale } => Arc::new(Stripes {
+                even: even.texture(base_directory, &format!("{}.even", field))?,
+                odd: odd.texture(base_directory, &format!("{}.odd", field))?,
+                scale: positive(*scale, field, "scale")?,
+            }),
+            TextureDescription::Gradient { start, end } => Arc::new(Gradient {
+                start: *start,
+                end: *end,
+            }),
+            TextureDescription::Noise {
+                low,
+                high,
+                scale,
+                octaves,
+                seed,
+            } => Arc::new(Noise {
+                low: *low,
+                high: *high,
+                scale: positive(*scale, field, "scale")?,
+                octaves: *octaves,
+                perlin: Perlin::new(*seed),
+            }),
+            TextureDescription::Image { path } => image_texture(base_directory, path, field)?,
+        })
+    }
+}
+
+impl<T: Real + Send + Sync + 'static> NormalMapDescription<T> {
+    fn normal_map(&self, base_directory: &Path, field: &str) -> Result<NormalMap<T>, SceneError> {
+        let (NormalMapDescription::Image { strength, .. }
+        | NormalMapDescription::Bump { strength, .. }) = self;
+        if !(*strength >= T::zero() && *strength <= T::max_value()) {
+            return Err(invalid_value(
+                &format!("{}.strength", field),
+                "must be finite and not negative",
+            ));
+        }
+        Ok(match self {
+            NormalMapDescription::Image { path, strength } => NormalMap::Tangent {
+                texture: image_texture(base_directory, path, field)?,
+                strength: *strength,
+            },
+            NormalMapDescription::Bump { height, strength } => NormalMap::Bump {
+                height: height.texture(base_directory, &format!("{}.height", field))?,
+                strength: *strength,
+            },
+        })
+    }
+}
+
+fn image_texture<T: Real>(
+    base_directory: &Path,
+    path: &Path,
+    field: &str,
+) -> Result<Arc<dyn Texture<T>>, SceneError> {
+    let path = base_directory.join(path);
+    let image = image::open(&path).map_err(|source| SceneError::Texture {
+        field: field.to_owned(),
+        path: path.clone(),
+        source,
+    })?;
+    Ok(Arc::new(ImageTexture {
+        image: image.to_rgb8(),
+    }))
+}
+
+fn material_reference<'de, D, T>(deserializer: D) -> Result<MaterialDescription<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Real + Deserialize<'de>,
+{
+    struct MaterialVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Real + Deserialize<'de>> Visitor<'de> for MaterialVisitor<T> {
+        type Value = MaterialDescription<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a material preset name or a material table")
+        }
+
+        fn visit_str<E: de::Error>(self, name: &str) -> Result<Self::Value, E> {
+            Ok(MaterialDescription::preset(name))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            MaterialDescription::deserialize(MapAccessDeserializer::new(map))
+        }
+    }
+
+    deserializer.deserialize_any(MaterialVisitor(PhantomData))
+}
+
+impl<'de, T: Real + Deserialize<'de>> Deserialize<'de> for ColorSource<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ColorSourceVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Real + Deserialize<'de>> Visitor<'de> for ColorSourceVisitor<T> {
+            type Value = ColorSource<T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a color or a texture table")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                Color::deserialize(SeqAccessDeserializer::new(seq)).map(ColorSource::Color)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let texture = TextureDescription::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(ColorSource::Texture(Box::new(texture)))
+            }
+        }
+
+        deserializer.deserialize_any(ColorSourceVisitor(PhantomData))
+    }
+}
+
+impl<T: Real + Serialize> Serialize for ColorSource<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ColorSource::Color(color) => color.serialize(serializer),
+            ColorSource::Texture(texture) => texture.serialize(serializer),
+        }
+    }
+}
+
+fn object_reference<'de, D, T>(deserializer: D) -> Result<ObjectReference<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Real + Deserialize<'de>,
+{
+    struct ObjectVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Real + Deserialize<'de>> Visitor<'de> for ObjectVisitor<T> {
+        type Value = ObjectReference<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a prototype name or an object table")
+        }
+
+        fn visit_str<E: de::Error>(self, name: &str) -> Result<Self::Value, E> {
+            Ok(ObjectReference::Prototype(name.to_owned()))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            let object = ObjectDescription::deserialize(MapAccessDeserializer::new(map))?;
+            Ok(ObjectReference::Inline(Box::new(object)))
+        }
+    }
+
+    deserializer.deserialize_any(ObjectVisitor(PhantomData))
+}
+
+impl<T: Real + Serialize> Serialize for ObjectReference<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ObjectReference::Prototype(name) => name.serialize(serializer),
+            ObjectReference::Inline(object) => object.serialize(serializer),
+        }
+    }
+}
+
+fn validate_light<T: Real>(light: &Light<T>, field: &str) -> Result<(), SceneError> {
+    let at = |name: &str| format!("{}.{}", field, name);
+    if light.samples == 0 {
+        return Err(invalid_value(&at("samples"), "must be positive"));
+    }
+    if let Some(direction) = light.direction {
+        unit_vector(direction, field, "direction")?;
+    }
+    if light.position.is_none() {
+        if light.direction.is_none() {
+            return Err(invalid_value(
+                &at("direction"),
+                "is required for directional lights, which have no position",
+            ));
+        }
+        if light.spot.is_some() || !matches!(light.shape, LightShape::Point) {
+            return Err(invalid_value(
+                field,
+                "directional lights cannot be spot or area lights",
+            ));
+        }
+        if light.attenuation != Attenuation::None {
+            return Err(invalid_value(
+                &at("attenuation"),
+                "directional lights are not attenuated",
+            ));
+        }
+    } else if light.direction.is_some() && light.spot.is_none() {
+        return Err(invalid_value(
+            &at("direction"),
+            "only directional and spot lights have a direction",
+        ));
+    }
+    if let Some(spot) = light.spot {
+        if light.direction.is_none() {
+            return Err(invalid_value(
+                &at("direction"),
+                "is required for spot lights",
+            ));
+        }
+        if !(spot.angle > T::zero() && spot.angle <= T::from(180.0).unwrap()) {
+            return Err(invalid_value(
+                &at("spot.angle"),
+                "must be between 0 and 180 degrees",
+            ));
+        }
+        if !(spot.falloff >= T::zero() && spot.falloff <= T::one()) {
+            return Err(invalid_value(
+                &at("spot.falloff"),
+                "must be between 0 and 1",
+            ));
+        }
+    }
+    if let Attenuation::Custom {
+        constant,
+        linear,
+        quadratic,
+    } = light.attenuation
+    {
+        if constant < T::zero() || linear < T::zero() || quadratic < T::zero() {
+            return Err(invalid_value(&at("attenuation"), "must not be negative"));
+        }
+        if constant + linear + quadratic == T::zero() {
+            return Err(invalid_value(
+                &at("attenuation"),
+                "at least one coefficient must be positive",
+            ));
+        }
+    }
+    match light.shape {
+        LightShape::Point => {}
+        LightShape::Rectangle { u_edge, v_edge } => {
+            if u_edge.cross(v_edge).norm() == T::zero() {
+                return Err(invalid_value(
+                    &at("shape.v_edge"),
+                    "must not be parallel to `u_edge`",
+                ));
+            }
+        }
+        LightShape::Disk { normal, radius } => {
+            unit_vector(normal, field, "shape.normal")?;
+            positive(radius, field, "shape.radius")?;
+        }
+        LightShape::Sphere { radius } => {
+            positive(radius, field, "shape.radius")?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_animation(animation: &Animation) -> Result<(), SceneError> {
+    if animation.frames == 0 {
+        return Err(invalid_value("animation.frames", "must be positive"));
+    }
+    if !(animation.frame_rate > 0.0 && animation.frame_rate.is_finite()) {
+        return Err(invalid_value("animation.frame_rate", "must be positive"));
+    }
+    for (index, track) in animation.tracks.iter().enumerate() {
+        let at = |name: &str| format!("animation.tracks[{}].{}", index, name);
+        let first = track
+            .keys
+            .first()
+            .ok_or_else(|| invalid_value(&at("keys"), "must not be empty"))?;
+        let same_kind = |value: &KeyValue| {
+            matches!(
+                (value, &first.value),
+                (KeyValue::Number(_), KeyValue::Number(_)) | (KeyValue::List(_), KeyValue::List(_))
+            ) && value.components().len() == first.value.components().len()
+        };
+        if !track.keys.iter().all(|key| same_kind(&key.value)) {
+            return Err(invalid_value(
+                &at("keys"),
+                "values must all be numbers or lists of the same length",
+            ));
+        }
+        if !track.keys.iter().all(|key| key.time.is_finite())
+            || track
+                .keys
+                .windows(2)
+                .any(|keys| keys[0].time >= keys[1].time)
+        {
+            return Err(invalid_value(
+                &at("keys"),
+                "times must be finite and increasing",
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn unit_vector<T: Real>(vector: Vec3D<T>, field: &str, name: &str) -> Result<Vec3D<T>, SceneError> {
+    if vector.norm() == T::zero() {
+        Err(invalid_value(
+            &format!("{}.{}", field, name),
+            "must not be zero",
+        ))
+    } else {
+        Ok(vector.normalize())
+    }
+}
+
+fn positive<T: Real>(value: T, field: &str, name: &str) -> Result<T, SceneError> {
+    if value > T::zero() {
+        Ok(value)
+    } else {
+        Err(invalid_value(
+            &format!("{}.{}", field, name),
+            "must be positive",
+        ))
+    }
+}
+
+fn invalid_value(field: &str, message: &str) -> SceneError {
+    SceneError::InvalidValue {
+        field: field.to_owned(),
+        message: message.to_owned(),
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io { path, source } => {
+                write!(f, "failed to read {}: {}", path.display(), source)
+            }
+            SceneError::Parse(error) => write!(f, "invalid scene description: {}", error),
+            SceneError::UnknownMaterial { field, name } => {
+                write!(f, "{}: unknown material preset `{}`", field, name)
+            }
+            SceneError::UnknownPrototype { field, name } => {
+                write!(f, "{}: unknown prototype `{}`", field, name)
+            }
+            SceneError::InvalidValue { field, message } => write!(f, "{}: {}", field, message),
+            SceneError::Mesh {
+                field,
+                path,
+                source,
+            } => write!(
+                f,
+                "{}: failed to load mesh {}: {}",
+                field,
+                path.display(),
+                source
+            ),
+            SceneError::Texture {
+                field,
+                path,
+                source,
+            } => write!(
+                f,
+                "{}: failed to load texture {}: {}",
+                field,
+                path.display(),
+                source
+            ),
+        }
+    }
+}
+
+impl Error for SceneError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SceneError::Io { source, .. } => Some(source),
+            SceneError::Parse(error) => Some(error),
+            SceneError::Mesh { source, .. } => Some(source),
+            SceneError::Texture { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<toml::de::Error> for SceneError {
+    fn from(error: toml::de::Error) -> Self {
+        SceneError::Parse(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material_presets() {
+        let description: SceneDescription<f64> = SceneDescription::parse(
+            r#"
+            [materials.shiny]
+            specular = [0.5, 0.5, 0.5]
+            shininess = 100.0
+            absorption = [0.1, 0.2, 0.3]
+            fresnel = "schlick"
+
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, -10.0]
+            radius = 2.0
+            material = "shiny"
+
+            [[objects]]
+            type = "plane"
+            origin = [0.0, -2.0, 0.0]
+            normal = [0.0, 2.0, 0.0]
+            material = { preset = "shiny", shininess = 10.0 }
+            "#,
+        )
+        .unwrap();
+        let scene = description.scene().unwrap();
+        assert_eq!(scene.objects().len(), 2);
+        match &scene.objects()[1] {
+            Hittable::Primitive {
+                shape: Shape::Plane(plane),
+                material,
+            } => {
+                assert_eq!(plane.normal.y, 1.0);
+                assert_eq!(material.base.shininess, 10.0);
+                assert_eq!(material.base.specular.r, 0.5);
+                assert_eq!(material.base.absorption.b, 0.3);
+                assert_eq!(material.base.fresnel, Fresnel::Schlick);
+            }
+            _ => panic!("expected a plane"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_material() {
+        let description: SceneDescription<f64> = SceneDescription::parse(
+            r#"
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, -10.0]
+            radius = 2.0
+            material = "glas"
+            "#,
+        )
+        .unwrap();
+        let error = description.scene().err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "objects[0].material: unknown material preset `glas`"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_has_line() {
+        let error =
+            SceneDescription::<f64>::parse("recursion_depth = 4\n[frame]\nwidth = \"wide\"\n")
+                .err()
+                .unwrap();
+        assert!(error.to_string().contains("line 3"), "{}", error);
+    }
+
+    #[test]
+    fn test_integrator() {
+        let description = SceneDescription::<f64>::parse("").unwrap();
+        assert_eq!(description.integrator, Integrator::Whitted);
+        let description = SceneDescription::<f64>::parse(
+            r#"
+            [integrator]
+            type = "path_tracing"
+            samples_per_pixel = 64
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            description.integrator,
+            Integrator::PathTracing {
+                samples_per_pixel: 64,
+                roulette_depth: 3,
+                max_depth: 64,
+            }
+        );
+        let error = SceneDescription::<f64>::parse(
+            "[integrator]\ntype = \"path_tracing\"\nsamples_per_pixel = 0\n",
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "integrator.samples_per_pixel: must be positive"
+        );
+    }
+
+    #[test]
+    fn test_light_types() {
+        let description = SceneDescription::<f64>::parse(
+            r#"
+            shadows = "colored"
+
+            [[lights]]
+            position = [0.0, 5.0, 0.0]
+            intensity = 2
+
+            [[lights]]
+            direction = [0.0, -1.0, -1.0]
+            intensity = [1.0, 0.9, 0.7]
+
+            [[lights]]
+            position = [0.0, 5.0, 0.0]
+            direction = [0.0, -1.0, 0.0]
+            intensity = [0.5, 0.5, 1.0]
+            attenuation = { type = "custom", linear = 0.1 }
+            spot = { angle = 30.0, falloff = 0.25 }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(description.shadows, Shadows::Colored);
+        assert!(description.scene().unwrap().shadows == Shadows::Colored);
+        let lights = &description.lights;
+        assert!(lights[0].intensity == Color::from([2.0, 2.0, 2.0]));
+        assert!(lights[1].position.is_none());
+        assert_eq!(lights[1].intensity.b, 0.7);
+        assert_eq!(
+            lights[2].attenuation,
+            Attenuation::Custom {
+                constant: 1.0,
+                linear: 0.1,
+                quadratic: 0.0,
+            }
+        );
+        assert_eq!(lights[2].spot.unwrap().falloff, 0.25);
+
+        for (source, message) in &[
+            (
+                "intensity = 1.0",
+                "lights[0].direction: is required for directional lights, which have no position",
+            ),
+            (
+                "position = [0.0, 0.0, 0.0]\ndirection = [0.0, -1.0, 0.0]\nintensity = 1.0",
+                "lights[0].direction: only directional and spot lights have a direction",
+            ),
+            (
+                "position = [0.0, 0.0, 0.0]\ndirection = [0.0, -1.0, 0.0]\nintensity = 1.0\n\
+                 spot = { angle = 100.0, falloff = 2.0 }",
+                "lights[0].spot.falloff: must be between 0 and 1",
+            ),
+        ] {
+            let source = format!("[[lights]]\n{}\n", source);
+            let error = SceneDescription::<f64>::parse(&source).err().unwrap();
+            assert_eq!(error.to_string(), *message);
+        }
+    }
+
+    #[test]
+    fn test_triangles_and_meshes() {
+        let mut description: SceneDescription<f64> = SceneDescription::parse(
+            r#"
+            [[objects]]
+            type = "triangle"
+            vertices = [[0.0, 0.0, -5.0], [1.0, 0.0, -5.0], [0.0, 1.0, -5.0]]
+            normals = [[0.0, 0.0, 2.0], [0.0, 0.0, 1.0], [0.0, 0.0, 1.0]]
+            material = {}
+
+            [[objects]]
+            type = "mesh"
+            path = "models/octahedron.obj"
+            smooth = false
+            material = {}
+            "#,
+        )
+        .unwrap();
+        description.base_directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+        let scene = description.scene().unwrap();
+        match &scene.objects()[0] {
+            Hittable::Primitive {
+                shape: Shape::Triangle(triangle),
+                ..
+            } => assert_eq!(triangle.normals.unwrap()[0].z, 1.0),
+            _ => panic!("expected a triangle"),
+        }
+        match &scene.objects()[1] {
+            Hittable::Mesh { mesh, .. } => {
+                assert_eq!(mesh.triangles().len(), 8);
+                assert!(mesh
+                    .triangles()
+                    .iter()
+                    .all(|triangle| triangle.normals.is_none()));
+            }
+            _ => panic!("expected a mesh"),
+        }
+
+        description.base_directory = PathBuf::from("missing");
+        let error = description.scene().err().unwrap();
+        assert!(
+            error
+                .to_string()
+                .starts_with("objects[1]: failed to load mesh missing/models/octahedron.obj"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_instances() {
+        let description: SceneDescription<f64> = SceneDescription::parse(
+            r#"
+            [prototypes.ball]
+            type = "sphere"
+            center = [0.0, 0.0, 0.0]
+            radius = 1.0
+            material = {}
+
+            [[objects]]
+            type = "instance"
+            object = "ball"
+            translate = [0.0, 0.0, -10.0]
+            scale = [2.0, 2.0, 2.0]
+
+            [[objects]]
+            type = "instance"
+            object = { type = "instance", object = "ball", translate = [3.0, 0.0, 0.0] }
+            rotate = [0.0, 90.0, 0.0]
+            "#,
+        )
+        .unwrap();
+        let scene = description.scene().unwrap();
+        let bounds: Vec<_> = scene
+            .objects()
+            .iter()
+            .map(|object| object.bounding_box().unwrap())
+            .collect();
+        assert_eq!((bounds[0].min.z, bounds[0].max.z), (-12.0, -8.0));
+        assert!((bounds[1].center() - Vec3D::from([0.0, 0.0, -3.0])).norm() < 1e-9);
+
+        let error = SceneDescription::<f64>::parse(
+            r#"
+            [[objects]]
+            type = "instance"
+            object = "missing"
+            "#,
+        )
+        .unwrap()
+        .scene()
+        .err()
+        .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "objects[0].object: unknown prototype `missing`"
+        );
+    }
+
+    #[test]
+    fn test_textures() {
+        let description: SceneDescription<f64> = SceneDescription::parse(
+            r#"
+            [materials.tiles]
+            diffuse = { type = "checker", even = [1.0, 1.0, 1.0], odd = { type = "stripes", even = [0.0, 0.0, 0.0], odd = [0.5, 0.5, 0.5], scale = 2.0 } }
+            shininess = 20.0
+            normal_map = { type = "bump", height = { type = "gradient", start = [0.0, 0.0, 0.0], end = [1.0, 1.0, 1.0] }, strength = 0.5 }
+
+            [[objects]]
+            type = "rectangle"
+            corner = [0.0, 0.0, 0.0]
+            u_edge = [2.0, 0.0, 0.0]
+            v_edge = [0.0, 0.0, -2.0]
+            material = "tiles"
+
+            [[objects]]
+            type = "rectangle"
+            corner = [0.0, 0.0, 0.0]
+            u_edge = [2.0, 0.0, 0.0]
+            v_edge = [0.0, 0.0, -2.0]
+            material = { preset = "tiles", diffuse = [0.0, 1.0, 0.0] }
+            "#,
+        )
+        .unwrap();
+        let scene = description.scene().unwrap();
+        let diffuse = |index: usize, uv: (f64, f64)| match &scene.objects()[index] {
+            Hittable::Primitive { material, .. } => material.at(uv, Vec3D::default()).diffuse,
+            _ => panic!("expected a rectangle"),
+        };
+        assert_eq!(diffuse(0, (0.5, 0.5)).r, 1.0);
+        assert_eq!(diffuse(0, (1.25, 0.5)).r, 0.0);
+        assert_eq!(diffuse(0, (1.75, 0.5)).r, 0.5);
+        // A color overrides the texture of the preset.
+        assert_eq!(diffuse(1, (1.5, 0.5)).g, 1.0);
+        match &scene.objects()[1] {
+            Hittable::Primitive { material, .. } => assert!(matches!(
+                material.normal_map,
+                Some(NormalMap::Bump { strength, .. }) if strength == 0.5
+            )),
+            _ => panic!("expected a rectangle"),
+        }
+
+        let error = SceneDescription::<f64>::parse(
+            r#"
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, -10.0]
+            radius = 2.0
+            material = { diffuse = { type = "image", path = "missing.png" } }
+            "#,
+        )
+        .unwrap()
+        .scene()
+        .err()
+        .unwrap();
+        assert!(
+            error
+                .to_string()
+                .starts_with("objects[0].material.diffuse: failed to load texture missing.png"),
+            "{}",
+            error
+        );
+
+        let error = SceneDescription::<f64>::parse(
+            r#"
+            [materials.first]
+            [materials.second]
+            preset = "first"
+            "#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "materials.second.preset: presets cannot refer to other presets"
+        );
+
+        for strength in ["-0.5", "inf", "nan"] {
+            let error = SceneDescription::<f64>::parse(&format!(
+                r#"
+                [[objects]]
+                type = "sphere"
+                center = [0.0, 0.0, -10.0]
+                radius = 2.0
+                material = {{ normal_map = {{ type = "bump", height = {{ type = "gradient", start = [0.0, 0.0, 0.0], end = [1.0, 1.0, 1.0] }}, strength = {} }} }}
+                "#,
+                strength
+            ))
+            .unwrap()
+            .scene()
+            .err()
+            .unwrap();
+            assert_eq!(
+                error.to_string(),
+                "objects[0].material.normal_map.strength: must be finite and not negative"
+            );
+        }
+    }
+
+    #[test]
+    fn test_animation() {
+        let source = r#"
+            [camera]
+            position = [0.0, 0.0, 0.0]
+            look_at = [0.0, 0.0, -1.0]
+
+            [materials.plastic]
+            shininess = 20.0
+
+            [animation]
+            frames = 5
+            frame_rate = 4.0
+
+            [[animation.tracks]]
+            target = "camera.position"
+            keys = [{ time = 0.0, value = [0.0, 0.0, 0.0] }, { time = 1.0, value = [0.0, 4.0, 0.0] }]
+
+            [[animation.tracks]]
+            target = "objects[0].material.diffuse"
+            interpolation = "smoothstep"
+            keys = [{ time = 0.0, value = [1.0, 0.0, 0.0] }, { time = 1.0, value = [0.0, 0.0, 1.0] }]
+
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, -5.0]
+            radius = 1.0
+            material = { preset = "plastic" }
+            "#;
+        let description: SceneDescription<f64> = SceneDescription::parse(source).unwrap();
+        let frame = description.frame(2).unwrap();
+        assert_eq!(frame.camera.position.y, 2.0);
+        match frame.objects[0] {
+            ObjectDescription::Sphere { ref material, .. } => {
+                assert_eq!(material.preset.as_deref(), Some("plastic"));
+                assert!(matches!(
+                    material.diffuse,
+                    Some(ColorSource::Color(color)) if color == Color::from([0.5, 0.0, 0.5])
+                ));
+            }
+            _ => panic!("expected a sphere"),
+        }
+        assert_eq!(description.frame(4).unwrap().camera.position.y, 4.0);
+        assert_eq!(description.camera.position.y, 0.0);
+        let scene = frame.scene().unwrap();
+        assert_eq!(scene.objects().len(), 1);
+
+        // Changes after parsing are kept, tracks only override their targets.
+        let mut changed: SceneDescription<f64> = SceneDescription::parse(source).unwrap();
+        changed.camera.field_of_view = 30.0;
+        changed.recursion_depth = 2;
+        let frame = changed.frame(2).unwrap();
+        assert_eq!(frame.camera.field_of_view, 30.0);
+        assert_eq!(frame.recursion_depth, 2);
+        assert_eq!(frame.camera.position.y, 2.0);
+        // Descriptions deserialized directly are animated too.
+        let deserialized: SceneDescription<f64> = toml::from_str(source).unwrap();
+        assert_eq!(deserialized.frame(4).unwrap().camera.position.y, 4.0);
+
+        let error = |replace: &str, with: &str| {
+            SceneDescription::<f64>::parse(&source.replace(replace, with))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            error(
+                "time = 1.0, value = [0.0, 4.0",
+                "time = 0.0, value = [0.0, 4.0"
+            ),
+            "animation.tracks[0].keys: times must be finite and increasing"
+        );
+        assert_eq!(
+            error("value = [0.0, 4.0, 0.0]", "value = 4.0"),
+            "animation.tracks[0].keys: values must all be numbers or lists of the same length"
+        );
+        assert_eq!(
+            error("objects[0].material", "objects[1].material"),
+            "animation.tracks: `objects` has no element 1"
+        );
+        assert!(error("camera.position", "camera.positions").contains("unknown field"));
+        assert_eq!(
+            error("frames = 5", "frames = 0"),
+            "animation.frames: must be positive"
+        );
+    }
+
+    #[test]
+    fn test_fog_and_volumes() {
+        let source = r#"
+            [camera]
+            position = [0.0, 0.0, 0.0]
+            look_at = [0.0, 0.0, -1.0]
+
+            [fog]
+            color = [0.5, 0.5, 0.6]
+            density = 0.05
+
+            [[volumes]]
+            boundary = { type = "sphere", center = [0.0, 0.0, -5.0], radius = 1.0 }
+            scattering = [0.5, 0.5, 0.5]
+            anisotropy = 0.4
+
+            [[volumes]]
+            boundary = { type = "box", min = [-1.0, -1.0, -9.0], max = [1.0, 1.0, -7.0] }
+            absorption = [0.1, 0.2, 0.3]
+            steps = 4
+
+            [[objects]]
+            type = "sphere"
+            center = [0.0, 0.0, -12.0]
+            radius = 1.0
+            material = { diffuse = [1.0, 1.0, 1.0] }
+            "#;
+        let scene: Scene<f64> = SceneDescription::parse(source).unwrap().scene().unwrap();
+        assert_eq!(scene.fog.unwrap().density, 0.05);
+        assert_eq!(scene.volumes.len(), 2);
+        assert_eq!(scene.volumes[0].steps, 16);
+        assert!(scene.volumes[0].absorption == Color::zero());
+        assert_eq!(scene.volumes[1].steps, 4);
+        assert_eq!(scene.objects().len(), 1);
+
+        let error = |replace: &str, with: &str| {
+            SceneDescription::<f64>::parse(&source.replace(replace, with))
+                .and_then(|description| description.scene())
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            error("density = 0.05", "density = -0.05"),
+            "fog.density: must not be negative"
+        );
+        assert_eq!(
+            error("radius = 1.0 }", "radius = 0.0 }"),
+            "volumes[0].boundary.radius: must be positive"
+        );
+        assert_eq!(
+            error("max = [1.0, 1.0, -7.0]", "max = [1.0, 1.0, -10.0]"),
+            "volumes[1].boundary.max: must be above `min` on every axis"
+        );
+        assert_eq!(
+            error("anisotropy = 0.4", "anisotropy = 1.0"),
+            "volumes[0].anisotropy: must be between -1 and 1"
+        );
+        assert_eq!(
+            error("absorption = [0.1", "absorption = [-0.1"),
+            "volumes[1]: absorption and scattering must not be negative"
+        );
+        assert_eq!(
+            error("steps = 4", "steps = 0"),
+            "volumes[1].steps: must be positive"
+        );
+        assert!(error("type = \"box\"", "type = \"cone\"").contains("unknown variant"));
+    }
+
+    #[test]
+    fn test_environment() {
+        let directory = std::env::temp_dir().join(format!("raytracing-env-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        image::RgbImage::from_pixel(4, 2, image::Rgb([255, 0, 0]))
+            .save(directory.join("sky.png"))
+            .unwrap();
+        let pixels = vec![image::Rgb([4.0f32, 2.0, 1.0]); 4];
+        image::codecs::hdr::HdrEncoder::new(fs::File::create(directory.join("face.hdr")).unwrap())
+            .encode(&pixels, 2, 2)
+            .unwrap();
+
+        let scene = |environment: &str| {
+            let mut description: SceneDescription<f64> =
+                SceneDescription::parse(&format!("[environment]\n{}", environment))?;
+            description.base_directory = directory.clone();
+            description.scene()
+        };
+        let forward = Vec3D::from([0.0, 0.0, -1.0]);
+        let color = scene("type = \"equirectangular\"\npath = \"sky.png\"\nintensity = 2.0")
+            .unwrap()
+            .environment
+            .color(forward);
+        assert!(color == Color::from([2.0, 0.0, 0.0]));
+        let faces = ["x", "y", "z"]
+            .iter()
+            .map(|axis| {
+                format!(
+                    "positive_{0} = \"face.hdr\"\nnegative_{0} = \"face.hdr\"\n",
+                    axis
+                )
+            })
+            .collect::<String>();
+        let color = scene(&format!("type = \"cube_map\"\n{}", faces))
+            .unwrap()
+            .environment
+            .color(forward);
+        assert!(color == Color::from([4.0, 2.0, 1.0]));
+        let color = scene("type = \"sky\"\nsun_direction = [0.0, 1.0, -1.0]")
+            .unwrap()
+            .environment
+            .color(Vec3D::from([0.0, 1.0, 0.0]));
+        assert!(color.b > color.r);
+        let background = SceneDescription::<f64>::parse("background_color = [0.5, 0.5, 0.5]")
+            .unwrap()
+            .scene()
+            .unwrap();
+        assert!(background.environment.color(forward) == Color::from([0.5, 0.5, 0.5]));
+        assert!(background.background_color() == Some(Color::from([0.5, 0.5, 0.5])));
+        let sky = scene("type = \"sky\"\nsun_direction = [0.0, 1.0, -1.0]").unwrap();
+        assert!(sky.background_color().is_none());
+
+        let error = |environment: &str| scene(environment).err().unwrap().to_string();
+        assert!(error("type = \"equirectangular\"\npath = \"missing.png\"")
+            .starts_with("environment.path: failed to load texture"));
+        assert_eq!(
+            error("type = \"sky\"\nsun_direction = [0.0, -1.0, 0.0]"),
+            "environment.sun_direction: must point above the horizon"
+        );
+        assert_eq!(
+            error("type = \"sky\"\nsun_direction = [0.0, 1.0, 0.0]\nturbidity = 1.0"),
+            "environment.turbidity: must be between 2 and 10"
+        );
+        assert_eq!(
+            error("type = \"equirectangular\"\npath = \"sky.png\"\nintensity = -1.0"),
+            "environment.intensity: must not be negative"
+        );
+        assert!(error("type = \"gradient\"\ntop = [1.0, 1.0, 1.0]").contains("missing field"));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_bundled_scenes() {
+        for name in &[
+            "animation_scene.toml",
+            "area_lights_scene.toml",
+            "box_scene.toml",
+            "csg_scene.toml",
+            "environment_scene.toml",
+            "glass_scene.toml",
+            "lights_scene.toml",
+            "mesh_scene.toml",
+            "motion_blur_scene.toml",
+            "primitives_scene.toml",
+            "textures_scene.toml",
+            "tutorial_scene.toml",
+            "volumes_scene.toml",
+        ] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("scenes")
+                .join(name);
+            let scene: Scene<f64> = Scene::load(path).unwrap();
+            assert!(!scene.objects().is_empty());
+        }
+    }
+}
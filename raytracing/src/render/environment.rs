@@ -0,0 +1,349 @@
+use crate::math::color::Color;
+use crate::math::vec3d::Vec3D;
+use image::codecs::hdr::HdrDecoder;
+use image::ImageResult;
+use num_traits::real::Real;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Light arriving from far away around the scene, seen along the rays missing all
+/// objects, directly or in reflections and refractions.
+pub enum Environment<T: Real> {
+    Solid(Color<T>),
+    /// Blend from the `bottom` color looking straight down to the `top` color looking
+    /// straight up.
+    Gradient {
+        top: Color<T>,
+        bottom: Color<T>,
+    },
+    Sky(Sky),
+    /// Image of the whole sphere of directions, the longitude across and the latitude
+    /// down the image, with `-z` in its center. The image is turned by `rotation`
+    /// radians around the `y` axis.
+    Equirectangular {
+        image: Arc<EnvironmentImage>,
+        rotation: T,
+    },
+    /// Images on the faces of a cube around the scene, in the order `+x`, `-x`, `+y`,
+    /// `-y`, `+z` and `-z`, as seen from inside the cube with `+y` up. The `+y` and
+    /// `-y` faces are seen turning up and down from facing `-z`.
+    CubeMap(Arc<[EnvironmentImage; 6]>),
+}
+
+/// Linear colors of an image the environment is looked up in.
+pub struct EnvironmentImage {
+    pub width: usize,
+    pub height: usize,
+    /// Rows from the top, each from the left.
+    pub pixels: Vec<[f32; 3]>,
+}
+
+/// Clear sky of the analytic model of Preetham, Shirley and Smits, lit by the sun in
+/// the given direction. Below the horizon the sky is continued as it looks at the
+/// horizon.
+pub struct Sky {
+    sun: Vec3D<f64>,
+    /// Perez coefficients of the luminance and of the two chromaticities.
+    coefficients: [[f64; 5]; 3],
+    /// Luminance and chromaticities at the zenith, divided by the Perez function there.
+    zenith: [f64; 3],
+}
+
+impl<T: Real> From<Color<T>> for Environment<T> {
+    fn from(color: Color<T>) -> Self {
+        Environment::Solid(color)
+    }
+}
+
+impl<T: Real> Environment<T> {
+    /// Light arriving from the direction.
+    pub fn color(&self, direction: Vec3D<T>) -> Color<T> {
+        let direction = direction.normalize();
+        match self {
+            Environment::Solid(color) => *color,
+            Environment::Gradient { top, bottom } => {
+                let t = (direction.y + T::one()) / (T::one() + T::one());
+                *bottom * (T::one() - t) + *top * t
+            }
+            Environment::Sky(sky) => color(sky.color(to_f64(direction))),
+            Environment::Equirectangular { image, rotation } => {
+                let Vec3D { x, y, z } = to_f64(direction);
+                let longitude = x.atan2(-z) - rotation.to_f64().unwrap();
+                let u = (0.5 + longitude / (2.0 * PI)).rem_euclid(1.0);
+                let v = 0.5 - y.clamp(-1.0, 1.0).asin() / PI;
+                color(image.sample(u, v, true))
+            }
+            Environment::CubeMap(faces) => {
+                let Vec3D { x, y, z } = to_f64(direction);
+                let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+                let (face, u, v) = if ax >= ay && ax >= az {
+                    if x > 0.0 {
+                        (0, z / ax, -y / ax)
+                    } else {
+                        (1, -z / ax, -y / ax)
+                    }
+                } else if ay >= az {
+                    if y > 0.0 {
+                        (2, x / ay, -z / ay)
+                    } else {
+                        (3, x / ay, z / ay)
+                    }
+                } else if z > 0.0 {
+                    (4, -x / az, -y / az)
+                } else {
+                    (5, x / az, -y / az)
+                };
+                color(faces[face].sample((u + 1.0) / 2.0, (v + 1.0) / 2.0, false))
+            }
+        }
+    }
+}
+
+impl EnvironmentImage {
+    /// Reads the image, Radiance HDR files with their linear colors and others with
+    /// the channels scaled to `[0, 1]`, all multiplied by `intensity`.
+    pub fn open(path: &Path, intensity: f32) -> ImageResult<EnvironmentImage> {
+        let is_hdr = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("hdr"));
+        if is_hdr {
+            let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+            let metadata = decoder.metadata();
+            let pixels = decoder.read_image_hdr()?;
+            Ok(EnvironmentImage {
+                width: metadata.width as usize,
+                height: metadata.height as usize,
+                pixels: pixels
+                    .into_iter()
+                    .map(|pixel| pixel.0.map(|channel| channel * intensity))
+                    .collect(),
+            })
+        } else {
+            let image = image::open(path)?.to_rgb8();
+            Ok(EnvironmentImage {
+                width: image.width() as usize,
+                height: image.height() as usize,
+                pixels: image
+                    .pixels()
+                    .map(|pixel| pixel.0.map(|channel| channel as f32 / 255.0 * intensity))
+                    .collect(),
+            })
+        }
+    }
+
+    /// Bilinearly interpolated color at the coordinates from `0` to `1` across and down
+    /// the image, wrapped around horizontally when `wrap` is set and clamped to the
+    /// edges otherwise.
+    pub fn sample(&self, u: f64, v: f64, wrap: bool) -> [f64; 3] {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let x = u * self.width as f64 - 0.5;
+        let y = v * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let texel = |x: f64, y: f64| {
+            let x = if wrap {
+                (x as i64).rem_euclid(width)
+            } else {
+                (x as i64).max(0).min(width - 1)
+            };
+            let y = (y as i64).max(0).min(height - 1);
+            self.pixels[(y * width + x) as usize].map(f64::from)
+        };
+        let (c00, c10) = (texel(x0, y0), texel(x0 + 1.0, y0));
+        let (c01, c11) = (texel(x0, y0 + 1.0), texel(x0 + 1.0, y0 + 1.0));
+        [0, 1, 2].map(|channel| {
+            let top = c00[channel] + (c10[channel] - c00[channel]) * fx;
+            let bottom = c01[channel] + (c11[channel] - c01[channel]) * fx;
+            top + (bottom - top) * fy
+        })
+    }
+}
+
+impl Sky {
+    /// Sky of the turbidity, from `2` for a very clear to `10` for a hazy one, scaled
+    /// to the luminance `intensity` at the zenith. The sun has to be above the horizon.
+    pub fn new(sun_direction: Vec3D<f64>, turbidity: f64, intensity: f64) -> Sky {
+        let sun = sun_direction.normalize();
+        let t = turbidity;
+        let coefficients = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+        let theta = sun.y.clamp(-1.0, 1.0).acos();
+        let cubic = |[a, b, c, d]: [f64; 4]| ((a * theta + b) * theta + c) * theta + d;
+        let x = t * t * cubic([0.00166, -0.00375, 0.00209, 0.0])
+            + t * cubic([-0.02903, 0.06377, -0.03202, 0.00394])
+            + cubic([0.11693, -0.21196, 0.06052, 0.25886]);
+        let y = t * t * cubic([0.00275, -0.00610, 0.00317, 0.0])
+            + t * cubic([-0.04214, 0.08970, -0.04153, 0.00516])
+            + cubic([0.15346, -0.26756, 0.06670, 0.26688]);
+        let mut zenith = [intensity, x, y];
+        for (value, coefficients) in zenith.iter_mut().zip(&coefficients) {
+            *value /= perez(coefficients, 1.0, theta);
+        }
+        Sky {
+            sun,
+            coefficients,
+            zenith,
+        }
+    }
+
+    /// Linear RGB color of the sky in the direction.
+    pub fn color(&self, direction: Vec3D<f64>) -> [f64; 3] {
+        let direction = direction.normalize();
+        // Keeps away from the horizon, where the model breaks down.
+        let cos_theta = direction.y.max(0.01);
+        let gamma = (direction * self.sun).clamp(-1.0, 1.0).acos();
+        let [luminance, x, y] = [0, 1, 2]
+            .map(|index| self.zenith[index] * perez(&self.coefficients[index], cos_theta, gamma));
+        let (x, z) = (x / y * luminance, (1.0 - x - y) / y * luminance);
+        let y = luminance;
+        [
+            3.2406 * x - 1.5372 * y - 0.4986 * z,
+            -0.9689 * x + 1.8758 * y + 0.0415 * z,
+            0.0557 * x - 0.2040 * y + 1.0570 * z,
+        ]
+        .map(|channel| channel.max(0.0))
+    }
+}
+
+/// Perez distribution of a sky value at the zenith angle with the cosine and the
+/// angle `gamma` to the sun.
+fn perez([a, b, c, d, e]: &[f64; 5], cos_theta: f64, gamma: f64) -> f64 {
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+fn to_f64<T: Real>(vector: Vec3D<T>) -> Vec3D<f64> {
+    Vec3D {
+        x: vector.x.to_f64().unwrap(),
+        y: vector.y.to_f64().unwrap(),
+        z: vector.z.to_f64().unwrap(),
+    }
+}
+
+fn color<T: Real>(channels: [f64; 3]) -> Color<T> {
+    Color::from(channels.map(|channel| T::from(channel).unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: usize, height: usize, pixels: &[[f32; 3]]) -> Arc<EnvironmentImage> {
+        Arc::new(EnvironmentImage {
+            width,
+            height,
+            pixels: pixels.to_vec(),
+        })
+    }
+
+    #[test]
+    fn test_gradient() {
+        let gradient = Environment::Gradient {
+            top: Color::from([0.0, 0.0, 1.0]),
+            bottom: Color::from([1.0, 0.0, 0.0]),
+        };
+        let up = gradient.color(Vec3D::from([0.0, 2.0, 0.0]));
+        assert!(up == Color::from([0.0, 0.0, 1.0]));
+        let horizon = gradient.color(Vec3D::from([1.0, 0.0, 0.0]));
+        assert!(horizon == Color::from([0.5, 0.0, 0.5]));
+        let solid: Environment<f64> = Color::unit().into();
+        assert!(solid.color(Vec3D::from([0.0, -1.0, 0.0])) == Color::unit());
+    }
+
+    #[test]
+    fn test_equirectangular() {
+        let (red, green, blue, white) = (
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 1.0, 1.0],
+        );
+        let image = image(4, 2, &[red, green, blue, white, red, green, blue, white]);
+        let environment = Environment::Equirectangular {
+            image: image.clone(),
+            rotation: 0.0,
+        };
+        let forward = environment.color(Vec3D::from([0.0, 0.0, -1.0]));
+        // Halfway between the second and third columns.
+        assert!(forward == Color::from([0.0, 0.5, 0.5]));
+        let behind = environment.color(Vec3D::from([0.0, 0.0, 1.0]));
+        assert!(behind == Color::from([1.0, 0.5, 0.5]));
+        let right = environment.color(Vec3D::from([1.0, 0.0, 0.0]));
+        assert!(right == Color::from([0.5, 0.5, 1.0]));
+
+        let rotated = Environment::Equirectangular {
+            image,
+            rotation: std::f64::consts::FRAC_PI_2,
+        };
+        assert!(rotated.color(Vec3D::from([1.0, 0.0, 0.0])) == forward);
+    }
+
+    #[test]
+    fn test_cube_map() {
+        let face = |color: [f32; 3]| EnvironmentImage {
+            width: 2,
+            height: 2,
+            pixels: vec![color, color, color, [0.0, 0.0, 0.0]],
+        };
+        let faces = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6].map(|value| face([value, 0.0, 0.0]));
+        let cube_map = Environment::CubeMap(Arc::new(faces));
+        let directions = [
+            [1.0, 0.6, -0.6],
+            [-1.0, 0.6, 0.6],
+            [-0.6, 1.0, 0.6],
+            [-0.6, -1.0, -0.6],
+            [0.6, 0.6, 1.0],
+            [-0.6, 0.6, -1.0],
+        ];
+        // Top left corners of the faces.
+        for (index, direction) in directions.iter().enumerate() {
+            let color: Color<f64> = cube_map.color(Vec3D::from(*direction));
+            assert!((color.r - (index + 1) as f64 / 10.0).abs() < 1e-6);
+        }
+        // The bottom right of the `-z` face, seen from the inside.
+        let corner = cube_map.color(Vec3D::from([0.9, -0.9, -1.0]));
+        assert_eq!(corner.r, 0.0);
+    }
+
+    #[test]
+    fn test_sky() {
+        let sky = Sky::new(Vec3D::from([0.0, 1.0, -1.0]), 3.0, 1.0);
+        let zenith = sky.color(Vec3D::from([0.0, 1.0, 0.0]));
+        let luminance = 0.2126 * zenith[0] + 0.7152 * zenith[1] + 0.0722 * zenith[2];
+        assert!((luminance - 1.0).abs() < 0.01);
+        // Blue overhead, brighter towards the sun and the horizon.
+        assert!(zenith[2] > zenith[0]);
+        let near_sun = sky.color(Vec3D::from([0.0, 1.0, -1.2]));
+        let away = sky.color(Vec3D::from([0.0, 1.0, 1.2]));
+        assert!(near_sun[1] > away[1]);
+        let horizon = sky.color(Vec3D::from([1.0, 0.05, 0.0]));
+        assert!(horizon[1] > zenith[1]);
+        let below = sky.color(Vec3D::from([1.0, -0.5, 0.0]));
+        assert!(below
+            .iter()
+            .all(|channel| channel.is_finite() && *channel > 0.0));
+    }
+}
@@ -1,81 +1,1028 @@
 use crate::math::color::Color;
-use crate::math::ray::Ray;
-use crate::math::vec3d::Vec3D;
+use crate::render::aov::Aov;
+use crate::render::camera::Camera;
+use crate::render::integrator::Integrator;
+use crate::render::sampling::{Pattern, Sampling};
 use crate::render::scene::Scene;
-use image::{ImageBuffer, ImageResult, Rgb};
+use crate::render::tone_mapping::ToneMapping;
+use image::codecs::hdr::HdrEncoder;
+use image::error::{ParameterError, ParameterErrorKind};
+use image::{ImageBuffer, ImageError, ImageFormat, ImageResult, Rgb};
 use num_traits::real::Real;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
 
 pub struct Frame<T: Real> {
     pub width: usize,
     pub height: usize,
-    pub field_of_view: f64,
+    pub camera: Camera<T>,
+    /// Number of threads rendering the frame, `1` renders on the calling thread.
+    pub threads: usize,
+    /// Seed of the random samples, equal seeds give equal images.
+    pub seed: u64,
+    pub sampling: Sampling,
+    pub integrator: Integrator,
+    pub tone_mapping: ToneMapping,
+    /// Extra buffers rendered along with the colors.
+    pub aovs: Vec<Aov>,
     pub(in crate::render) frame_buffer: Vec<Color<T>>,
+    aov_buffers: HashMap<Aov, Vec<Color<T>>>,
 }
 
+/// Passes of a progressive render besides the one at full resolution.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Progressive {
+    /// Size of the square blocks of pixels sharing a single sample in the preview
+    /// pass, `1` skips the preview.
+    pub preview_block: usize,
+    /// Number of passes after the full resolution ones, each averaging the frame with
+    /// a render of new random samples.
+    pub refinement_passes: usize,
+}
+
+impl Default for Progressive {
+    fn default() -> Self {
+        Progressive {
+            preview_block: 8,
+            refinement_passes: 0,
+        }
+    }
+}
+
+/// State of a progressive render, handed to the callback after every pass.
+pub struct Progress<'a, T: Real> {
+    /// Number of passes done so far, from `1` to `passes`.
+    pub pass: usize,
+    pub passes: usize,
+    /// Colors of the frame after the pass.
+    pub frame_buffer: &'a [Color<T>],
+}
+
+impl<'a, T: Real> Progress<'a, T> {
+    /// Fraction of the passes done, in `(0, 1]`.
+    pub fn fraction(&self) -> f64 {
+        self.pass as f64 / self.passes as f64
+    }
+}
+
+/// Number of scanlines in one unit of work handed to a render thread.
+const TILE_HEIGHT: usize = 16;
+
+/// Smallest average filter weight of the samples of a pixel normalized by their total.
+const MIN_TOTAL_WEIGHT: f64 = 1e-3;
+
 impl<T: Real> Frame<T> {
     pub fn new(width: usize, height: usize, field_of_view_degrees: f64) -> Frame<T> {
+        let camera = Camera::new(
+            T::from(field_of_view_degrees).unwrap(),
+            T::from(width as f64 / height as f64).unwrap(),
+        );
+        Frame::with_camera(width, height, camera)
+    }
+
+    pub fn with_camera(width: usize, height: usize, camera: Camera<T>) -> Frame<T> {
         Frame {
             width,
             height,
-            field_of_view: field_of_view_degrees.to_radians(),
+            camera,
+            threads: 1,
+            seed: 0,
+            sampling: Sampling::default(),
+            integrator: Integrator::default(),
+            tone_mapping: ToneMapping::default(),
+            aovs: Vec::new(),
             frame_buffer: vec![Color::default(); width * height],
+            aov_buffers: HashMap::new(),
         }
     }
 
     pub fn render(&mut self, scene: &Scene<T>)
     where
-        T: From<f64>,
+        T: From<f64> + Send + Sync,
+    {
+        let progressive = Progressive {
+            preview_block: 1,
+            refinement_passes: 0,
+        };
+        self.render_progressive(scene, &progressive, &AtomicBool::new(false), |_| {});
+    }
+
+    /// Renders the frame in passes, calling `callback` after each of them with the
+    /// frame buffer so far: a preview at a lower resolution, the frame at full
+    /// resolution, the adaptive samples, then the refinement passes. Setting `cancel`
+    /// stops the render within the current pass, leaving the rest of the frame from the
+    /// previous ones, and returns `false` without calling `callback` for the interrupted
+    /// pass.
+    pub fn render_progressive<F>(
+        &mut self,
+        scene: &Scene<T>,
+        progressive: &Progressive,
+        cancel: &AtomicBool,
+        mut callback: F,
+    ) -> bool
+    where
+        T: From<f64> + Send + Sync,
+        F: FnMut(&Progress<T>),
+    {
+        let samples = self.sampling.samples;
+        let adaptive_samples = self.sampling.adaptive_samples;
+        let adaptive_threshold = self
+            .sampling
+            .adaptive_threshold
+            .filter(|_| adaptive_samples > samples);
+        let block = progressive.preview_block.max(1);
+        let passes = (block > 1) as usize
+            + 1
+            + adaptive_threshold.is_some() as usize
+            + progressive.refinement_passes;
+        let mut pass = 0;
+        let mut finish_pass = |frame_buffer: &[Color<T>]| {
+            if cancel.load(Ordering::Relaxed) {
+                return false;
+            }
+            pass += 1;
+            callback(&Progress {
+                pass,
+                passes,
+                frame_buffer,
+            });
+            !cancel.load(Ordering::Relaxed)
+        };
+        let mut frame_buffer = std::mem::take(&mut self.frame_buffer);
+        let mut depths = vec![0; frame_buffer.len()];
+
+        if block > 1 {
+            // Every block takes the color of a single sample through its center pixel.
+            let width = self.width;
+            let height = self.height;
+            let center = |index: usize| {
+                let x_index = (index % width / block * block + block / 2).min(width - 1);
+                let y_index = (index / width / block * block + block / 2).min(height - 1);
+                y_index * width + x_index
+            };
+            self.render_pass(scene, &mut frame_buffer, &mut depths, self.seed, |index| {
+                (center(index) == index && !cancel.load(Ordering::Relaxed)) as usize
+            });
+            for index in 0..frame_buffer.len() {
+                frame_buffer[index] = frame_buffer[center(index)];
+            }
+            if !finish_pass(&frame_buffer) {
+                self.frame_buffer = frame_buffer;
+                return false;
+            }
+        }
+
+        self.render_pass(scene, &mut frame_buffer, &mut depths, self.seed, |_| {
+            if cancel.load(Ordering::Relaxed) {
+                0
+            } else {
+                samples
+            }
+        });
+        let mut completed = finish_pass(&frame_buffer);
+
+        let mut mask = None;
+        if let (true, Some(threshold)) = (completed, adaptive_threshold) {
+            let refined = self.adaptive_mask(&frame_buffer, threshold.into());
+            self.render_pass(scene, &mut frame_buffer, &mut depths, self.seed, |index| {
+                if refined[index] && !cancel.load(Ordering::Relaxed) {
+                    adaptive_samples
+                } else {
+                    0
+                }
+            });
+            mask = Some(refined);
+            completed = finish_pass(&frame_buffer);
+        }
+        let pixel_samples = |index: usize| match mask {
+            Some(ref mask) if mask[index] => adaptive_samples,
+            _ => samples,
+        };
+
+        // Refinement passes average the frame with renders of new random samples.
+        for refinement in 1..=progressive.refinement_passes {
+            if !completed {
+                break;
+            }
+            let seed = self
+                .seed
+                .wrapping_add((refinement as u64).wrapping_mul(0x5851_f42d_4c95_7f2d));
+            let mut refined = frame_buffer.clone();
+            let mut refined_depths = depths.clone();
+            self.render_pass(scene, &mut refined, &mut refined_depths, seed, |index| {
+                if cancel.load(Ordering::Relaxed) {
+                    0
+                } else {
+                    pixel_samples(index)
+                }
+            });
+            if cancel.load(Ordering::Relaxed) {
+                break;
+            }
+            let weight = <T as From<f64>>::from(1.0 / (refinement + 1) as f64);
+            for (color, refined) in frame_buffer.iter_mut().zip(refined) {
+                *color = *color * (T::one() - weight) + refined * weight;
+            }
+            for (depth, refined) in depths.iter_mut().zip(refined_depths) {
+                *depth = (*depth).max(refined);
+            }
+            completed = finish_pass(&frame_buffer);
+        }
+
+        self.frame_buffer = frame_buffer;
+        if completed && !self.aovs.is_empty() {
+            self.render_aovs(scene, &depths);
+        }
+        completed
+    }
+
+    /// Buffer of the AOV, if it was among `aovs` when the frame was rendered.
+    pub fn aov(&self, aov: Aov) -> Option<&[Color<T>]> {
+        self.aov_buffers.get(&aov).map(Vec::as_slice)
+    }
+
+    /// Renders every pixel of the buffer for which `samples` of its index is positive
+    /// with that many samples along each axis, along with the deepest recursion of its
+    /// samples, keeping the rest of the buffers as they are.
+    fn render_pass<F>(
+        &self,
+        scene: &Scene<T>,
+        buffer: &mut [Color<T>],
+        depths: &mut [usize],
+        seed: u64,
+        samples: F,
+    ) where
+        T: From<f64> + Send + Sync,
+        F: Fn(usize) -> usize + Sync,
     {
+        let width = self.width;
+        let mut pixels: Vec<(Color<T>, usize)> =
+            buffer.iter().copied().zip(depths.iter().copied()).collect();
+        self.fill_buffer(&mut pixels, |index| {
+            let pixel_samples = samples(index);
+            if pixel_samples > 0 {
+                Some(self.render_pixel(scene, seed, index % width, index / width, pixel_samples))
+            } else {
+                None
+            }
+        });
+        for ((color, depth), pixel) in buffer.iter_mut().zip(depths.iter_mut()).zip(pixels) {
+            (*color, *depth) = pixel;
+        }
+    }
+
+    /// Renders the buffers of `aovs` from the rays through the pixel centers, or takes
+    /// them from the recursion `depths` recorded by the render of the colors.
+    fn render_aovs(&mut self, scene: &Scene<T>, depths: &[usize])
+    where
+        T: From<f64> + Send + Sync,
+    {
+        let width = self.width;
+        let mut aov_buffers = HashMap::new();
+        for aov in &self.aovs {
+            let buffer = if *aov == Aov::RecursionDepth {
+                depths
+                    .iter()
+                    .map(|depth| Color::from([<T as From<f64>>::from(*depth as f64); 3]))
+                    .collect()
+            } else {
+                let mut buffer = vec![Color::zero(); self.frame_buffer.len()];
+                self.fill_buffer(&mut buffer, |index| {
+                    let x = ((index % width) as f64 + 0.5) / width as f64;
+                    let y = ((index / width) as f64 + 0.5) / self.height as f64;
+                    aov.value(scene, self.camera.ray(x.into(), y.into()))
+                });
+                buffer
+            };
+            aov_buffers.insert(*aov, buffer);
+        }
+        self.aov_buffers = aov_buffers;
+    }
+
+    /// Sets every pixel of the buffer for which `pixel` of its index gives a value,
+    /// handing out tiles of the buffer to the render threads.
+    fn fill_buffer<P, F>(&self, buffer: &mut [P], pixel: F)
+    where
+        T: Sync,
+        P: Send,
+        F: Fn(usize) -> Option<P> + Sync,
+    {
+        let tile_size = (self.width * TILE_HEIGHT).max(1);
+        let tiles = Mutex::new(buffer.chunks_mut(tile_size).enumerate());
+        let render_tiles = || loop {
+            let next_tile = tiles.lock().unwrap().next();
+            let (tile_index, tile) = match next_tile {
+                Some(tile) => tile,
+                None => break,
+            };
+            for (index, value) in tile.iter_mut().enumerate() {
+                if let Some(new_value) = pixel(tile_index * tile_size + index) {
+                    *value = new_value;
+                }
+            }
+        };
+        if self.threads <= 1 {
+            render_tiles();
+        } else {
+            thread::scope(|scope| {
+                for _ in 0..self.threads {
+                    scope.spawn(render_tiles);
+                }
+            });
+        }
+    }
+
+    /// Marks the pixels differing from one of their neighbours by more than `threshold`
+    /// in any color channel.
+    fn adaptive_mask(&self, frame_buffer: &[Color<T>], threshold: T) -> Vec<bool> {
+        let differs = |first: Color<T>, second: Color<T>| {
+            (first.r - second.r).abs() > threshold
+                || (first.g - second.g).abs() > threshold
+                || (first.b - second.b).abs() > threshold
+        };
+        let mut mask = vec![false; frame_buffer.len()];
         for y_index in 0..self.height {
             for x_index in 0..self.width {
-                let dir_x = (x_index as f64 + 0.5) - self.width as f64 / 2.0;
-                let dir_y = -(y_index as f64 + 0.5) + self.height as f64 / 2.0;
-                let dir_z = -(self.height as f64) / (2.0 * (self.field_of_view / 2.0).tan());
-
-                let direction = Vec3D {
-                    x: dir_x.into(),
-                    y: dir_y.into(),
-                    z: dir_z.into(),
+                let index = y_index * self.width + x_index;
+                let color = frame_buffer[index];
+                if x_index + 1 < self.width && differs(color, frame_buffer[index + 1]) {
+                    mask[index] = true;
+                    mask[index + 1] = true;
+                }
+                if y_index + 1 < self.height && differs(color, frame_buffer[index + self.width]) {
+                    mask[index] = true;
+                    mask[index + self.width] = true;
                 }
-                .normalize();
-                let ray = Ray {
-                    origin: Vec3D::default(),
-                    direction,
-                };
-                self.frame_buffer[y_index * self.width + x_index] = scene.cast_ray(ray, 0);
             }
         }
+        mask
+    }
+
+    fn render_pixel(
+        &self,
+        scene: &Scene<T>,
+        seed: u64,
+        x_index: usize,
+        y_index: usize,
+        samples: usize,
+    ) -> (Color<T>, usize)
+    where
+        T: From<f64>,
+    {
+        let mut rng = self.pixel_rng(seed, x_index, y_index);
+        let filter = self.sampling.filter;
+        let mut weighted_samples = Vec::new();
+        let mut deepest = 0;
+        let mut offsets = self.sampling.offsets(samples, &mut rng);
+        // Path tracing repeats the grid of samples with new random offsets until the
+        // pixel gets enough paths, jittering the cells of a uniform grid so that the
+        // repeats do not trace the same positions again.
+        if let Integrator::PathTracing {
+            samples_per_pixel, ..
+        } = self.integrator
+        {
+            let repeats = match self.sampling.pattern {
+                Pattern::Uniform => Sampling {
+                    pattern: Pattern::Stratified,
+                    ..self.sampling
+                },
+                _ => self.sampling,
+            };
+            while offsets.len() < samples_per_pixel {
+                let more = repeats.offsets(samples, &mut rng);
+                offsets.extend(more);
+            }
+        }
+        // Samples spread over the shutter interval in strata of equal length, shuffled
+        // so that the times do not follow the positions within the pixel.
+        let shutter = self.camera.shutter_close - self.camera.shutter_open;
+        let mut time_strata: Vec<usize> = Vec::new();
+        if shutter > T::zero() {
+            time_strata.extend(0..offsets.len());
+            time_strata.shuffle(&mut rng);
+        }
+        for (index, (x_offset, y_offset)) in offsets.into_iter().enumerate() {
+            let weight = filter.weight(x_offset) * filter.weight(y_offset);
+            if weight == 0.0 {
+                continue;
+            }
+            let x = (x_index as f64 + 0.5 + x_offset) / self.width as f64;
+            let y = (y_index as f64 + 0.5 + y_offset) / self.height as f64;
+            let mut ray = if self.camera.aperture == T::zero() {
+                self.camera.ray(x.into(), y.into())
+            } else {
+                let (lens_x, lens_y): (f64, f64) = (rng.gen(), rng.gen());
+                self.camera
+                    .lens_ray(x.into(), y.into(), lens_x.into(), lens_y.into())
+            };
+            if let Some(stratum) = time_strata.get(index) {
+                let time = (*stratum as f64 + rng.gen::<f64>()) / time_strata.len() as f64;
+                ray.time = self.camera.shutter_open + shutter * time.into();
+            }
+            let sample = self.integrator.radiance(scene, ray, &mut rng);
+            weighted_samples.push((sample.color, weight));
+            deepest = deepest.max(sample.depth);
+        }
+        (filtered_mean(&weighted_samples), deepest)
+    }
+
+    /// Random generator depending only on the seed and the pixel, so that the image
+    /// does not depend on the order pixels are rendered in.
+    fn pixel_rng(&self, seed: u64, x_index: usize, y_index: usize) -> Pcg32 {
+        let pixel_index = (y_index * self.width + x_index) as u64;
+        Pcg32::seed_from_u64(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ pixel_index)
+    }
+}
+
+/// File format a frame is saved in.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// 8-bit image of the tone mapped colors.
+    Image(ImageFormat),
+    /// Radiance RGBE image of the linear colors.
+    Hdr,
+    /// Portable float map of the linear colors.
+    Pfm,
+}
+
+impl OutputFormat {
+    pub fn from_extension(extension: &str) -> Option<OutputFormat> {
+        match extension.to_ascii_lowercase().as_str() {
+            "hdr" => Some(OutputFormat::Hdr),
+            "pfm" => Some(OutputFormat::Pfm),
+            _ => ImageFormat::from_extension(extension).map(OutputFormat::Image),
+        }
+    }
+
+    pub fn from_path<Q: AsRef<Path>>(path: Q) -> Option<OutputFormat> {
+        OutputFormat::from_extension(path.as_ref().extension()?.to_str()?)
+    }
+
+    /// Usual file extension of the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Image(format) => format.extensions_str().first().copied().unwrap_or(""),
+            OutputFormat::Hdr => "hdr",
+            OutputFormat::Pfm => "pfm",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        OutputFormat::from_extension(name).ok_or_else(|| format!("unknown image format `{}`", name))
     }
 }
 
 impl<T: Real> Frame<T> {
+    /// Saves the frame in the format given by the extension of the path.
     pub fn save<Q: AsRef<Path>>(&self, path: Q) -> ImageResult<()> {
-        let mut image_buffer: ImageBuffer<Rgb<u8>, Vec<u8>> =
-            image::ImageBuffer::new(self.width as u32, self.height as u32);
-        for (x, y, pixel) in image_buffer.enumerate_pixels_mut() {
-            *pixel = self.frame_buffer[y as usize * self.width + x as usize].into();
-        }
-        image_buffer.save(path)
-    }
-
-    pub fn save_compressed<Q: AsRef<Path>>(&self, path: Q) -> ImageResult<()> {
-        let mut image_buffer: ImageBuffer<Rgb<u8>, Vec<u8>> =
-            image::ImageBuffer::new(self.width as u32 / 2, self.height as u32 / 2);
-        for (x, y, pixel) in image_buffer.enumerate_pixels_mut() {
-            let x_index = x as usize * 2;
-            let y_index = y as usize * 2;
-            let color: Color<T> = vec![
-                self.frame_buffer[y_index * self.width + x_index],
-                self.frame_buffer[y_index * self.width + x_index + 1],
-                self.frame_buffer[(y_index + 1) * self.width + x_index],
-                self.frame_buffer[(y_index + 1) * self.width + x_index + 1],
-            ]
-            .into_iter()
-            .fold(Color::zero(), |sum, x| sum + x)
-                / 4;
-            *pixel = color.into();
+        let path = path.as_ref();
+        match OutputFormat::from_path(path) {
+            Some(format) => self.save_with_format(path, format),
+            None => self.save_with_format(path, OutputFormat::Image(ImageFormat::from_path(path)?)),
+        }
+    }
+
+    pub fn save_with_format<Q: AsRef<Path>>(
+        &self,
+        path: Q,
+        format: OutputFormat,
+    ) -> ImageResult<()> {
+        self.save_buffer(&self.frame_buffer, path, format, || {
+            self.frame_buffer
+                .iter()
+                .map(|color| self.tone_mapping.map(*color))
+                .collect()
+        })
+    }
+
+    /// Saves the buffer of the AOV in the format given by the extension of the path.
+    pub fn save_aov<Q: AsRef<Path>>(&self, aov: Aov, path: Q) -> ImageResult<()> {
+        let path = path.as_ref();
+        let format = match OutputFormat::from_path(path) {
+            Some(format) => format,
+            None => OutputFormat::Image(ImageFormat::from_path(path)?),
+        };
+        self.save_aov_with_format(aov, path, format)
+    }
+
+    pub fn save_aov_with_format<Q: AsRef<Path>>(
+        &self,
+        aov: Aov,
+        path: Q,
+        format: OutputFormat,
+    ) -> ImageResult<()> {
+        let buffer = self.aov(aov).ok_or_else(|| {
+            ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(
+                format!("the {:?} AOV was not rendered", aov),
+            )))
+        })?;
+        self.save_buffer(buffer, path, format, || aov.view(buffer))
+    }
+
+    /// Saves the linear values of the buffer to high dynamic range formats, and the
+    /// pixels given by `view` to 8-bit images.
+    fn save_buffer<Q, F>(
+        &self,
+        buffer: &[Color<T>],
+        path: Q,
+        format: OutputFormat,
+        view: F,
+    ) -> ImageResult<()>
+    where
+        Q: AsRef<Path>,
+        F: FnOnce() -> Vec<Rgb<u8>>,
+    {
+        match format {
+            OutputFormat::Image(format) => {
+                let mut image_buffer: ImageBuffer<Rgb<u8>, Vec<u8>> =
+                    ImageBuffer::new(self.width as u32, self.height as u32);
+                for (pixel, value) in image_buffer.pixels_mut().zip(view()) {
+                    *pixel = value;
+                }
+                image_buffer.save_with_format(path, format)
+            }
+            OutputFormat::Hdr => {
+                let writer = BufWriter::new(File::create(path)?);
+                let pixels: Vec<Rgb<f32>> = buffer
+                    .iter()
+                    .map(|color| Rgb([color.r, color.g, color.b].map(channel_f32)))
+                    .collect();
+                HdrEncoder::new(writer).encode(&pixels, self.width, self.height)
+            }
+            OutputFormat::Pfm => {
+                let mut writer = BufWriter::new(File::create(path)?);
+                // A negative scale marks little-endian data, stored bottom row first.
+                write!(writer, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+                for row in buffer.chunks(self.width.max(1)).rev() {
+                    for color in row {
+                        for channel in [color.r, color.g, color.b] {
+                            writer.write_all(&channel_f32(channel).to_le_bytes())?;
+                        }
+                    }
+                }
+                writer.flush()?;
+                Ok(())
+            }
+        }
+    }
+}
+
+fn channel_f32<T: Real>(value: T) -> f32 {
+    value.to_f32().unwrap_or(0.0)
+}
+
+/// Mean of the samples of a pixel weighted by the reconstruction filter, the plain mean
+/// when the weights nearly cancel out, which filters with negative lobes can do.
+fn filtered_mean<T: Real + From<f64>>(samples: &[(Color<T>, f64)]) -> Color<T> {
+    if samples.is_empty() {
+        return Color::zero();
+    }
+    let total_weight: f64 = samples.iter().map(|(_, weight)| weight).sum();
+    let mean = if total_weight > MIN_TOTAL_WEIGHT * samples.len() as f64 {
+        samples
+            .iter()
+            .fold(Color::zero(), |color, (sample, weight)| {
+                color + *sample * <T as From<f64>>::from(weight / total_weight)
+            })
+    } else {
+        let share = <T as From<f64>>::from(1.0 / samples.len() as f64);
+        samples
+            .iter()
+            .fold(Color::zero(), |color, (sample, _)| color + *sample * share)
+    };
+    mean.max(Color::zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::shape::{Rectangle, Shape};
+    use crate::math::vec3d::Vec3D;
+    use crate::render::description::SceneDescription;
+    use crate::render::hittable::Hittable;
+    use crate::render::material::Material;
+    use crate::render::sampling::Filter;
+    use std::path::PathBuf;
+
+    fn tutorial_scene() -> Scene<f64> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("scenes/tutorial_scene.toml");
+        Scene::load(path).unwrap()
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let scene = tutorial_scene();
+        let mut serial: Frame<f64> = Frame::new(67, 45, 60.0);
+        serial.render(&scene);
+
+        for threads in &[2, 3, 8] {
+            let mut parallel: Frame<f64> = Frame::new(67, 45, 60.0);
+            parallel.threads = *threads;
+            parallel.render(&scene);
+            assert!(serial.frame_buffer == parallel.frame_buffer);
+        }
+    }
+
+    #[test]
+    fn test_depth_of_field_is_seeded() {
+        let scene = tutorial_scene();
+        let render = |seed, threads| {
+            let mut frame: Frame<f64> = Frame::new(40, 30, 60.0);
+            frame.camera.aperture = 0.5;
+            frame.camera.focus_distance = 12.0;
+            frame.seed = seed;
+            frame.threads = threads;
+            frame.render(&scene);
+            frame.frame_buffer
+        };
+        assert!(render(7, 1) == render(7, 1));
+        assert!(render(7, 1) == render(7, 4));
+        assert!(render(7, 1) != render(8, 1));
+    }
+
+    #[test]
+    fn test_single_sample_hits_pixel_center() {
+        let scene = tutorial_scene();
+        let mut frame: Frame<f64> = Frame::new(20, 10, 60.0);
+        frame.render(&scene);
+        let ray = frame.camera.ray(13.5 / 20.0, 4.5 / 10.0);
+        assert!(frame.frame_buffer[4 * 20 + 13] == scene.cast_ray(ray, 0));
+    }
+
+    /// Black wall covering the left half of the view in front of a white background.
+    fn edge_scene() -> Scene<f64> {
+        let wall = Hittable::Primitive {
+            shape: Shape::Rectangle(Rectangle {
+                corner: Vec3D::from([-100.0, -100.0, -1.0]),
+                u_edge: Vec3D::from([100.0, 0.0, 0.0]),
+                v_edge: Vec3D::from([0.0, 200.0, 0.0]),
+            }),
+            material: Material::default().into(),
+        };
+        Scene::new(Color::unit(), vec![wall], Vec::new(), 4)
+    }
+
+    #[test]
+    fn test_filters_across_edge() {
+        let scene = edge_scene();
+        let row = |filter, samples| {
+            let mut frame: Frame<f64> = Frame::new(8, 4, 60.0);
+            frame.sampling = Sampling {
+                samples,
+                filter,
+                ..Sampling::default()
+            };
+            frame.render(&scene);
+            frame.frame_buffer[8..16]
+                .iter()
+                .map(|color| color.r)
+                .collect::<Vec<_>>()
+        };
+        // The edge lies between the columns 3 and 4.
+        assert_eq!(
+            row(Filter::Box, 4),
+            vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0]
+        );
+        let tent = row(Filter::Tent, 4);
+        assert!(tent[3] > 0.0 && tent[3] < 0.5 && tent[4] > 0.5 && tent[4] < 1.0);
+        assert_eq!((tent[2], tent[5]), (0.0, 1.0));
+        // The negative lobes of Mitchell ring beyond the white side of the edge.
+        let mitchell = row(Filter::Mitchell, 8);
+        assert!(mitchell[3] > 0.0 && mitchell[4] < 1.0);
+        assert!(mitchell[5] > 1.0, "{:?}", mitchell);
+
+        // Paths repeating a uniform grid are jittered to new positions across the edge.
+        let mut frame: Frame<f64> = Frame::new(8, 4, 60.0);
+        frame.sampling.filter = Filter::Tent;
+        frame.integrator = Integrator::PathTracing {
+            samples_per_pixel: 16,
+            roulette_depth: 3,
+            max_depth: 4,
+        };
+        frame.render(&scene);
+        let color = frame.frame_buffer[8 + 3];
+        assert!(color.r > 0.0 && color.r < 1.0);
+    }
+
+    #[test]
+    fn test_mitchell_pixel_with_cancelling_weights() {
+        let scene = edge_scene();
+        let mut frame: Frame<f64> = Frame::new(8, 4, 60.0);
+        frame.sampling = Sampling {
+            samples: 2,
+            pattern: Pattern::Random,
+            filter: Filter::Mitchell,
+            ..Sampling::default()
+        };
+        // Looks for a pixel across the edge whose random samples have weights cancelling out.
+        let cancelling = (0..1000)
+            .flat_map(|seed| (2..6).map(move |x_index| (seed, x_index)))
+            .find_map(|(seed, x_index)| {
+                let mut rng = frame.pixel_rng(seed, x_index, 1);
+                let samples = frame
+                    .sampling
+                    .offsets(2, &mut rng)
+                    .into_iter()
+                    .map(|(x_offset, y_offset)| {
+                        let x = (x_index as f64 + 0.5 + x_offset) / 8.0;
+                        let y = (1.5 + y_offset) / 4.0;
+                        let weight =
+                            Filter::Mitchell.weight(x_offset) * Filter::Mitchell.weight(y_offset);
+                        (scene.cast_ray(frame.camera.ray(x, y), 0), weight)
+                    })
+                    .collect::<Vec<_>>();
+                let total_weight: f64 = samples.iter().map(|(_, weight)| weight).sum();
+                let mixed = samples.iter().any(|(color, _)| color.r != samples[0].0.r);
+                (mixed && total_weight.abs() <= MIN_TOTAL_WEIGHT * samples.len() as f64)
+                    .then_some((seed, x_index, samples))
+            });
+        let (seed, x_index, samples) = cancelling.expect("no pixel with cancelling weights");
+        let mean = samples.iter().map(|(color, _)| color.r).sum::<f64>() / samples.len() as f64;
+        let (color, _) = frame.render_pixel(&scene, seed, x_index, 1, 2);
+        assert!((color.r - mean).abs() < 1e-12, "{} {}", color.r, mean);
+    }
+
+    #[test]
+    fn test_path_tracing_is_seeded() {
+        let scene = tutorial_scene();
+        let render = |seed, threads| {
+            let mut frame: Frame<f64> = Frame::new(24, 18, 60.0);
+            frame.integrator = Integrator::PathTracing {
+                samples_per_pixel: 4,
+                roulette_depth: 2,
+                max_depth: 8,
+            };
+            frame.seed = seed;
+            frame.threads = threads;
+            frame.render(&scene);
+            frame.frame_buffer
+        };
+        let image = render(3, 1);
+        assert!(image == render(3, 2));
+        assert!(image != render(4, 1));
+
+        let mut whitted: Frame<f64> = Frame::new(24, 18, 60.0);
+        whitted.render(&scene);
+        assert!(image != whitted.frame_buffer);
+    }
+
+    #[test]
+    fn test_adaptive_sampling() {
+        let scene = tutorial_scene();
+        let mut frame: Frame<f64> = Frame::new(40, 30, 60.0);
+        frame.render(&scene);
+        let plain = frame.frame_buffer.clone();
+
+        let mask = frame.adaptive_mask(&frame.frame_buffer, 0.1);
+        let refined = mask.iter().filter(|refined| **refined).count();
+        assert!(refined > 0 && refined < mask.len());
+
+        frame.sampling.adaptive_threshold = Some(0.1);
+        frame.render(&scene);
+        for (index, refined) in mask.into_iter().enumerate() {
+            if !refined {
+                assert!(frame.frame_buffer[index] == plain[index]);
+            }
         }
-        image_buffer.save(path)
+    }
+
+    #[test]
+    fn test_progressive_rendering() {
+        let scene = tutorial_scene();
+        let mut frame: Frame<f64> = Frame::new(40, 30, 60.0);
+        frame.sampling.adaptive_threshold = Some(0.1);
+        frame.render(&scene);
+        let expected = frame.frame_buffer.clone();
+
+        let mut progressive: Frame<f64> = Frame::new(40, 30, 60.0);
+        progressive.sampling.adaptive_threshold = Some(0.1);
+        progressive.threads = 2;
+        let mut passes = Vec::new();
+        let completed = progressive.render_progressive(
+            &scene,
+            &Progressive::default(),
+            &AtomicBool::new(false),
+            |progress| {
+                passes.push((progress.pass, progress.passes, progress.fraction()));
+                if progress.pass == 1 {
+                    // Blocks of the preview share one color.
+                    assert!(progress.frame_buffer[0] == progress.frame_buffer[7 * 40 + 7]);
+                    assert!(progress.frame_buffer[0] == progress.frame_buffer[4 * 40 + 4]);
+                }
+            },
+        );
+        assert!(completed);
+        assert_eq!(
+            passes,
+            vec![(1, 3, 1.0 / 3.0), (2, 3, 2.0 / 3.0), (3, 3, 1.0)]
+        );
+        assert!(progressive.frame_buffer == expected);
+
+        let cancel = AtomicBool::new(false);
+        let mut cancelled: Frame<f64> = Frame::new(40, 30, 60.0);
+        let mut last_pass = 0;
+        let completed =
+            cancelled.render_progressive(&scene, &Progressive::default(), &cancel, |progress| {
+                last_pass = progress.pass;
+                cancel.store(true, Ordering::Relaxed);
+            });
+        assert!(!completed);
+        assert_eq!(last_pass, 1);
+        assert!(cancelled.frame_buffer != expected);
+
+        // A pass interrupted by the cancellation is not reported as finished.
+        let cancel = AtomicBool::new(true);
+        let mut events = 0;
+        let completed =
+            cancelled.render_progressive(&scene, &Progressive::default(), &cancel, |_| {
+                events += 1;
+            });
+        assert!(!completed);
+        assert_eq!(events, 0);
+    }
+
+    #[test]
+    fn test_refinement_passes() {
+        let scene = tutorial_scene();
+        let render = |refinement_passes| {
+            let mut frame: Frame<f64> = Frame::new(24, 18, 60.0);
+            frame.integrator = Integrator::PathTracing {
+                samples_per_pixel: 2,
+                roulette_depth: 2,
+                max_depth: 8,
+            };
+            let progressive = Progressive {
+                preview_block: 4,
+                refinement_passes,
+            };
+            let mut passes = 0;
+            frame.render_progressive(&scene, &progressive, &AtomicBool::new(false), |_| {
+                passes += 1
+            });
+            assert_eq!(passes, 2 + refinement_passes);
+            frame.frame_buffer
+        };
+        let image = render(0);
+        assert!(image != render(2));
+        assert!(render(2) == render(2));
+    }
+
+    #[test]
+    fn test_motion_blur() {
+        let description: SceneDescription<f64> = SceneDescription::parse(
+            r#"
+            [[objects]]
+            type = "moving"
+            object = { type = "sphere", center = [-1.0, 0.0, -5.0], radius = 1.0, material = { diffuse = [1.0, 1.0, 1.0] } }
+            end = [2.0, 0.0, 0.0]
+
+            [[lights]]
+            position = [0.0, 5.0, 0.0]
+            intensity = 1.0
+            "#,
+        )
+        .unwrap();
+        let scene = description.scene().unwrap();
+        let render = |shutter_close, seed| {
+            let mut frame: Frame<f64> = Frame::new(32, 16, 60.0);
+            frame.camera.shutter_close = shutter_close;
+            frame.sampling.samples = 3;
+            frame.seed = seed;
+            frame.render(&scene);
+            frame.frame_buffer
+        };
+        let still = render(0.0, 0);
+        let blurred = render(1.0, 0);
+        assert!(blurred != still);
+        assert!(blurred == render(1.0, 0));
+        assert!(blurred != render(1.0, 1));
+        // Only the start of the motion is seen without blur, the middle of the path is
+        // covered part of the time.
+        let row = 8 * 32;
+        let (left, middle) = (row + 11, row + 16);
+        assert!(still[left] != Color::zero() && still[middle] == Color::zero());
+        assert!(blurred[middle] != Color::zero());
+        assert!(blurred[left].r < still[left].r);
+    }
+
+    #[test]
+    fn test_aov_buffers() {
+        let scene = tutorial_scene();
+        let mut frame: Frame<f64> = Frame::new(20, 10, 60.0);
+        frame.aovs = vec![Aov::Depth, Aov::ObjectId];
+        frame.render(&scene);
+        let ray = frame.camera.ray(13.5 / 20.0, 4.5 / 10.0);
+        let depth = frame.aov(Aov::Depth).unwrap();
+        assert_eq!(depth.len(), 200);
+        assert!(Some(depth[4 * 20 + 13]) == Aov::Depth.value(&scene, ray));
+        assert!(
+            Some(frame.aov(Aov::ObjectId).unwrap()[4 * 20 + 13])
+                == Aov::ObjectId.value(&scene, ray)
+        );
+        assert!(frame.aov(Aov::Normal).is_none());
+
+        // Between two mirrors the rays are reflected as deep as the scene allows.
+        let mirrors: Scene<f64> = SceneDescription::parse(
+            r#"
+            recursion_depth = 2
+
+            [[objects]]
+            type = "plane"
+            origin = [0.0, 0.0, -5.0]
+            normal = [0.0, 0.0, 1.0]
+            material = { reflectiveness = 1.0 }
+
+            [[objects]]
+            type = "plane"
+            origin = [0.0, 0.0, 5.0]
+            normal = [0.0, 0.0, -1.0]
+            material = { reflectiveness = 1.0 }
+            "#,
+        )
+        .unwrap()
+        .scene()
+        .unwrap();
+        let mut frame: Frame<f64> = Frame::new(4, 2, 60.0);
+        frame.aovs = vec![Aov::RecursionDepth];
+        frame.render(&mirrors);
+        assert!(frame
+            .aov(Aov::RecursionDepth)
+            .unwrap()
+            .iter()
+            .all(|depth| depth.r == 3.0));
+        frame.integrator = Integrator::PathTracing {
+            samples_per_pixel: 1,
+            roulette_depth: 10,
+            max_depth: 5,
+        };
+        frame.render(&mirrors);
+        assert!(frame
+            .aov(Aov::RecursionDepth)
+            .unwrap()
+            .iter()
+            .all(|depth| depth.r == 6.0));
+
+        let path = std::env::temp_dir().join("raytracing-missing-aov.png");
+        assert!(frame.save_aov(Aov::Normal, &path).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_high_dynamic_range_output() {
+        let mut frame: Frame<f64> = Frame::new(3, 2, 60.0);
+        for (index, color) in frame.frame_buffer.iter_mut().enumerate() {
+            *color = Color::from([index as f64 * 4.0, 0.5, 0.0]);
+        }
+        let directory = std::env::temp_dir().join(format!("raytracing-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let path = directory.join("frame.pfm");
+        frame.save(&path).unwrap();
+        let data = std::fs::read(&path).unwrap();
+        let header = b"PF\n3 2\n-1.0\n";
+        assert_eq!(&data[..header.len()], header);
+        let floats: Vec<f32> = data[header.len()..]
+            .chunks(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+        // Bottom row first.
+        assert_eq!(
+            floats,
+            vec![
+                12.0, 0.5, 0.0, 16.0, 0.5, 0.0, 20.0, 0.5, 0.0, 0.0, 0.5, 0.0, 4.0, 0.5, 0.0, 8.0,
+                0.5, 0.0
+            ]
+        );
+
+        let path = directory.join("frame.hdr");
+        frame.save(&path).unwrap();
+        let reader = std::io::BufReader::new(File::open(&path).unwrap());
+        let pixels = image::codecs::hdr::HdrDecoder::new(reader)
+            .unwrap()
+            .read_image_hdr()
+            .unwrap();
+        assert_eq!(pixels.len(), 6);
+        assert!((pixels[5].0[0] - 20.0).abs() < 0.1);
+        assert!((pixels[5].0[1] - 0.5).abs() < 0.01);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            OutputFormat::from_str("PNG"),
+            Ok(OutputFormat::Image(ImageFormat::Png))
+        );
+        assert!(OutputFormat::from_str("exr").is_err());
     }
 }
@@ -1,76 +1,127 @@
-use crate::math::color::Color;
+use crate::math::aabb::Aabb;
 use crate::math::ray::Ray;
-use crate::math::shape::{Intersect, Plane, Shape};
-use crate::math::vec3d::Vec3D;
-use crate::render::material::Material;
+use crate::math::shape::{Intersect, Shape};
+use crate::render::csg::Csg;
+use crate::render::instance::Instance;
+use crate::render::material::TexturedMaterial;
+use crate::render::mesh::Mesh;
+use crate::render::motion::Motion;
 use crate::render::scene::IntersectResult;
 use num_traits::real::Real;
+use std::sync::Arc;
 
 pub enum Hittable<T: Real> {
     Primitive {
         shape: Shape<T>,
-        material: Material<T>,
+        material: TexturedMaterial<T>,
     },
-    CheckerBoard {
-        plane: Plane<T>,
-        width: T,
-        basic_material: Material<T>,
-        checker_color: Color<T>,
-        checker_size: T,
+    Mesh {
+        mesh: Arc<Mesh<T>>,
+        material: TexturedMaterial<T>,
     },
+    Instance(Instance<T>),
+    Csg(Csg<T>),
+    Moving(Motion<T>),
+}
+
+/// Ray hit of an object, `element` tells which part of a composite object is hit.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Hit<T: Real> {
+    pub distance: T,
+    pub element: usize,
+}
+
+impl<T: Real> Hit<T> {
+    pub(crate) fn new(distance: T) -> Hit<T> {
+        Hit {
+            distance,
+            element: 0,
+        }
+    }
+}
+
+/// Part of the line of a ray inside a solid object.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Span<T: Real> {
+    pub enter: Hit<T>,
+    pub exit: Hit<T>,
 }
 
 impl<T: Real> Hittable<T> {
-    pub fn intersect(&self, ray: Ray<T>) -> Option<T> {
+    pub fn intersect(&self, ray: Ray<T>) -> Option<Hit<T>> {
         match self {
-            Hittable::Primitive { shape, .. } => shape.intersect(ray),
-            Hittable::CheckerBoard { plane, width, .. } => {
-                if let Some(t) = plane.intersect(ray) {
-                    let point = ray.point_at(t);
-                    if point.x < plane.origin.x
-                        || point.z < plane.origin.z
-                        || point.x - plane.origin.x > *width
-                        || point.z - plane.origin.z > *width
-                    {
-                        None
-                    } else {
-                        Some(t)
-                    }
-                } else {
-                    None
-                }
-            }
+            Hittable::Primitive { shape, .. } => shape.intersect(ray).map(Hit::new),
+            Hittable::Mesh { mesh, .. } => mesh
+                .intersect(ray)
+                .map(|(distance, element)| Hit { distance, element }),
+            Hittable::Instance(instance) => instance.intersect(ray),
+            Hittable::Csg(csg) => csg.intersect(ray),
+            Hittable::Moving(motion) => motion.intersect(ray),
         }
     }
 
-    pub fn intersection_result(&self, point: Vec3D<T>) -> IntersectResult<T> {
+    /// Sorted and disjoint spans of the whole line of the ray inside the object, see
+    /// `Intersect::intervals`. Meshes are not assumed to be closed and have no inside.
+    pub fn spans(&self, ray: Ray<T>) -> Vec<Span<T>> {
         match self {
-            Hittable::Primitive { shape, material } => IntersectResult {
-                point,
-                normal: shape.normal(point),
-                material: *material,
-            },
-            Hittable::CheckerBoard {
-                plane,
-                basic_material,
-                checker_color,
-                checker_size,
-                ..
-            } => {
-                let mut material = *basic_material;
-                if ((point.x / *checker_size).floor().to_i32().unwrap()
-                    + (point.z / *checker_size).floor().to_i32().unwrap())
-                    % 2
-                    == 0
-                {
-                    material.diffuse = *checker_color;
+            Hittable::Primitive { shape, .. } => shape
+                .intervals(ray)
+                .into_iter()
+                .map(|(enter, exit)| Span {
+                    enter: Hit::new(enter),
+                    exit: Hit::new(exit),
+                })
+                .collect(),
+            Hittable::Mesh { .. } => Vec::new(),
+            Hittable::Instance(instance) => instance.spans(ray),
+            Hittable::Csg(csg) => csg.spans(ray),
+            Hittable::Moving(motion) => motion.spans(ray),
+        }
+    }
+
+    /// Box containing the whole object, `None` for unbounded objects.
+    pub fn bounding_box(&self) -> Option<Aabb<T>> {
+        match self {
+            Hittable::Primitive { shape, .. } => shape.bounding_box(),
+            Hittable::Mesh { mesh, .. } => mesh.bounding_box(),
+            Hittable::Instance(instance) => instance.bounding_box(),
+            Hittable::Csg(csg) => csg.bounding_box(),
+            Hittable::Moving(motion) => motion.bounding_box(),
+        }
+    }
+
+    pub fn intersection_result(&self, ray: Ray<T>, hit: Hit<T>) -> IntersectResult<T> {
+        let point = ray.point_at(hit.distance);
+        match self {
+            Hittable::Primitive { shape, material } => {
+                let uv = shape.uv(point);
+                let normal = match &material.normal_map {
+                    Some(normal_map) => normal_map.normal(shape.tangent_frame(point), uv, point),
+                    None => shape.normal(point),
+                };
+                IntersectResult {
+                    point,
+                    normal,
+                    material: material.at(uv, point),
                 }
+            }
+            Hittable::Mesh { mesh, material } => {
+                let uv = mesh.uv(point, hit.element);
+                let normal = match &material.normal_map {
+                    Some(normal_map) => {
+                        normal_map.normal(mesh.tangent_frame(point, hit.element), uv, point)
+                    }
+                    None => mesh.normal(point, hit.element),
+                };
                 IntersectResult {
                     point,
-                    normal: plane.normal,
-                    material,
+                    normal,
+                    material: material.at(uv, point),
                 }
             }
+            Hittable::Instance(instance) => instance.intersection_result(ray, hit),
+            Hittable::Csg(csg) => csg.intersection_result(ray, hit),
+            Hittable::Moving(motion) => motion.intersection_result(ray, hit),
         }
     }
 }
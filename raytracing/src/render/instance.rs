@@ -0,0 +1,202 @@
+use crate::math::aabb::Aabb;
+use crate::math::matrix::Matrix4;
+use crate::math::ray::Ray;
+use crate::math::vec3d::Vec3D;
+use crate::render::hittable::{Hit, Hittable, Span};
+use crate::render::scene::IntersectResult;
+use num_traits::real::Real;
+use std::sync::Arc;
+
+/// Shared object placed in the scene by an affine transform from its own object space.
+pub struct Instance<T: Real> {
+    object: Arc<Hittable<T>>,
+    transform: Matrix4<T>,
+    inverse: Matrix4<T>,
+}
+
+impl<T: Real> Instance<T> {
+    /// `None` if the transform is singular.
+    pub fn new(object: Arc<Hittable<T>>, transform: Matrix4<T>) -> Option<Instance<T>> {
+        Some(Instance {
+            object,
+            inverse: transform.inverse()?,
+            transform,
+        })
+    }
+
+    pub fn object(&self) -> &Hittable<T> {
+        &self.object
+    }
+
+    pub fn transform(&self) -> Matrix4<T> {
+        self.transform
+    }
+
+    /// Ray in object space with a unit direction, and the object space length of
+    /// a unit of distance along the original ray.
+    fn object_ray(&self, ray: Ray<T>) -> (Ray<T>, T) {
+        let direction = self.inverse.transform_vector(ray.direction);
+        let scale = direction.norm();
+        let object_ray = Ray {
+            origin: self.inverse.transform_point(ray.origin),
+            direction: direction * (T::one() / scale),
+            time: ray.time,
+        };
+        (object_ray, scale)
+    }
+
+    pub fn intersect(&self, ray: Ray<T>) -> Option<Hit<T>> {
+        let (object_ray, scale) = self.object_ray(ray);
+        let hit = self.object.intersect(object_ray)?;
+        Some(Hit {
+            distance: hit.distance / scale,
+            ..hit
+        })
+    }
+
+    pub fn spans(&self, ray: Ray<T>) -> Vec<Span<T>> {
+        let (object_ray, scale) = self.object_ray(ray);
+        let unscale = |hit: Hit<T>| Hit {
+            distance: if hit.distance == T::min_value() || hit.distance == T::max_value() {
+                hit.distance
+            } else {
+                hit.distance / scale
+            },
+            ..hit
+        };
+        self.object
+            .spans(object_ray)
+            .into_iter()
+            .map(|span| Span {
+                enter: unscale(span.enter),
+                exit: unscale(span.exit),
+            })
+            .collect()
+    }
+
+    pub fn intersection_result(&self, ray: Ray<T>, hit: Hit<T>) -> IntersectResult<T> {
+        let (object_ray, scale) = self.object_ray(ray);
+        let object_hit = Hit {
+            distance: hit.distance * scale,
+            ..hit
+        };
+        let result = self.object.intersection_result(object_ray, object_hit);
+        IntersectResult {
+            point: ray.point_at(hit.distance),
+            normal: self
+                .inverse
+                .transpose()
+                .transform_vector(result.normal)
+                .normalize(),
+            ..result
+        }
+    }
+
+    /// Box around the transformed corners of the object's box.
+    pub fn bounding_box(&self) -> Option<Aabb<T>> {
+        let bounds = self.object.bounding_box()?;
+        (0..8)
+            .map(|corner| {
+                let point = self.transform.transform_point(Vec3D {
+                    x: if corner & 1 == 0 {
+                        bounds.min.x
+                    } else {
+                        bounds.max.x
+                    },
+                    y: if corner & 2 == 0 {
+                        bounds.min.y
+                    } else {
+                        bounds.max.y
+                    },
+                    z: if corner & 4 == 0 {
+                        bounds.min.z
+                    } else {
+                        bounds.max.z
+                    },
+                });
+                Aabb {
+                    min: point,
+                    max: point,
+                }
+            })
+            .reduce(Aabb::union)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::shape::{Shape, Sphere};
+    use crate::render::material::Material;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn unit_sphere() -> Arc<Hittable<f64>> {
+        Arc::new(Hittable::Primitive {
+            shape: Shape::Sphere(Sphere {
+                center: Vec3D::from([0.0, 0.0, 0.0]),
+                radius: 1.0,
+            }),
+            material: Material::default().into(),
+        })
+    }
+
+    fn ray(origin: [f64; 3], direction: [f64; 3]) -> Ray<f64> {
+        Ray {
+            origin: Vec3D::from(origin),
+            direction: Vec3D::from(direction).normalize(),
+            time: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_scaled_instance() {
+        // An ellipsoid with semi-axes 2, 1 and 1 moved to (0, 0, -10).
+        let transform = Matrix4::translation(Vec3D::from([0.0, 0.0, -10.0]))
+            * Matrix4::scaling(Vec3D::from([2.0, 1.0, 1.0]));
+        let instance = Instance::new(unit_sphere(), transform).unwrap();
+
+        let side = ray([5.0, 0.0, -10.0], [-1.0, 0.0, 0.0]);
+        let hit = instance.intersect(side).unwrap();
+        assert!((hit.distance - 3.0).abs() < 1e-12);
+        let front = ray([0.0, 0.0, 0.0], [0.0, 0.0, -1.0]);
+        let hit = instance.intersect(front).unwrap();
+        assert!((hit.distance - 9.0).abs() < 1e-12);
+        assert!(instance
+            .intersect(ray([1.5, 1.5, 0.0], [0.0, 0.0, -1.0]))
+            .is_none());
+
+        // The normal of the stretched sphere at (sqrt(2), sqrt(0.5)) leans towards Y.
+        let diagonal = ray([2.0f64.sqrt(), 5.0, -10.0], [0.0, -1.0, 0.0]);
+        let hit = instance.intersect(diagonal).unwrap();
+        let result = instance.intersection_result(diagonal, hit);
+        assert!((result.point.y - 0.5f64.sqrt()).abs() < 1e-9);
+        let expected = Vec3D::from([0.5f64.sqrt() / 2.0, 0.5f64.sqrt(), 0.0]).normalize();
+        assert!((result.normal - expected).norm() < 1e-9);
+
+        let bounds = instance.bounding_box().unwrap();
+        assert_eq!((bounds.min.x, bounds.max.x), (-2.0, 2.0));
+        assert_eq!((bounds.min.z, bounds.max.z), (-11.0, -9.0));
+    }
+
+    #[test]
+    fn test_rotated_instance() {
+        let object = Arc::new(Hittable::Primitive {
+            shape: Shape::Sphere(Sphere {
+                center: Vec3D::from([3.0, 0.0, 0.0]),
+                radius: 1.0,
+            }),
+            material: Material::default().into(),
+        });
+        let instance = Instance::new(object, Matrix4::rotation_z(FRAC_PI_2)).unwrap();
+        let down = ray([0.0, 10.0, 0.0], [0.0, -1.0, 0.0]);
+        let hit = instance.intersect(down).unwrap();
+        assert!((hit.distance - 6.0).abs() < 1e-9);
+        let result = instance.intersection_result(down, hit);
+        assert!((result.normal - Vec3D::from([0.0, 1.0, 0.0])).norm() < 1e-9);
+        assert!(Instance::new(
+            unit_sphere(),
+            Matrix4::scaling(Vec3D::from([1.0, 0.0, 1.0]))
+        )
+        .is_none());
+    }
+}
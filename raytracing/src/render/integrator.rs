@@ -0,0 +1,302 @@
+use crate::math::color::Color;
+use crate::math::ray::Ray;
+use crate::math::shape::TangentFrame;
+use crate::math::vec3d::Vec3D;
+use crate::render::lightning::{dielectric, phong_lightning, reflect};
+use crate::render::medium::Media;
+use crate::render::scene::{Scene, Traced};
+use num_traits::real::Real;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Highest probability of a path to survive the Russian roulette, so that no path
+/// goes on forever.
+const MAX_SURVIVAL: f64 = 0.95;
+
+/// How the color seen along a camera ray is computed.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Integrator {
+    /// Phong lighting by the lights with recursive mirror reflection and refraction.
+    #[default]
+    Whitted,
+    /// Monte Carlo path tracing, adding the light reflected diffusely between the
+    /// objects and the light of the environment.
+    PathTracing {
+        /// Paths traced per pixel, rounded up to whole grids of the pixel samples.
+        #[serde(default = "default_samples_per_pixel")]
+        samples_per_pixel: usize,
+        /// Bounces after which paths are terminated randomly by Russian roulette.
+        #[serde(default = "default_roulette_depth")]
+        roulette_depth: usize,
+        /// Bounces after which paths are always terminated.
+        #[serde(default = "default_max_depth")]
+        max_depth: usize,
+    },
+}
+
+fn default_samples_per_pixel() -> usize {
+    16
+}
+
+fn default_roulette_depth() -> usize {
+    3
+}
+
+fn default_max_depth() -> usize {
+    64
+}
+
+impl Integrator {
+    pub fn path_tracing() -> Integrator {
+        Integrator::PathTracing {
+            samples_per_pixel: default_samples_per_pixel(),
+            roulette_depth: default_roulette_depth(),
+            max_depth: default_max_depth(),
+        }
+    }
+
+    /// Color seen along the ray, `rng` gives the random numbers of path tracing.
+    pub fn radiance<T: Real, R: Rng>(
+        &self,
+        scene: &Scene<T>,
+        ray: Ray<T>,
+        rng: &mut R,
+    ) -> Traced<T> {
+        match *self {
+            Integrator::Whitted => scene.cast_ray_through(ray, 0, &Media::new()),
+            Integrator::PathTracing {
+                roulette_depth,
+                max_depth,
+                ..
+            } => trace_path(scene, ray, roulette_depth, max_depth, rng),
+        }
+    }
+}
+
+/// Estimates the light along the ray by following a single path. Every surface on
+/// the path emits its ambient color and gets the Phong lighting of the lights, then
+/// the path goes on by a diffuse bounce, a mirror reflection or a refraction chosen
+/// randomly in proportion to the diffuse color, the reflectiveness and the transparency.
+/// Refraction is itself chosen against reflection by the Fresnel reflectance, and the
+/// light is absorbed by the media the path travels through.
+fn trace_path<T: Real, R: Rng>(
+    scene: &Scene<T>,
+    ray: Ray<T>,
+    roulette_depth: usize,
+    max_depth: usize,
+    rng: &mut R,
+) -> Traced<T> {
+    let mut radiance = Color::zero();
+    let mut throughput = Color::unit();
+    let mut ray = ray;
+    let mut media = Media::new();
+    let mut hits = 0;
+    for depth in 0..=max_depth {
+        let medium = media.current();
+        let result = scene.intersect(ray);
+        let distance = match result {
+            Some(result) => (result.point - ray.origin).norm(),
+            None => T::max_value(),
+        };
+        let (transmittance, scattered) = scene.participating_media(ray, distance, &media);
+        radiance = radiance + throughput * scattered;
+        throughput = throughput * transmittance * medium.transmittance(distance);
+        let result = match result {
+            Some(result) => result,
+            None => {
+                return Traced {
+                    color: radiance + throughput * scene.environment.color(ray.direction),
+                    depth: hits,
+                }
+            }
+        };
+        hits = depth + 1;
+        radiance = radiance + throughput * phong_lightning(scene, ray, result);
+        if depth == max_depth {
+            break;
+        }
+
+        let material = result.material;
+        let diffuse_weight =
+            (material.diffuse.r + material.diffuse.g + material.diffuse.b) / T::from(3.0).unwrap();
+        let total_weight = diffuse_weight + material.reflectiveness + material.transparency;
+        if total_weight <= T::zero() {
+            break;
+        }
+        let choice = random::<T, R>(rng) * total_weight;
+        let (direction, filter) = if choice < material.reflectiveness {
+            (reflect(ray.direction, result.normal), Color::unit())
+        } else if choice < material.reflectiveness + material.transparency
+            || diffuse_weight <= T::zero()
+        {
+            let (reflectance, refraction) =
+                dielectric(ray.direction, result.normal, &material, &media);
+            match refraction {
+                Some(refraction) if random::<T, R>(rng) >= reflectance => {
+                    media = refraction.media;
+                    (refraction.direction, Color::unit())
+                }
+                _ => (reflect(ray.direction, result.normal), Color::unit()),
+            }
+        } else {
+            let normal = if result.normal * ray.direction > T::zero() {
+                -result.normal
+            } else {
+                result.normal
+            };
+            (
+                cosine_direction(normal, rng),
+                material.diffuse * (T::one() / diffuse_weight),
+            )
+        };
+        throughput = throughput * filter * total_weight;
+
+        if depth >= roulette_depth {
+            let survival = throughput
+                .r
+                .max(throughput.g)
+                .max(throughput.b)
+                .min(T::from(MAX_SURVIVAL).unwrap());
+            if random::<T, R>(rng) >= survival {
+                break;
+            }
+            throughput = throughput * (T::one() / survival);
+        }
+        ray = Ray {
+            origin: result.point,
+            direction: direction.normalize(),
+            time: ray.time,
+        };
+    }
+    Traced {
+        color: radiance,
+        depth: hits,
+    }
+}
+
+/// Random direction around the unit normal with a density proportional to the cosine
+/// of its angle to the normal.
+fn cosine_direction<T: Real, R: Rng>(normal: Vec3D<T>, rng: &mut R) -> Vec3D<T> {
+    let frame = TangentFrame::new(normal, Vec3D::default(), Vec3D::default());
+    let angle = T::from(2.0 * std::f64::consts::PI).unwrap() * random(rng);
+    let radius_square: T = random(rng);
+    let radius = radius_square.sqrt();
+    frame.to_world(Vec3D {
+        x: radius * angle.cos(),
+        y: radius * angle.sin(),
+        z: (T::one() - radius_square).sqrt(),
+    })
+}
+
+fn random<T: Real, R: Rng>(rng: &mut R) -> T {
+    T::from(rng.gen::<f64>()).unwrap()
+}
+
+impl FromStr for Integrator {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "whitted" => Ok(Integrator::Whitted),
+            "path_tracing" => Ok(Integrator::path_tracing()),
+            _ => Err(format!(
+                "unknown integrator `{}`, expected `whitted` or `path_tracing`",
+                name
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::shape::{Plane, Shape};
+    use crate::render::hittable::Hittable;
+    use crate::render::material::Material;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg32;
+
+    fn plane(height: f64, normal: f64, material: Material<f64>) -> Hittable<f64> {
+        Hittable::Primitive {
+            shape: Shape::Plane(Plane {
+                origin: Vec3D::from([0.0, height, 0.0]),
+                normal: Vec3D::from([0.0, normal, 0.0]),
+            }),
+            material: material.into(),
+        }
+    }
+
+    fn gray(value: f64) -> Color<f64> {
+        Color::from([value, value, value])
+    }
+
+    fn downwards() -> Ray<f64> {
+        Ray {
+            origin: Vec3D::from([0.0, 1.0, 0.0]),
+            direction: Vec3D::from([0.3, -1.0, 0.2]).normalize(),
+            time: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_diffuse_bounce_sees_background() {
+        let floor = Material {
+            diffuse: gray(0.5),
+            ..Material::default()
+        };
+        let background = Color::from([1.0, 0.5, 0.25]);
+        let scene = Scene::new(background, vec![plane(0.0, 1.0, floor)], Vec::new(), 4);
+        let mut rng = Pcg32::seed_from_u64(1);
+        for _ in 0..10 {
+            let color = Integrator::path_tracing()
+                .radiance(&scene, downwards(), &mut rng)
+                .color;
+            assert!((color.r - 0.5).abs() < 1e-12);
+            assert!((color.g - 0.25).abs() < 1e-12);
+            assert!((color.b - 0.125).abs() < 1e-12);
+        }
+        // The Whitted integrator sees no light on the floor.
+        let color = Integrator::Whitted
+            .radiance(&scene, downwards(), &mut rng)
+            .color;
+        assert!(color == Color::zero());
+    }
+
+    #[test]
+    fn test_russian_roulette_is_unbiased() {
+        // Between two glowing planes reflecting half of the light, the light bounced
+        // back and forth sums up to `emission / (1 - albedo)`.
+        let wall = Material {
+            ambient: gray(0.1),
+            diffuse: gray(0.5),
+            ..Material::default()
+        };
+        let scene = Scene::new(
+            Color::zero(),
+            vec![plane(0.0, 1.0, wall), plane(2.0, -1.0, wall)],
+            Vec::new(),
+            4,
+        );
+        let mut rng = Pcg32::seed_from_u64(7);
+        let paths = 20000;
+        let total = (0..paths).fold(0.0, |total, _| {
+            total
+                + Integrator::path_tracing()
+                    .radiance(&scene, downwards(), &mut rng)
+                    .color
+                    .r
+        });
+        let mean = total / paths as f64;
+        assert!((mean - 0.2).abs() < 0.005, "{}", mean);
+
+        let truncated = Integrator::PathTracing {
+            samples_per_pixel: 1,
+            roulette_depth: 10,
+            max_depth: 1,
+        };
+        let color = truncated.radiance(&scene, downwards(), &mut rng).color;
+        assert!((color.r - 0.15).abs() < 1e-12);
+    }
+}
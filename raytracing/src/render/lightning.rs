@@ -1,12 +1,334 @@
 use crate::math::color::Color;
 use crate::math::ray::Ray;
+use crate::math::shape::TangentFrame;
 use crate::math::vec3d::Vec3D;
-use crate::render::scene::{IntersectResult, Scene};
+use crate::render::material::Material;
+use crate::render::medium::{Media, Medium};
+use crate::render::scene::{IntersectResult, Scene, Traced};
 use num_traits::real::Real;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use serde::de::value::SeqAccessDeserializer;
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::f64::consts::PI;
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
 
+#[derive(Copy, Clone, Deserialize, Serialize)]
+#[serde(
+    deny_unknown_fields,
+    bound(
+        deserialize = "T: Real + Deserialize<'de>",
+        serialize = "T: Real + Serialize"
+    )
+)]
 pub struct Light<T: Real> {
-    pub position: Vec3D<T>,
-    pub intensity: T,
+    /// Position of a point or spot light, the center of an area light. Lights without
+    /// a position are directional lights, shining along `direction` from infinitely far.
+    #[serde(default)]
+    pub position: Option<Vec3D<T>>,
+    /// Direction the light of a directional light travels in, the axis of a spot light.
+    #[serde(default)]
+    pub direction: Option<Vec3D<T>>,
+    /// Color of the light, a single number for white light.
+    #[serde(deserialize_with = "light_intensity")]
+    pub intensity: Color<T>,
+    #[serde(default)]
+    pub attenuation: Attenuation<T>,
+    /// Cone of a spot light around its direction.
+    #[serde(default)]
+    pub spot: Option<Spot<T>>,
+    #[serde(default)]
+    pub shape: LightShape<T>,
+    /// Number of shadow rays cast towards an area light from every lit point.
+    #[serde(default = "default_light_samples")]
+    pub samples: usize,
+}
+
+/// How objects between a point and a light block the light.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Shadows {
+    /// Any object blocks the light completely.
+    Opaque,
+    /// Transparent objects let through their transparency of the light, and
+    /// absorb it along the way inside them.
+    #[default]
+    Transparent,
+    /// Like `Transparent`, with the light also tinted by the diffuse color of
+    /// the objects.
+    Colored,
+}
+
+/// Shape of a light around its position. Area lights are sampled by several shadow
+/// rays towards random points of them, softening the edges of their shadows.
+#[derive(Copy, Clone, Default, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum LightShape<T: Real> {
+    #[default]
+    Point,
+    /// Parallelogram centered at the light position.
+    Rectangle {
+        u_edge: Vec3D<T>,
+        v_edge: Vec3D<T>,
+    },
+    Disk {
+        normal: Vec3D<T>,
+        radius: T,
+    },
+    Sphere {
+        radius: T,
+    },
+}
+
+/// How the light of a positioned light fades with the distance.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Attenuation<T: Real> {
+    #[default]
+    None,
+    InverseSquare,
+    /// Light divided by `constant + linear * distance + quadratic * distance^2`.
+    Custom {
+        #[serde(default = "T::one")]
+        constant: T,
+        #[serde(default = "T::zero")]
+        linear: T,
+        #[serde(default = "T::zero")]
+        quadratic: T,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spot<T: Real> {
+    /// Angle in degrees between the axis and the edge of the lit cone.
+    pub angle: T,
+    /// Fraction of the angle, from the edge of the cone inwards, over which the light
+    /// fades out smoothly. Zero gives a hard edge.
+    #[serde(default = "T::zero")]
+    pub falloff: T,
+}
+
+/// Light arriving at a point from one sample of a light.
+#[derive(Copy, Clone)]
+pub struct LightSample<T: Real> {
+    /// Unit direction from the point towards the light.
+    pub direction: Vec3D<T>,
+    /// Distance to the light, the largest value for directional lights.
+    pub distance: T,
+    pub intensity: Color<T>,
+}
+
+fn default_light_samples() -> usize {
+    16
+}
+
+impl<T: Real> Light<T> {
+    pub fn point(position: Vec3D<T>, intensity: Color<T>) -> Light<T> {
+        Light {
+            position: Some(position),
+            direction: None,
+            intensity,
+            attenuation: Attenuation::None,
+            spot: None,
+            shape: LightShape::Point,
+            samples: default_light_samples(),
+        }
+    }
+
+    pub fn directional(direction: Vec3D<T>, intensity: Color<T>) -> Light<T> {
+        Light {
+            position: None,
+            direction: Some(direction),
+            ..Light::point(Vec3D::default(), intensity)
+        }
+    }
+
+    /// Light reaching `point`, one sample for every shadow ray to cast, the samples of
+    /// an area light sharing its intensity equally.
+    pub fn samples_at<R: Rng>(&self, point: Vec3D<T>, rng: &mut R) -> Vec<LightSample<T>> {
+        let position = match self.position {
+            Some(position) => position,
+            None => {
+                let direction = self.direction.unwrap_or_default();
+                return vec![LightSample {
+                    direction: -direction.normalize(),
+                    distance: T::max_value(),
+                    intensity: self.intensity,
+                }];
+            }
+        };
+        let light_points = self.sample_points(position, point, rng);
+        let share = T::one() / T::from(light_points.len()).unwrap();
+        light_points
+            .into_iter()
+            .map(|light_point| {
+                let offset = light_point - point;
+                let distance = offset.norm();
+                let direction = offset.normalize();
+                let mut scale = share * self.attenuation.factor(distance);
+                if let (Some(spot), Some(axis)) = (self.spot, self.direction) {
+                    scale = scale * spot.factor(-direction * axis.normalize());
+                }
+                LightSample {
+                    direction,
+                    distance,
+                    intensity: self.intensity * scale,
+                }
+            })
+            .collect()
+    }
+
+    /// Points of the light at `position` the shadow rays from `point` are cast towards.
+    fn sample_points<R: Rng>(
+        &self,
+        position: Vec3D<T>,
+        point: Vec3D<T>,
+        rng: &mut R,
+    ) -> Vec<Vec3D<T>> {
+        let mut random = || T::from(rng.gen::<f64>()).unwrap();
+        let samples = self.samples.max(1);
+        match self.shape {
+            LightShape::Point => vec![position],
+            LightShape::Rectangle { u_edge, v_edge } => {
+                let half = T::from(0.5).unwrap();
+                (0..samples)
+                    .map(|_| position + u_edge * (random() - half) + v_edge * (random() - half))
+                    .collect()
+            }
+            LightShape::Disk { normal, radius } => {
+                let frame =
+                    TangentFrame::new(normal.normalize(), Vec3D::default(), Vec3D::default());
+                (0..samples)
+                    .map(|_| {
+                        let distance = radius * random().sqrt();
+                        let angle = T::from(2.0 * PI).unwrap() * random();
+                        position
+                            + frame.tangent * (distance * angle.cos())
+                            + frame.bitangent * (distance * angle.sin())
+                    })
+                    .collect()
+            }
+            LightShape::Sphere { radius } => (0..samples)
+                .map(|_| {
+                    // Uniformly over the half of the sphere facing the point.
+                    let height = random() * T::from(2.0).unwrap() - T::one();
+                    let angle = T::from(2.0 * PI).unwrap() * random();
+                    let ring = (T::one() - height * height).max(T::zero()).sqrt();
+                    let direction = Vec3D {
+                        x: ring * angle.cos(),
+                        y: height,
+                        z: ring * angle.sin(),
+                    };
+                    let direction = if direction * (point - position) < T::zero() {
+                        -direction
+                    } else {
+                        direction
+                    };
+                    position + direction * radius
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for Shadows {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "opaque" => Ok(Shadows::Opaque),
+            "transparent" => Ok(Shadows::Transparent),
+            "colored" => Ok(Shadows::Colored),
+            _ => Err(format!(
+                "unknown shadows `{}`, expected `opaque`, `transparent` or `colored`",
+                name
+            )),
+        }
+    }
+}
+
+impl<T: Real> Attenuation<T> {
+    /// Fraction of the light left at the distance.
+    pub fn factor(&self, distance: T) -> T {
+        match *self {
+            Attenuation::None => T::one(),
+            Attenuation::InverseSquare => T::one() / (distance * distance),
+            Attenuation::Custom {
+                constant,
+                linear,
+                quadratic,
+            } => T::one() / (constant + linear * distance + quadratic * distance * distance),
+        }
+    }
+}
+
+impl<T: Real> Spot<T> {
+    /// Fraction of the light shone at the given cosine of the angle to the axis.
+    pub fn factor(&self, cosine: T) -> T {
+        let angle = self.angle.to_radians();
+        let outer = angle.cos();
+        let inner = (angle * (T::one() - self.falloff)).cos();
+        if cosine <= outer {
+            T::zero()
+        } else if cosine >= inner {
+            T::one()
+        } else {
+            let t = (cosine - outer) / (inner - outer);
+            t * t * (T::from(3.0).unwrap() - t - t)
+        }
+    }
+}
+
+/// Reads a light intensity given as a color or as a single number for white light.
+fn light_intensity<'de, D, T>(deserializer: D) -> Result<Color<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Real + Deserialize<'de>,
+{
+    struct IntensityVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Real + Deserialize<'de>> Visitor<'de> for IntensityVisitor<T> {
+        type Value = Color<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a number or a color")
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+            let value = T::from(value).ok_or_else(|| E::custom("intensity out of range"))?;
+            Ok(Color::from([value, value, value]))
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+            self.visit_f64(value as f64)
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+            self.visit_f64(value as f64)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+            Color::deserialize(SeqAccessDeserializer::new(seq))
+        }
+    }
+
+    deserializer.deserialize_any(IntensityVisitor(PhantomData))
+}
+
+/// Random generator of the light samples of a lit point, depending only on the seed
+/// of the scene and the point so that images do not depend on the render order.
+pub(crate) fn shading_rng<T: Real>(seed: u64, point: Vec3D<T>) -> Pcg32 {
+    let bits = |value: T| value.to_f64().unwrap_or(0.0).to_bits();
+    Pcg32::seed_from_u64(
+        seed.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+            ^ bits(point.x)
+            ^ bits(point.y).rotate_left(21)
+            ^ bits(point.z).rotate_left(42),
+    )
 }
 
 pub fn phong_lightning<T: Real>(
@@ -20,31 +342,32 @@ pub fn phong_lightning<T: Real>(
         material,
     } = intersect_result;
 
-    let mut diffuse_light = T::zero();
-    let mut specular_light = T::zero();
+    let mut rng = shading_rng(scene.seed, point);
+    let mut diffuse_light = Color::zero();
+    let mut specular_light = Color::zero();
     for light in &scene.lights {
-        let light_direction = (light.position - point).normalize();
-        let light_distance = (light.position - point).norm();
-
-        if let Some(IntersectResult {
-            point: shadow_point,
-            ..
-        }) = scene.intersect(Ray {
-            origin: point,
-            direction: light_direction,
-        }) {
-            if (shadow_point - point).norm() < light_distance {
+        for sample in light.samples_at(point, &mut rng) {
+            if sample.intensity == Color::zero() {
+                continue;
+            }
+            let shadow_ray = Ray {
+                origin: point,
+                direction: sample.direction,
+                time: ray.time,
+            };
+            let intensity = sample.intensity * scene.transmittance(shadow_ray, sample.distance);
+            if intensity == Color::zero() {
                 continue;
             }
-        }
 
-        diffuse_light = diffuse_light + light.intensity * (light_direction * normal).max(T::zero());
+            diffuse_light = diffuse_light + intensity * (sample.direction * normal).max(T::zero());
 
-        specular_light = specular_light
-            + (reflect(light_direction, normal) * ray.direction)
-                .max(T::zero())
-                .powf(material.shininess)
-                * light.intensity;
+            specular_light = specular_light
+                + intensity
+                    * (reflect(sample.direction, normal) * ray.direction)
+                        .max(T::zero())
+                        .powf(material.shininess);
+        }
     }
     material.ambient + material.diffuse * diffuse_light + material.specular * specular_light
 }
@@ -54,25 +377,39 @@ pub fn reflected_lightning<T: Real>(
     ray: Ray<T>,
     intersect_result: IntersectResult<T>,
     depth: i32,
-) -> Color<T> {
+    media: &Media<T>,
+) -> Traced<T> {
     let IntersectResult {
         point,
         normal,
         material,
     } = intersect_result;
-    if material.reflectiveness == T::zero() {
-        return Color::zero();
+    let mut reflectiveness = material.reflectiveness;
+    if material.transparency != T::zero() {
+        let (reflectance, _) = dielectric(ray.direction, normal, &material, media);
+        reflectiveness = reflectiveness + material.transparency * reflectance;
+    }
+    if reflectiveness == T::zero() {
+        return Traced {
+            color: Color::zero(),
+            depth: 0,
+        };
     }
 
     let reflect_direction = reflect(ray.direction, normal).normalize();
-    let reflected_color = scene.cast_ray(
+    let reflected = scene.cast_ray_through(
         Ray {
             origin: point,
             direction: reflect_direction,
+            time: ray.time,
         },
         depth + 1,
+        media,
     );
-    reflected_color * material.reflectiveness
+    Traced {
+        color: reflected.color * reflectiveness,
+        ..reflected
+    }
 }
 
 pub fn refracted_lightning<T: Real>(
@@ -80,37 +417,83 @@ pub fn refracted_lightning<T: Real>(
     ray: Ray<T>,
     intersect_result: IntersectResult<T>,
     depth: i32,
-) -> Color<T> {
+    media: &Media<T>,
+) -> Traced<T> {
     let IntersectResult {
         point,
         normal,
         material,
     } = intersect_result;
+    let nothing = Traced {
+        color: Color::zero(),
+        depth: 0,
+    };
     if material.transparency == T::zero() {
-        return Color::zero();
+        return nothing;
     }
 
-    if let Some(refract_direction) =
-        refract(ray.direction, normal, material.refractive_index, T::one())
-    {
-        let refracted_color = scene.cast_ray(
-            Ray {
-                origin: point,
-                direction: refract_direction.normalize(),
-            },
-            depth + 1,
-        );
-        refracted_color * material.transparency
-    } else {
-        Color::zero()
+    match dielectric(ray.direction, normal, &material, media) {
+        (reflectance, Some(refraction)) => {
+            let refracted = scene.cast_ray_through(
+                Ray {
+                    origin: point,
+                    direction: refraction.direction.normalize(),
+                    time: ray.time,
+                },
+                depth + 1,
+                &refraction.media,
+            );
+            Traced {
+                color: refracted.color * (material.transparency * (T::one() - reflectance)),
+                ..refracted
+            }
+        }
+        (_, None) => nothing,
     }
 }
 
-fn reflect<T: Real>(direction: Vec3D<T>, normal: Vec3D<T>) -> Vec3D<T> {
+/// Light passing through a transparent surface.
+pub(crate) struct Refraction<T: Real> {
+    pub direction: Vec3D<T>,
+    /// Media the refracted light travels through.
+    pub media: Media<T>,
+}
+
+/// Splits the light hitting a transparent surface: the fraction reflected, and the
+/// refracted direction with the media on the other side of the surface unless the
+/// light is totally reflected. Rays against the normal enter the object.
+pub(crate) fn dielectric<T: Real>(
+    direction: Vec3D<T>,
+    normal: Vec3D<T>,
+    material: &Material<T>,
+    media: &Media<T>,
+) -> (T, Option<Refraction<T>>) {
+    let medium = Medium::of(material);
+    let cosine = direction * normal;
+    let (from_index, facing_normal, next_media) = if cosine < T::zero() {
+        (
+            media.current().refractive_index,
+            normal,
+            media.enter(medium),
+        )
+    } else {
+        (material.refractive_index, -normal, media.leave(medium))
+    };
+    let to_index = next_media.current().refractive_index;
+    let reflectance = material.fresnel.reflectance(cosine, from_index, to_index);
+    let refraction =
+        refract(direction, facing_normal, to_index, from_index).map(|direction| Refraction {
+            direction,
+            media: next_media,
+        });
+    (reflectance, refraction)
+}
+
+pub(crate) fn reflect<T: Real>(direction: Vec3D<T>, normal: Vec3D<T>) -> Vec3D<T> {
     direction - (normal + normal) * (direction * normal)
 }
 
-fn refract<T: Real>(
+pub(crate) fn refract<T: Real>(
     direction: Vec3D<T>,
     normal: Vec3D<T>,
     inner_refractive_index: T,
@@ -134,3 +517,276 @@ fn refract<T: Real>(
         Some(direction * eta + normal * (eta * direction_projection - k.sqrt()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::shape::{Plane, Shape, Sphere};
+    use crate::render::hittable::Hittable;
+    use crate::render::material::{Fresnel, Material};
+
+    fn area_light(shape: LightShape<f64>) -> Light<f64> {
+        Light {
+            shape,
+            ..Light::point(Vec3D::from([0.0, 4.0, 0.0]), Color::unit())
+        }
+    }
+
+    #[test]
+    fn test_light_samples() {
+        let point = Vec3D::from([3.0, 0.0, 0.0]);
+        let points = |light: &Light<f64>, seed| {
+            let samples = light.samples_at(point, &mut shading_rng(seed, point));
+            assert!(samples
+                .iter()
+                .all(|sample| sample.intensity.r == 1.0 / 16.0));
+            samples
+                .iter()
+                .map(|sample| point + sample.direction * sample.distance)
+                .collect::<Vec<_>>()
+        };
+        let rectangle = area_light(LightShape::Rectangle {
+            u_edge: Vec3D::from([2.0, 0.0, 0.0]),
+            v_edge: Vec3D::from([0.0, 0.0, 1.0]),
+        });
+        let samples = points(&rectangle, 1);
+        assert_eq!(samples.len(), 16);
+        assert!(samples
+            .iter()
+            .all(|sample| sample.x.abs() <= 1.0 && sample.y == 4.0 && sample.z.abs() <= 0.5));
+        let again = points(&rectangle, 1);
+        assert!(samples
+            .iter()
+            .zip(&again)
+            .all(|(first, second)| first.x == second.x));
+        let other = points(&rectangle, 2);
+        assert!(samples
+            .iter()
+            .zip(&other)
+            .any(|(first, second)| first.x != second.x));
+
+        let disk = area_light(LightShape::Disk {
+            normal: Vec3D::from([0.0, -2.0, 0.0]),
+            radius: 0.5,
+        });
+        for sample in points(&disk, 0) {
+            assert!((sample.y - 4.0).abs() < 1e-12);
+            assert!((sample - disk.position.unwrap()).norm() <= 0.5);
+        }
+
+        let sphere = area_light(LightShape::Sphere { radius: 0.5 });
+        for sample in points(&sphere, 0) {
+            let offset = sample - sphere.position.unwrap();
+            assert!((offset.norm() - 0.5).abs() < 1e-12);
+            assert!(offset * (point - sphere.position.unwrap()) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_soft_shadows() {
+        let floor = || Hittable::Primitive {
+            shape: Shape::Plane(Plane {
+                origin: Vec3D::default(),
+                normal: Vec3D::from([0.0, 1.0, 0.0]),
+            }),
+            material: Material::default().into(),
+        };
+        let blocker = Hittable::Primitive {
+            shape: Shape::Sphere(Sphere {
+                center: Vec3D::from([0.0, 2.0, 0.0]),
+                radius: 0.5,
+            }),
+            material: Material::default().into(),
+        };
+        let light = area_light(LightShape::Disk {
+            normal: Vec3D::from([0.0, -1.0, 0.0]),
+            radius: 1.0,
+        });
+        let open = Scene::new(Color::zero(), vec![floor()], vec![light], 4);
+        let mut scene = Scene::new(Color::zero(), vec![floor(), blocker], vec![light], 4);
+        let lit = |scene: &Scene<f64>, x: f64| {
+            let point = Vec3D::from([x, 0.0, 0.0]);
+            let ray = Ray {
+                origin: Vec3D::from([x, 1.0, 1.0]),
+                direction: Vec3D::from([0.0, -1.0, -1.0]).normalize(),
+                time: 0.0,
+            };
+            let result = IntersectResult {
+                point,
+                normal: Vec3D::from([0.0, 1.0, 0.0]),
+                material: Material {
+                    diffuse: Color::unit(),
+                    ..Material::default()
+                },
+            };
+            phong_lightning(scene, ray, result).r
+        };
+        // Right below the blocker is the umbra, farther out a penumbra.
+        assert_eq!(lit(&scene, 0.0), 0.0);
+        let penumbra = lit(&scene, 0.8);
+        assert!(penumbra > 0.0 && penumbra < lit(&open, 0.8), "{}", penumbra);
+
+        scene.lights[0].shape = LightShape::Point;
+        assert_eq!(lit(&scene, 0.8), 0.0);
+    }
+
+    #[test]
+    fn test_light_types() {
+        let point = Vec3D::from([3.0, 0.0, 0.0]);
+        let mut rng = shading_rng(0, point);
+        let colored = Color::from([1.0, 0.5, 0.25]);
+
+        let sun = Light::directional(Vec3D::from([0.0, -2.0, 0.0]), colored);
+        let samples = sun.samples_at(point, &mut rng);
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].direction.y, 1.0);
+        assert_eq!(samples[0].distance, f64::MAX);
+        assert!(samples[0].intensity == colored);
+
+        let mut lamp = Light::point(Vec3D::from([3.0, 2.0, 0.0]), colored);
+        lamp.attenuation = Attenuation::InverseSquare;
+        let sample = lamp.samples_at(point, &mut rng)[0];
+        assert_eq!(sample.distance, 2.0);
+        assert_eq!(sample.intensity.g, 0.125);
+        lamp.attenuation = Attenuation::Custom {
+            constant: 1.0,
+            linear: 0.5,
+            quadratic: 0.0,
+        };
+        assert_eq!(lamp.samples_at(point, &mut rng)[0].intensity.r, 0.5);
+
+        let spot = Spot {
+            angle: 60.0,
+            falloff: 0.5,
+        };
+        assert_eq!(spot.factor(1.0), 1.0);
+        assert_eq!(spot.factor(30f64.to_radians().cos() + 1e-9), 1.0);
+        let edge = spot.factor(45f64.to_radians().cos());
+        assert!(edge > 0.0 && edge < 1.0);
+        assert_eq!(spot.factor(0.4), 0.0);
+        let mut lamp = Light::point(Vec3D::from([0.0, 2.0, 0.0]), colored);
+        lamp.direction = Some(Vec3D::from([0.0, -1.0, 0.0]));
+        lamp.spot = Some(Spot {
+            angle: 30.0,
+            falloff: 0.0,
+        });
+        let outside = lamp.samples_at(point, &mut rng)[0];
+        assert!(outside.intensity == Color::zero());
+        let inside = lamp.samples_at(Vec3D::from([0.5, 0.0, 0.0]), &mut rng)[0];
+        assert!(inside.intensity == colored);
+    }
+
+    #[test]
+    fn test_directional_shadows() {
+        let floor = Hittable::Primitive {
+            shape: Shape::Plane(Plane {
+                origin: Vec3D::default(),
+                normal: Vec3D::from([0.0, 1.0, 0.0]),
+            }),
+            material: Material::default().into(),
+        };
+        let blocker = Hittable::Primitive {
+            shape: Shape::Sphere(Sphere {
+                center: Vec3D::from([0.0, 1000.0, 0.0]),
+                radius: 1.0,
+            }),
+            material: Material::default().into(),
+        };
+        let sun = Light::directional(Vec3D::from([0.0, -1.0, 0.0]), Color::from([1.0, 0.5, 0.0]));
+        let scene = Scene::new(Color::zero(), vec![floor, blocker], vec![sun], 4);
+        let lit = |x: f64| {
+            let result = IntersectResult {
+                point: Vec3D::from([x, 0.0, 0.0]),
+                normal: Vec3D::from([0.0, 1.0, 0.0]),
+                material: Material {
+                    diffuse: Color::unit(),
+                    ..Material::default()
+                },
+            };
+            let ray = Ray {
+                origin: Vec3D::from([x, 1.0, 1.0]),
+                direction: Vec3D::from([0.0, -1.0, -1.0]).normalize(),
+                time: 0.0,
+            };
+            phong_lightning(&scene, ray, result)
+        };
+        assert!(lit(0.0) == Color::zero());
+        assert!(lit(2.0) == Color::from([1.0, 0.5, 0.0]));
+    }
+
+    #[test]
+    fn test_dielectrics() {
+        let glass = Material {
+            transparency: 1.0,
+            refractive_index: 1.5,
+            ..Material::default()
+        };
+        let surface = |material: Material<f64>| Hittable::Primitive {
+            shape: Shape::Plane(Plane {
+                origin: Vec3D::default(),
+                normal: Vec3D::from([0.0, 1.0, 0.0]),
+            }),
+            material: material.into(),
+        };
+        let background = Color::from([0.2, 0.4, 0.8]);
+        let inside = Media::new().enter(Medium::of(&glass));
+        let grazing = Ray {
+            origin: Vec3D::from([0.0, -1.0, 0.0]),
+            direction: Vec3D::from([1.0, 0.3, 0.0]).normalize(),
+            time: 0.0,
+        };
+        // Total internal reflection sends all the light back into the glass.
+        let scene = Scene::new(background, vec![surface(glass)], Vec::new(), 1);
+        assert!(scene.cast_ray_through(grazing, 0, &inside).color == background);
+        let constant = Material {
+            fresnel: Fresnel::Constant,
+            ..glass
+        };
+        let scene = Scene::new(background, vec![surface(constant)], Vec::new(), 1);
+        assert!(scene.cast_ray_through(grazing, 0, &inside).color == Color::zero());
+
+        // Leaving glass nested in water bends the light less than leaving it into air.
+        let water = Medium {
+            refractive_index: 1.33,
+            absorption: Color::zero(),
+        };
+        let direction = Vec3D::from([0.5, 1.0, 0.0]).normalize();
+        let normal = Vec3D::from([0.0, 1.0, 0.0]);
+        let sine = |media: &Media<f64>| {
+            let (_, refraction) =
+                dielectric(direction, normal, &glass, &media.enter(Medium::of(&glass)));
+            let refraction = refraction.unwrap();
+            (
+                refraction.direction.normalize().x,
+                refraction.media.current().refractive_index,
+            )
+        };
+        let incident_sine = direction.x;
+        let (air_sine, air_index) = sine(&Media::new());
+        assert!((air_sine - incident_sine * 1.5).abs() < 1e-12);
+        assert_eq!(air_index, 1.0);
+        let (water_sine, water_index) = sine(&Media::new().enter(water));
+        assert!((water_sine - incident_sine * 1.5 / 1.33).abs() < 1e-12);
+        assert_eq!(water_index, 1.33);
+
+        // Light is absorbed along the path inside a tinted medium.
+        let wall = Material {
+            ambient: Color::unit(),
+            ..Material::default()
+        };
+        let scene = Scene::new(background, vec![surface(wall)], Vec::new(), 1);
+        let tinted = Media::new().enter(Medium {
+            refractive_index: 1.0,
+            absorption: Color::from([0.0, 0.5, 1.0]),
+        });
+        let ray = Ray {
+            origin: Vec3D::from([0.0, 2.0, 0.0]),
+            direction: Vec3D::from([0.0, -1.0, 0.0]),
+            time: 0.0,
+        };
+        let color = scene.cast_ray_through(ray, 0, &tinted).color;
+        assert_eq!(color.r, 1.0);
+        assert!((color.g - (-1.0f64).exp()).abs() < 1e-12);
+        assert!((color.b - (-2.0f64).exp()).abs() < 1e-12);
+    }
+}
@@ -1,7 +1,13 @@
 use crate::math::color::Color;
+use crate::math::shape::TangentFrame;
+use crate::math::vec3d::Vec3D;
+use crate::render::texture::Texture;
 use num_traits::real::Real;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Material<T: Real> {
     pub ambient: Color<T>,
     pub diffuse: Color<T>,
@@ -10,6 +16,25 @@ pub struct Material<T: Real> {
     pub reflectiveness: T,
     pub transparency: T,
     pub refractive_index: T,
+    /// Fraction of each color channel absorbed per unit of length traveled inside
+    /// a transparent object.
+    pub absorption: Color<T>,
+    pub fresnel: Fresnel,
+}
+
+/// How the light hitting a transparent surface is split between reflection and
+/// refraction.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fresnel {
+    /// Fresnel equations for unpolarized light.
+    #[default]
+    Exact,
+    /// Schlick's approximation of the Fresnel equations.
+    Schlick,
+    /// Constant transparency, the light refracted under total internal reflection
+    /// is lost.
+    Constant,
 }
 
 impl<T: Real> Default for Material<T> {
@@ -22,6 +47,214 @@ impl<T: Real> Default for Material<T> {
             reflectiveness: T::zero(),
             transparency: T::zero(),
             refractive_index: T::one(),
+            absorption: Color::zero(),
+            fresnel: Fresnel::default(),
+        }
+    }
+}
+
+impl Fresnel {
+    /// Fraction of the light reflected at a surface hit at the given cosine of the
+    /// angle to its normal, going from a medium with refractive index `from_index` into
+    /// one with `to_index`. All light is reflected under total internal reflection.
+    pub fn reflectance<T: Real>(&self, cosine: T, from_index: T, to_index: T) -> T {
+        if *self == Fresnel::Constant {
+            return T::zero();
+        }
+        let cosine = cosine.abs().min(T::one());
+        let ratio = from_index / to_index;
+        let sine_square = ratio * ratio * (T::one() - cosine * cosine);
+        if sine_square >= T::one() {
+            return T::one();
+        }
+        let refracted_cosine = (T::one() - sine_square).sqrt();
+        match self {
+            Fresnel::Exact => {
+                let perpendicular = (from_index * cosine - to_index * refracted_cosine)
+                    / (from_index * cosine + to_index * refracted_cosine);
+                let parallel = (to_index * cosine - from_index * refracted_cosine)
+                    / (to_index * cosine + from_index * refracted_cosine);
+                (perpendicular * perpendicular + parallel * parallel) / T::from(2.0).unwrap()
+            }
+            _ => {
+                let normal = (from_index - to_index) / (from_index + to_index);
+                let normal = normal * normal;
+                let cosine = if from_index > to_index {
+                    refracted_cosine
+                } else {
+                    cosine
+                };
+                normal + (T::one() - normal) * (T::one() - cosine).powi(5)
+            }
+        }
+    }
+}
+
+/// Offset in texture coordinates and in space over which bump maps are differentiated.
+const BUMP_STEP: f64 = 0.001;
+
+/// Material with color channels optionally given by textures instead of the
+/// constant colors of the base material.
+#[derive(Clone)]
+pub struct TexturedMaterial<T: Real> {
+    pub base: Material<T>,
+    pub ambient: Option<Arc<dyn Texture<T>>>,
+    pub diffuse: Option<Arc<dyn Texture<T>>>,
+    pub specular: Option<Arc<dyn Texture<T>>>,
+    pub normal_map: Option<NormalMap<T>>,
+}
+
+/// Perturbation of the shading normal within the tangent frame of the surface.
+#[derive(Clone)]
+pub enum NormalMap<T: Real> {
+    /// Directions in the tangent frame encoded by colors as `2 * color - 1`, with
+    /// the tilt away from the normal scaled by `strength`.
+    Tangent {
+        texture: Arc<dyn Texture<T>>,
+        strength: T,
+    },
+    /// Height field given by the mean of the color channels, the normal leaning
+    /// against its slope scaled by `strength`.
+    Bump {
+        height: Arc<dyn Texture<T>>,
+        strength: T,
+    },
+}
+
+impl<T: Real> TexturedMaterial<T> {
+    /// Material at a point of a surface with the given texture coordinates.
+    pub fn at(&self, uv: (T, T), point: Vec3D<T>) -> Material<T> {
+        let channel = |texture: &Option<Arc<dyn Texture<T>>>, color: Color<T>| match texture {
+            Some(texture) => texture.color(uv, point),
+            None => color,
+        };
+        Material {
+            ambient: channel(&self.ambient, self.base.ambient),
+            diffuse: channel(&self.diffuse, self.base.diffuse),
+            specular: channel(&self.specular, self.base.specular),
+            ..self.base
+        }
+    }
+}
+
+impl<T: Real> From<Material<T>> for TexturedMaterial<T> {
+    fn from(base: Material<T>) -> Self {
+        TexturedMaterial {
+            base,
+            ambient: None,
+            diffuse: None,
+            specular: None,
+            normal_map: None,
         }
     }
 }
+
+impl<T: Real> NormalMap<T> {
+    /// Shading normal at a point of a surface with the given frame and texture coordinates.
+    pub fn normal(&self, frame: TangentFrame<T>, uv: (T, T), point: Vec3D<T>) -> Vec3D<T> {
+        let normal = match self {
+            NormalMap::Tangent { texture, strength } => {
+                let color = texture.color(uv, point);
+                let component = |value: T| value + value - T::one();
+                let local = Vec3D {
+                    x: component(color.r) * *strength,
+                    y: component(color.g) * *strength,
+                    z: component(color.b),
+                };
+                if local.z <= T::zero() {
+                    return frame.normal;
+                }
+                frame.to_world(local)
+            }
+            NormalMap::Bump { height, strength } => {
+                let step = T::from(BUMP_STEP).unwrap();
+                let sample = |uv: (T, T), point: Vec3D<T>| {
+                    let color = height.color(uv, point);
+                    (color.r + color.g + color.b) / T::from(3.0).unwrap()
+                };
+                let (u, v) = uv;
+                let center = sample(uv, point);
+                let u_slope = (sample((u + step, v), point + frame.tangent * step) - center) / step;
+                let v_slope =
+                    (sample((u, v + step), point + frame.bitangent * step) - center) / step;
+                frame.normal - (frame.tangent * u_slope + frame.bitangent * v_slope) * *strength
+            }
+        };
+        normal.normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::texture::Solid;
+
+    /// Height growing along the X axis, whatever the texture coordinates.
+    struct Ramp;
+
+    impl Texture<f64> for Ramp {
+        fn color(&self, _uv: (f64, f64), point: Vec3D<f64>) -> Color<f64> {
+            Color::from([point.x, point.x, point.x])
+        }
+    }
+
+    fn frame() -> TangentFrame<f64> {
+        TangentFrame {
+            tangent: Vec3D::from([1.0, 0.0, 0.0]),
+            bitangent: Vec3D::from([0.0, 0.0, -1.0]),
+            normal: Vec3D::from([0.0, 1.0, 0.0]),
+        }
+    }
+
+    fn assert_close(first: Vec3D<f64>, second: [f64; 3]) {
+        assert!((first - Vec3D::from(second)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn test_fresnel_reflectance() {
+        for fresnel in &[Fresnel::Exact, Fresnel::Schlick] {
+            assert!((fresnel.reflectance(1.0, 1.0, 1.5) - 0.04).abs() < 1e-12);
+            assert!((fresnel.reflectance(1.0, 1.5, 1.0) - 0.04).abs() < 1e-12);
+            assert!(fresnel.reflectance(0.5, 1.0, 1.5) < fresnel.reflectance(0.1, 1.0, 1.5));
+            assert!((fresnel.reflectance(1e-9, 1.0, 1.5) - 1.0).abs() < 1e-6);
+            // Beyond the critical angle of about 42 degrees out of glass.
+            assert_eq!(fresnel.reflectance(0.5, 1.5, 1.0), 1.0);
+        }
+        let exact = Fresnel::Exact.reflectance(0.7, 1.0, 1.5);
+        let schlick = Fresnel::Schlick.reflectance(0.7, 1.0, 1.5);
+        assert!((exact - schlick).abs() < 0.01);
+        assert_eq!(Fresnel::Constant.reflectance(0.5, 1.5, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_normal_maps() {
+        let origin = Vec3D::default();
+        let flat = NormalMap::Tangent {
+            texture: Arc::new(Solid {
+                color: Color::from([0.5, 0.5, 1.0]),
+            }),
+            strength: 1.0,
+        };
+        assert_close(flat.normal(frame(), (0.0, 0.0), origin), [0.0, 1.0, 0.0]);
+
+        let tilted = NormalMap::Tangent {
+            texture: Arc::new(Solid {
+                color: Color::from([1.0, 0.5, 1.0]),
+            }),
+            strength: 1.0,
+        };
+        let half = 0.5f64.sqrt();
+        assert_close(
+            tilted.normal(frame(), (0.0, 0.0), origin),
+            [half, half, 0.0],
+        );
+
+        let bump = NormalMap::Bump {
+            height: Arc::new(Ramp),
+            strength: 1.0,
+        };
+        assert_close(bump.normal(frame(), (0.0, 0.0), origin), [-half, half, 0.0]);
+        let material = TexturedMaterial::from(Material::<f64>::default());
+        assert!(material.normal_map.is_none());
+    }
+}
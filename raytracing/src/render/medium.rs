@@ -0,0 +1,122 @@
+use crate::math::color::Color;
+use crate::render::material::Material;
+use crate::render::volume::extinguish;
+use num_traits::real::Real;
+
+/// Transparent medium light travels through, absorbed along the way by the
+/// Beer–Lambert law.
+#[derive(Copy, Clone, PartialEq)]
+pub struct Medium<T: Real> {
+    pub refractive_index: T,
+    /// Fraction of each color channel absorbed per unit of length.
+    pub absorption: Color<T>,
+}
+
+impl<T: Real> Medium<T> {
+    /// Empty space around all objects.
+    pub fn vacuum() -> Medium<T> {
+        Medium {
+            refractive_index: T::one(),
+            absorption: Color::zero(),
+        }
+    }
+
+    /// Medium inside objects of the material.
+    pub fn of(material: &Material<T>) -> Medium<T> {
+        Medium {
+            refractive_index: material.refractive_index,
+            absorption: material.absorption,
+        }
+    }
+
+    /// Fraction of the light left after traveling the distance through the medium.
+    pub fn transmittance(&self, distance: T) -> Color<T> {
+        extinguish(self.absorption, distance)
+    }
+}
+
+/// Media a ray is inside of, innermost last, so that the refractive index on either
+/// side of a surface is known for nested transparent objects.
+#[derive(Clone)]
+pub struct Media<T: Real> {
+    stack: Vec<Medium<T>>,
+}
+
+impl<T: Real> Media<T> {
+    /// Media of a ray starting outside of all objects.
+    pub fn new() -> Media<T> {
+        Media { stack: Vec::new() }
+    }
+
+    /// Whether the ray is outside of all objects.
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Medium the ray is traveling through.
+    pub fn current(&self) -> Medium<T> {
+        self.stack.last().copied().unwrap_or_else(Medium::vacuum)
+    }
+
+    /// Media after the ray enters an object of the medium.
+    pub fn enter(&self, medium: Medium<T>) -> Media<T> {
+        let mut media = self.clone();
+        media.stack.push(medium);
+        media
+    }
+
+    /// Media after the ray leaves an object of the medium, which need not be the
+    /// innermost one when objects overlap.
+    pub fn leave(&self, medium: Medium<T>) -> Media<T> {
+        let mut media = self.clone();
+        if let Some(index) = media.stack.iter().rposition(|inner| *inner == medium) {
+            media.stack.remove(index);
+        }
+        media
+    }
+}
+
+impl<T: Real> Default for Media<T> {
+    fn default() -> Self {
+        Media::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn medium(refractive_index: f64, absorption: f64) -> Medium<f64> {
+        Medium {
+            refractive_index,
+            absorption: Color::from([absorption, 0.0, absorption]),
+        }
+    }
+
+    #[test]
+    fn test_transmittance() {
+        let tinted = medium(1.5, 0.5);
+        let color = tinted.transmittance(2.0);
+        assert!((color.r - (-1.0f64).exp()).abs() < 1e-12);
+        assert_eq!(color.g, 1.0);
+        assert!(tinted.transmittance(0.0) == Color::unit());
+        assert!(Medium::vacuum().transmittance(f64::MAX) == Color::unit());
+        assert_eq!(tinted.transmittance(f64::MAX).b, 0.0);
+    }
+
+    #[test]
+    fn test_nested_media() {
+        let (water, glass) = (medium(1.33, 0.1), medium(1.5, 0.0));
+        let outside = Media::new();
+        assert_eq!(outside.current().refractive_index, 1.0);
+        let in_water = outside.enter(water);
+        let in_glass = in_water.enter(glass);
+        assert_eq!(in_glass.current().refractive_index, 1.5);
+        assert!(in_glass.leave(glass).current() == water);
+        // Leaving the water first while still inside the overlapping glass.
+        let in_glass_only = in_glass.leave(water);
+        assert!(in_glass_only.current() == glass);
+        assert!(in_glass_only.leave(glass).current() == Medium::vacuum());
+        assert!(outside.leave(glass).current() == Medium::vacuum());
+    }
+}
@@ -0,0 +1,60 @@
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+use crate::math::shape::{Intersect, TangentFrame, Triangle};
+use crate::math::vec3d::Vec3D;
+use crate::render::bvh::Bvh;
+use num_traits::real::Real;
+
+/// Triangle mesh with its own bounding volume hierarchy.
+pub struct Mesh<T: Real> {
+    triangles: Vec<Triangle<T>>,
+    bvh: Bvh<T>,
+    bounding_box: Option<Aabb<T>>,
+}
+
+impl<T: Real> Mesh<T> {
+    pub fn new(triangles: Vec<Triangle<T>>) -> Mesh<T> {
+        let boxes: Vec<_> = triangles
+            .iter()
+            .enumerate()
+            .filter_map(|(index, triangle)| Some((index, triangle.bounding_box()?)))
+            .collect();
+        let bounding_box = boxes.iter().map(|(_, bounds)| *bounds).reduce(Aabb::union);
+        Mesh {
+            triangles,
+            bvh: Bvh::new(boxes),
+            bounding_box,
+        }
+    }
+
+    pub fn triangles(&self) -> &[Triangle<T>] {
+        &self.triangles
+    }
+
+    /// Distance to the nearest triangle hit by the ray and the index of that triangle.
+    pub fn intersect(&self, ray: Ray<T>) -> Option<(T, usize)> {
+        self.bvh
+            .nearest(ray, |index| {
+                let distance = self.triangles[index].intersect(ray)?;
+                Some((distance, distance))
+            })
+            .map(|(index, distance)| (distance, index))
+    }
+
+    pub fn normal(&self, point: Vec3D<T>, triangle: usize) -> Vec3D<T> {
+        self.triangles[triangle].normal(point)
+    }
+
+    pub fn uv(&self, point: Vec3D<T>, triangle: usize) -> (T, T) {
+        self.triangles[triangle].uv(point)
+    }
+
+    pub fn tangent_frame(&self, point: Vec3D<T>, triangle: usize) -> TangentFrame<T> {
+        self.triangles[triangle].tangent_frame(point)
+    }
+
+    /// Box containing the whole mesh, `None` for an empty mesh.
+    pub fn bounding_box(&self) -> Option<Aabb<T>> {
+        self.bounding_box
+    }
+}
@@ -1,5 +1,22 @@
+pub mod animation;
+pub mod aov;
+pub mod bvh;
+pub mod camera;
+pub mod csg;
+pub mod description;
+pub mod environment;
 pub mod frame;
 pub mod hittable;
+pub mod instance;
+pub mod integrator;
 pub mod lightning;
 pub mod material;
+pub mod medium;
+pub mod mesh;
+pub mod motion;
+pub mod obj;
+pub mod sampling;
 pub mod scene;
+pub mod texture;
+pub mod tone_mapping;
+pub mod volume;
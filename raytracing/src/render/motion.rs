@@ -0,0 +1,104 @@
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+use crate::math::vec3d::Vec3D;
+use crate::render::hittable::{Hit, Hittable, Span};
+use crate::render::scene::IntersectResult;
+use num_traits::real::Real;
+use std::sync::Arc;
+
+/// Object moving in a straight line during the frame, translated by `start` at time `0`
+/// and by `end` at time `1`.
+pub struct Motion<T: Real> {
+    object: Arc<Hittable<T>>,
+    start: Vec3D<T>,
+    end: Vec3D<T>,
+}
+
+impl<T: Real> Motion<T> {
+    pub fn new(object: Arc<Hittable<T>>, start: Vec3D<T>, end: Vec3D<T>) -> Motion<T> {
+        Motion { object, start, end }
+    }
+
+    pub fn object(&self) -> &Hittable<T> {
+        &self.object
+    }
+
+    /// Translation of the object at the time.
+    pub fn offset(&self, time: T) -> Vec3D<T> {
+        self.start + (self.end - self.start) * time
+    }
+
+    /// Ray relative to the object at the time of the ray, distances along both are equal.
+    fn object_ray(&self, ray: Ray<T>) -> Ray<T> {
+        Ray {
+            origin: ray.origin - self.offset(ray.time),
+            ..ray
+        }
+    }
+
+    pub fn intersect(&self, ray: Ray<T>) -> Option<Hit<T>> {
+        self.object.intersect(self.object_ray(ray))
+    }
+
+    pub fn spans(&self, ray: Ray<T>) -> Vec<Span<T>> {
+        self.object.spans(self.object_ray(ray))
+    }
+
+    pub fn intersection_result(&self, ray: Ray<T>, hit: Hit<T>) -> IntersectResult<T> {
+        let result = self.object.intersection_result(self.object_ray(ray), hit);
+        IntersectResult {
+            point: ray.point_at(hit.distance),
+            ..result
+        }
+    }
+
+    /// Box around the object at both ends of its motion, which holds it in between.
+    pub fn bounding_box(&self) -> Option<Aabb<T>> {
+        let bounds = self.object.bounding_box()?;
+        let moved = |offset: Vec3D<T>| Aabb {
+            min: bounds.min + offset,
+            max: bounds.max + offset,
+        };
+        Some(moved(self.start).union(moved(self.end)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::shape::{Shape, Sphere};
+    use crate::render::material::Material;
+
+    #[test]
+    fn test_moving_sphere() {
+        let sphere = Hittable::Primitive {
+            shape: Shape::Sphere(Sphere {
+                center: Vec3D::from([0.0, 0.0, 0.0]),
+                radius: 1.0,
+            }),
+            material: Material::default().into(),
+        };
+        let motion = Motion::new(
+            Arc::new(sphere),
+            Vec3D::from([0.0, 0.0, 0.0]),
+            Vec3D::from([4.0, 0.0, 0.0]),
+        );
+        let ray = |time| Ray {
+            origin: Vec3D::from([4.0, 0.0, -5.0]),
+            direction: Vec3D::from([0.0, 0.0, 1.0]),
+            time,
+        };
+        assert!(motion.intersect(ray(0.0)).is_none());
+        assert!(motion.intersect(ray(0.5)).is_none());
+        let hit = motion.intersect(ray(1.0)).unwrap();
+        assert_eq!(hit.distance, 4.0);
+        let result = motion.intersection_result(ray(1.0), hit);
+        assert_eq!(result.point.z, -1.0);
+        assert_eq!(result.normal.z, -1.0);
+        assert_eq!(motion.spans(ray(1.0)).len(), 1);
+
+        let bounds = motion.bounding_box().unwrap();
+        assert_eq!((bounds.min.x, bounds.max.x), (-1.0, 5.0));
+        assert_eq!((bounds.min.y, bounds.max.y), (-1.0, 1.0));
+    }
+}
@@ -0,0 +1,212 @@
+//! Reader of Wavefront OBJ files supporting vertices, texture coordinates, vertex normals
+//! and polygonal faces.
+
+use crate::math::shape::Triangle;
+use crate::math::vec3d::Vec3D;
+use num_traits::real::Real;
+use std::error::Error;
+use std::path::Path;
+use std::{fmt, fs, io};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+/// Reads the faces of an OBJ file, polygons are split into triangle fans.
+pub fn load<T: Real, Q: AsRef<Path>>(path: Q) -> Result<Vec<Triangle<T>>, ObjError> {
+    let source = fs::read_to_string(path).map_err(ObjError::Io)?;
+    parse(&source)
+}
+
+pub fn parse<T: Real>(source: &str) -> Result<Vec<Triangle<T>>, ObjError> {
+    let mut vertices = Vec::new();
+    let mut uvs = Vec::new();
+    let mut normals = Vec::new();
+    let mut triangles = Vec::new();
+
+    for (line_index, line) in source.lines().enumerate() {
+        let error = |message: String| ObjError::Parse {
+            line: line_index + 1,
+            message,
+        };
+        let line = line.split('#').next().unwrap();
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => vertices.push(parse_vector(tokens).map_err(error)?),
+            Some("vt") => uvs.push(parse_uv(tokens).map_err(error)?),
+            Some("vn") => normals.push(parse_vector(tokens).map_err(error)?),
+            Some("f") => {
+                let corners = tokens
+                    .map(|token| parse_corner(token, vertices.len(), uvs.len(), normals.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+                if corners.len() < 3 {
+                    return Err(error("face needs at least three vertices".to_owned()));
+                }
+                for index in 1..corners.len() - 1 {
+                    let face = [corners[0], corners[index], corners[index + 1]];
+                    let face_uvs = match face {
+                        [(_, Some(a), _), (_, Some(b), _), (_, Some(c), _)] => {
+                            Some([uvs[a], uvs[b], uvs[c]])
+                        }
+                        _ => None,
+                    };
+                    let face_normals = match face {
+                        [(_, _, Some(a)), (_, _, Some(b)), (_, _, Some(c))] => {
+                            Some([normals[a], normals[b], normals[c]])
+                        }
+                        _ => None,
+                    };
+                    triangles.push(Triangle {
+                        vertices: [
+                            vertices[face[0].0],
+                            vertices[face[1].0],
+                            vertices[face[2].0],
+                        ],
+                        normals: face_normals,
+                        uvs: face_uvs,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(triangles)
+}
+
+/// Texture coordinates `u` and `v`, an optional third coordinate is ignored.
+fn parse_uv<'a, T, I>(mut tokens: I) -> Result<(T, T), String>
+where
+    T: Real,
+    I: Iterator<Item = &'a str>,
+{
+    let mut coordinate = || {
+        let token = tokens.next().ok_or("expected two texture coordinates")?;
+        token
+            .parse::<f64>()
+            .ok()
+            .and_then(T::from)
+            .ok_or_else(|| format!("invalid texture coordinate `{}`", token))
+    };
+    Ok((coordinate()?, coordinate()?))
+}
+
+fn parse_vector<'a, T, I>(mut tokens: I) -> Result<Vec3D<T>, String>
+where
+    T: Real,
+    I: Iterator<Item = &'a str>,
+{
+    let mut coordinate = || {
+        let token = tokens.next().ok_or("expected three coordinates")?;
+        token
+            .parse::<f64>()
+            .ok()
+            .and_then(T::from)
+            .ok_or_else(|| format!("invalid coordinate `{}`", token))
+    };
+    Ok(Vec3D {
+        x: coordinate()?,
+        y: coordinate()?,
+        z: coordinate()?,
+    })
+}
+
+/// Parses a face corner `v`, `v/vt`, `v//vn` or `v/vt/vn` into zero based vertex,
+/// texture coordinate and normal indices.
+fn parse_corner(
+    token: &str,
+    vertex_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+) -> Result<(usize, Option<usize>, Option<usize>), String> {
+    let mut indices = token.split('/');
+    let vertex = resolve_index(indices.next().unwrap_or(""), vertex_count, "vertex")?;
+    let mut optional_index = |count, kind| match indices.next() {
+        Some(index) if !index.is_empty() => resolve_index(index, count, kind).map(Some),
+        _ => Ok(None),
+    };
+    let uv = optional_index(uv_count, "texture coordinate")?;
+    let normal = optional_index(normal_count, "normal")?;
+    Ok((vertex, uv, normal))
+}
+
+/// OBJ indices start from one, negative ones count back from the last element.
+fn resolve_index(token: &str, count: usize, kind: &str) -> Result<usize, String> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| format!("invalid {} index `{}`", kind, token))?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        Err(format!("{} index {} is out of range", kind, index))
+    } else {
+        Ok(resolved as usize)
+    }
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(error) => write!(f, "{}", error),
+            ObjError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for ObjError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ObjError::Io(error) => Some(error),
+            ObjError::Parse { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_faces() {
+        let triangles: Vec<Triangle<f64>> = parse(
+            "# unit square and a triangle
+            v 0 0 0
+            v 1 0 0
+            v 1 1 0
+            v 0 1 0
+            vt 0 0
+            vt 1 1
+            vn 0 0 1
+            o square
+            f 1/1 2/1 3/2 4/1
+            f -4//1 -3//1 -1//1",
+        )
+        .unwrap();
+        assert_eq!(triangles.len(), 3);
+        assert_eq!(triangles[1].vertices[1].x, 1.0);
+        assert_eq!(triangles[1].vertices[2].y, 1.0);
+        assert!(triangles[0].normals.is_none());
+        assert_eq!(triangles[0].uvs.unwrap()[2], (1.0, 1.0));
+        assert!(triangles[2].uvs.is_none());
+        assert_eq!(triangles[2].normals.unwrap()[2].z, 1.0);
+        assert_eq!(triangles[2].vertices[2].y, 1.0);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = parse::<f64>("v 0 0 0\nv 1 0 0\nf 1 2 3\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 3: vertex index 3 is out of range");
+        let error = parse::<f64>("v 0 zero 0\n").err().unwrap();
+        assert_eq!(error.to_string(), "line 1: invalid coordinate `zero`");
+        let error = parse::<f64>("v 0 0 0\nf 1 1\n").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "line 2: face needs at least three vertices"
+        );
+    }
+}
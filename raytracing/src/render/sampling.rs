@@ -0,0 +1,214 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// How the samples of a pixel are placed inside the `samples` x `samples` grid cells.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    /// Samples in the cell centers, a single sample hits the pixel center.
+    Uniform,
+    /// One sample jittered randomly inside every cell.
+    Stratified,
+    /// Samples anywhere in the filter support, ignoring the grid.
+    Random,
+}
+
+/// Reconstruction filter weighting the samples of a pixel by their offset from its center.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+}
+
+#[derive(Copy, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sampling {
+    /// Every pixel is sampled by a `samples` x `samples` grid.
+    pub samples: usize,
+    pub pattern: Pattern,
+    pub filter: Filter,
+    /// Pixels differing from a neighbour by more than this in any channel are rendered
+    /// again with `adaptive_samples` x `adaptive_samples` samples.
+    pub adaptive_threshold: Option<f64>,
+    pub adaptive_samples: usize,
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Sampling {
+            samples: 1,
+            pattern: Pattern::Uniform,
+            filter: Filter::Box,
+            adaptive_threshold: None,
+            adaptive_samples: 4,
+        }
+    }
+}
+
+impl Sampling {
+    /// Offsets of `samples` x `samples` samples from the pixel center, covering the filter support.
+    pub fn offsets<R: Rng>(&self, samples: usize, rng: &mut R) -> Vec<(f64, f64)> {
+        let radius = self.filter.radius();
+        let cell = 2.0 * radius / samples as f64;
+        let mut offset = |index: usize| match self.pattern {
+            Pattern::Uniform => -radius + (index as f64 + 0.5) * cell,
+            Pattern::Stratified => -radius + (index as f64 + rng.gen::<f64>()) * cell,
+            Pattern::Random => -radius + rng.gen::<f64>() * 2.0 * radius,
+        };
+        let mut offsets = Vec::with_capacity(samples * samples);
+        for y_index in 0..samples {
+            for x_index in 0..samples {
+                let x = offset(x_index);
+                let y = offset(y_index);
+                offsets.push((x, y));
+            }
+        }
+        offsets
+    }
+}
+
+impl Filter {
+    /// Half of the filter support in pixels.
+    pub fn radius(self) -> f64 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian => 1.5,
+            Filter::Mitchell => 2.0,
+        }
+    }
+
+    /// Weight of a sample `offset` pixels away from the pixel center along one axis.
+    pub fn weight(self, offset: f64) -> f64 {
+        let x = offset.abs();
+        if x > self.radius() {
+            return 0.0;
+        }
+        match self {
+            Filter::Box => 1.0,
+            Filter::Tent => 1.0 - x,
+            Filter::Gaussian => {
+                let alpha = 2.0;
+                (-alpha * x * x).exp() - (-alpha * self.radius() * self.radius()).exp()
+            }
+            Filter::Mitchell => {
+                let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+                if x < 1.0 {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x.powi(3)
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x.powi(2)
+                        + (6.0 - 2.0 * b))
+                        / 6.0
+                } else {
+                    ((-b - 6.0 * c) * x.powi(3)
+                        + (6.0 * b + 30.0 * c) * x.powi(2)
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c))
+                        / 6.0
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "uniform" => Ok(Pattern::Uniform),
+            "stratified" => Ok(Pattern::Stratified),
+            "random" => Ok(Pattern::Random),
+            _ => Err(format!(
+                "unknown pattern `{}`, expected `uniform`, `stratified` or `random`",
+                name
+            )),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "box" => Ok(Filter::Box),
+            "tent" => Ok(Filter::Tent),
+            "gaussian" => Ok(Filter::Gaussian),
+            "mitchell" => Ok(Filter::Mitchell),
+            _ => Err(format!(
+                "unknown filter `{}`, expected `box`, `tent`, `gaussian` or `mitchell`",
+                name
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_pcg::Pcg32;
+
+    #[test]
+    fn test_filter_weights() {
+        for filter in &[
+            Filter::Box,
+            Filter::Tent,
+            Filter::Gaussian,
+            Filter::Mitchell,
+        ] {
+            assert!(filter.weight(0.0) > 0.0);
+            assert_eq!(filter.weight(0.3), filter.weight(-0.3));
+            assert_eq!(filter.weight(filter.radius() + 0.1), 0.0);
+        }
+        for filter in &[Filter::Tent, Filter::Gaussian, Filter::Mitchell] {
+            assert!(filter.weight(filter.radius()).abs() < 1e-9);
+        }
+        assert_eq!(Filter::Box.weight(0.5), 1.0);
+        assert_eq!(Filter::Tent.weight(0.25), 0.75);
+        assert!((Filter::Mitchell.weight(0.0) - 8.0 / 9.0).abs() < 1e-12);
+        assert!(Filter::Mitchell.weight(1.5) < 0.0);
+    }
+
+    #[test]
+    fn test_offsets() {
+        let mut rng = Pcg32::seed_from_u64(0);
+        let sampling = Sampling::default();
+        assert_eq!(sampling.offsets(1, &mut rng), vec![(0.0, 0.0)]);
+        assert_eq!(
+            sampling.offsets(2, &mut rng),
+            vec![(-0.25, -0.25), (0.25, -0.25), (-0.25, 0.25), (0.25, 0.25)]
+        );
+
+        let stratified = Sampling {
+            pattern: Pattern::Stratified,
+            filter: Filter::Tent,
+            ..Sampling::default()
+        };
+        let offsets = stratified.offsets(4, &mut rng);
+        assert_eq!(offsets.len(), 16);
+        for (index, (x, y)) in offsets.into_iter().enumerate() {
+            let cell_x = ((x + 1.0) / 0.5).floor() as usize;
+            let cell_y = ((y + 1.0) / 0.5).floor() as usize;
+            assert_eq!(cell_y * 4 + cell_x, index);
+        }
+
+        // Samples of wide filters reach into the negative lobes of Mitchell.
+        let mitchell = Sampling {
+            pattern: Pattern::Random,
+            filter: Filter::Mitchell,
+            ..Sampling::default()
+        };
+        let offsets = mitchell.offsets(8, &mut rng);
+        assert!(offsets
+            .iter()
+            .all(|(x, y)| x.abs() <= 2.0 && y.abs() <= 2.0));
+        assert!(offsets
+            .iter()
+            .any(|(x, y)| Filter::Mitchell.weight(*x) * Filter::Mitchell.weight(*y) < 0.0));
+    }
+}
@@ -1,16 +1,49 @@
 use crate::math::color::Color;
 use crate::math::ray::Ray;
 use crate::math::vec3d::Vec3D;
-use crate::render::hittable::Hittable;
-use crate::render::lightning::{phong_lightning, reflected_lightning, refracted_lightning, Light};
+use crate::render::bvh::Bvh;
+use crate::render::description::{SceneDescription, SceneError};
+use crate::render::environment::Environment;
+use crate::render::hittable::{Hit, Hittable};
+use crate::render::lightning::{
+    phong_lightning, reflected_lightning, refracted_lightning, shading_rng, Light, Shadows,
+};
 use crate::render::material::Material;
+use crate::render::medium::{Media, Medium};
+use crate::render::volume::{extinguish, Fog, Volume};
 use num_traits::real::Real;
+use rand::Rng;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::Path;
+
+/// Most surfaces a shadow ray passes through before the light counts as blocked.
+const MAX_SHADOW_HITS: usize = 64;
 
 pub struct Scene<T: Real> {
-    pub background_color: Color<T>,
-    pub objects: Vec<Hittable<T>>,
+    /// Light seen along the rays missing all objects.
+    pub environment: Environment<T>,
     pub lights: Vec<Light<T>>,
     pub recursion_depth: i32,
+    /// Seed of the random samples of area lights, equal seeds give equal images.
+    pub seed: u64,
+    pub shadows: Shadows,
+    pub fog: Option<Fog<T>>,
+    /// Participating media, which are not objects and are only seen through.
+    pub volumes: Vec<Volume<T>>,
+    objects: Vec<Hittable<T>>,
+    bvh: Bvh<T>,
+    /// Objects without a bounding box, tested against every ray.
+    unbounded_objects: Vec<usize>,
+}
+
+/// Light seen along a ray, with the depth it was traced to.
+#[derive(Copy, Clone)]
+pub struct Traced<T: Real> {
+    pub color: Color<T>,
+    /// Number of nested rays hitting objects in the tree of reflected and refracted
+    /// rays, or along the path, zero when the ray misses everything.
+    pub depth: usize,
 }
 
 #[derive(Copy, Clone)]
@@ -20,33 +53,444 @@ pub struct IntersectResult<T: Real> {
     pub material: Material<T>,
 }
 
+impl<T: Real + Serialize + DeserializeOwned + Send + Sync + 'static> Scene<T> {
+    pub fn load<Q: AsRef<Path>>(path: Q) -> Result<Scene<T>, SceneError> {
+        SceneDescription::load(path)?.scene()
+    }
+}
+
 impl<T: Real> Scene<T> {
+    pub fn new(
+        background_color: Color<T>,
+        objects: Vec<Hittable<T>>,
+        lights: Vec<Light<T>>,
+        recursion_depth: i32,
+    ) -> Scene<T> {
+        let mut bounded_objects = Vec::new();
+        let mut unbounded_objects = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            match object.bounding_box() {
+                Some(bounding_box) => bounded_objects.push((index, bounding_box)),
+                None => unbounded_objects.push(index),
+            }
+        }
+        Scene {
+            environment: Environment::Solid(background_color),
+            lights,
+            recursion_depth,
+            seed: 0,
+            shadows: Shadows::default(),
+            fog: None,
+            volumes: Vec::new(),
+            objects,
+            bvh: Bvh::new(bounded_objects),
+            unbounded_objects,
+        }
+    }
+
+    pub fn objects(&self) -> &[Hittable<T>] {
+        &self.objects
+    }
+
+    /// Color of a solid environment, `None` for other environments.
+    pub fn background_color(&self) -> Option<Color<T>> {
+        match self.environment {
+            Environment::Solid(color) => Some(color),
+            _ => None,
+        }
+    }
+
+    /// Index of the nearest object hit by the ray and the hit itself.
+    fn nearest(&self, ray: Ray<T>) -> Option<(usize, Hit<T>)> {
+        let mut nearest = self.bvh.nearest(ray, |index| {
+            let hit = self.objects[index].intersect(ray)?;
+            Some((hit.distance, hit))
+        });
+        for &index in &self.unbounded_objects {
+            if let Some(hit) = self.objects[index].intersect(ray) {
+                if nearest.is_none_or(|(_, nearest_hit)| hit.distance < nearest_hit.distance) {
+                    nearest = Some((index, hit));
+                }
+            }
+        }
+        nearest
+    }
+
     pub fn intersect(&self, ray: Ray<T>) -> Option<IntersectResult<T>> {
-        let mut min_distance = T::max_value();
-        let mut result: Option<IntersectResult<T>> = None;
-        for object in &self.objects {
-            if let Some(distance) = object.intersect(ray) {
-                if min_distance > distance {
-                    min_distance = distance;
-                    result = Some(object.intersection_result(ray.point_at(distance)));
+        self.intersect_object(ray).map(|(_, result)| result)
+    }
+
+    /// Nearest intersection along the ray with the index of the object hit in `objects`.
+    pub fn intersect_object(&self, ray: Ray<T>) -> Option<(usize, IntersectResult<T>)> {
+        self.nearest(ray)
+            .map(|(index, hit)| (index, self.objects[index].intersection_result(ray, hit)))
+    }
+
+    /// Checks whether anything is hit by the ray closer than `max_distance`.
+    pub fn occluded(&self, ray: Ray<T>, max_distance: T) -> bool {
+        let hits = |index: usize| Some(self.objects[index].intersect(ray)?.distance);
+        self.unbounded_objects
+            .iter()
+            .any(|&index| hits(index).is_some_and(|distance| distance < max_distance))
+            || self.bvh.any(ray, max_distance, hits)
+    }
+
+    /// Fraction of the light passing along the ray up to `max_distance`, through
+    /// the transparent objects on the way unless shadows are opaque, and through
+    /// the volumes.
+    pub fn transmittance(&self, ray: Ray<T>, max_distance: T) -> Color<T> {
+        let surfaces = self.surface_transmittance(ray, max_distance);
+        if surfaces == Color::zero() {
+            return surfaces;
+        }
+        self.volumes.iter().fold(surfaces, |filter, volume| {
+            filter * volume.transmittance(ray, max_distance)
+        })
+    }
+
+    fn surface_transmittance(&self, ray: Ray<T>, max_distance: T) -> Color<T> {
+        let blocked = self.occluded(ray, max_distance);
+        if !blocked {
+            return Color::unit();
+        }
+        if self.shadows == Shadows::Opaque {
+            return Color::zero();
+        }
+        let mut filter = Color::unit();
+        let mut ray = ray;
+        let mut remaining = max_distance;
+        for _ in 0..MAX_SHADOW_HITS {
+            let (index, hit) = match self.nearest(ray) {
+                Some((index, hit)) if hit.distance < remaining => (index, hit),
+                _ => return filter,
+            };
+            let result = self.objects[index].intersection_result(ray, hit);
+            let material = result.material;
+            if material.transparency <= T::zero() {
+                return Color::zero();
+            }
+            filter = filter * material.transparency;
+            if self.shadows == Shadows::Colored {
+                let diffuse = material.diffuse;
+                let brightest = diffuse.r.max(diffuse.g).max(diffuse.b);
+                if brightest > T::zero() {
+                    filter = filter * (diffuse / brightest);
                 }
             }
+            // Leaving an object, the light was absorbed since entering it.
+            if ray.direction * result.normal > T::zero() {
+                filter = filter * Medium::of(&material).transmittance(hit.distance);
+            }
+            remaining = remaining - hit.distance;
+            ray.origin = result.point;
         }
-        result
+        Color::zero()
     }
 
     pub fn cast_ray(&self, ray: Ray<T>, depth: i32) -> Color<T> {
+        self.cast_ray_through(ray, depth, &Media::new()).color
+    }
+
+    /// Color seen along a ray traveling inside the given media, absorbed by the
+    /// current one up to the first hit.
+    pub fn cast_ray_through(&self, ray: Ray<T>, depth: i32, media: &Media<T>) -> Traced<T> {
+        let medium = media.current();
         if depth > self.recursion_depth {
-            return self.background_color;
+            return Traced {
+                color: self.environment.color(ray.direction) * medium.transmittance(T::max_value()),
+                depth: 0,
+            };
         }
 
-        if let Some(result) = self.intersect(ray) {
+        let (color, distance, tree_depth) = if let Some(result) = self.intersect(ray) {
             let phong = phong_lightning(self, ray, result);
-            let reflected = reflected_lightning(self, ray, result, depth);
-            let refracted = refracted_lightning(self, ray, result, depth);
-            phong + reflected + refracted
+            let reflected = reflected_lightning(self, ray, result, depth, media);
+            let refracted = refracted_lightning(self, ray, result, depth, media);
+            (
+                phong + reflected.color + refracted.color,
+                (result.point - ray.origin).norm(),
+                reflected.depth.max(refracted.depth) + 1,
+            )
         } else {
-            self.background_color
+            (self.environment.color(ray.direction), T::max_value(), 0)
+        };
+        let (transmittance, scattered) = self.participating_media(ray, distance, media);
+        Traced {
+            color: color * medium.transmittance(distance) * transmittance + scattered,
+            depth: tree_depth,
         }
     }
+
+    /// Fraction of the light passing along the ray up to `distance` through the volumes
+    /// and the fog, which only fills the space outside of all objects, and the light
+    /// they add on the way.
+    pub fn participating_media(
+        &self,
+        ray: Ray<T>,
+        distance: T,
+        media: &Media<T>,
+    ) -> (Color<T>, Color<T>) {
+        let fog = self.fog.filter(|_| media.is_empty());
+        let fog_transmittance =
+            |distance: T| fog.map_or(T::one(), |fog| fog.transmittance(distance));
+        let mut transmittance = Color::unit();
+        let mut scattered = Color::zero();
+        // Segments of all volumes from the nearest, overlapping ones are taken one
+        // after the other.
+        let mut segments: Vec<(T, T, &Volume<T>)> = self
+            .volumes
+            .iter()
+            .flat_map(|volume| {
+                volume
+                    .segments(ray, distance)
+                    .into_iter()
+                    .map(move |(enter, exit)| (enter, exit, volume))
+            })
+            // Only finite distances, which are totally ordered.
+            .filter(|(enter, exit, _)| {
+                enter.abs() <= T::max_value() && exit.abs() <= T::max_value()
+            })
+            .collect();
+        segments.sort_by(|first, second| first.0.partial_cmp(&second.0).unwrap());
+        for (enter, exit, volume) in segments {
+            let light = self.in_scattering(ray, enter, exit, volume);
+            scattered = scattered + transmittance * light * fog_transmittance(enter);
+            transmittance = transmittance * extinguish(volume.extinction(), exit - enter);
+        }
+        if let Some(fog) = fog {
+            let fog_transmittance = fog.transmittance(distance);
+            scattered = scattered + fog.color * (T::one() - fog_transmittance);
+            transmittance = transmittance * fog_transmittance;
+        }
+        (transmittance, scattered)
+    }
+
+    /// Light of the lights scattered once towards the origin of the ray by the volume
+    /// between the distances, gathered at evenly spread points with a random jitter.
+    fn in_scattering(&self, ray: Ray<T>, enter: T, exit: T, volume: &Volume<T>) -> Color<T> {
+        if volume.scattering == Color::zero() || volume.steps == 0 {
+            return Color::zero();
+        }
+        let extinction = volume.extinction();
+        let step = (exit - enter) / T::from(volume.steps).unwrap();
+        let mut rng = shading_rng(self.seed, ray.point_at(enter));
+        let mut scattered = Color::zero();
+        for index in 0..volume.steps {
+            let jitter = T::from(rng.gen::<f64>()).unwrap();
+            let distance = enter + step * (T::from(index).unwrap() + jitter);
+            let point = ray.point_at(distance);
+            let mut light = Color::zero();
+            for source in &self.lights {
+                for sample in source.samples_at(point, &mut rng) {
+                    if sample.intensity == Color::zero() {
+                        continue;
+                    }
+                    let shadow_ray = Ray {
+                        origin: point,
+                        direction: sample.direction,
+                        time: ray.time,
+                    };
+                    let phase = volume.phase(sample.direction * ray.direction);
+                    light = light
+                        + sample.intensity
+                            * self.transmittance(shadow_ray, sample.distance)
+                            * phase;
+                }
+            }
+            scattered = scattered + light * extinguish(extinction, distance - enter);
+        }
+        scattered * volume.scattering * step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::shape::{Intersect, Plane, Shape, Sphere};
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg32;
+
+    fn random_vector(rng: &mut Pcg32, size: f64) -> Vec3D<f64> {
+        Vec3D {
+            x: rng.gen_range(-size..size),
+            y: rng.gen_range(-size..size),
+            z: rng.gen_range(-size..size),
+        }
+    }
+
+    fn random_scene(rng: &mut Pcg32) -> Scene<f64> {
+        let mut objects: Vec<Hittable<f64>> = (0..500)
+            .map(|_| Hittable::Primitive {
+                shape: Shape::Sphere(Sphere {
+                    center: random_vector(rng, 50.0),
+                    radius: rng.gen_range(0.1..3.0),
+                }),
+                material: Material::default().into(),
+            })
+            .collect();
+        objects.insert(
+            100,
+            Hittable::Primitive {
+                shape: Shape::Plane(Plane {
+                    origin: Vec3D::from([0.0, -40.0, 0.0]),
+                    normal: Vec3D::from([0.0, 1.0, 0.0]),
+                }),
+                material: Material::default().into(),
+            },
+        );
+        Scene::new(Color::zero(), objects, vec![], 4)
+    }
+
+    fn brute_force_nearest(scene: &Scene<f64>, ray: Ray<f64>) -> Option<(usize, Hit<f64>)> {
+        let mut nearest = None;
+        let mut min_distance = f64::MAX;
+        for (index, object) in scene.objects().iter().enumerate() {
+            if let Some(hit) = object.intersect(ray) {
+                if hit.distance < min_distance {
+                    min_distance = hit.distance;
+                    nearest = Some((index, hit));
+                }
+            }
+        }
+        nearest
+    }
+
+    #[test]
+    fn test_bvh_matches_brute_force() {
+        let mut rng = Pcg32::seed_from_u64(42);
+        let scene = random_scene(&mut rng);
+        let mut hits = 0;
+        for _ in 0..5000 {
+            let ray = Ray {
+                origin: random_vector(&mut rng, 60.0),
+                direction: random_vector(&mut rng, 1.0).normalize(),
+                time: 0.0,
+            };
+            let expected = brute_force_nearest(&scene, ray);
+            assert_eq!(scene.nearest(ray), expected);
+            hits += expected.is_some() as usize;
+
+            let max_distance = rng.gen_range(0.0..100.0);
+            let blocked = expected.is_some_and(|(_, hit)| hit.distance < max_distance);
+            assert_eq!(scene.occluded(ray, max_distance), blocked);
+        }
+        assert!(hits > 1000);
+    }
+
+    #[test]
+    fn test_unbounded_objects() {
+        let mut rng = Pcg32::seed_from_u64(7);
+        let scene = random_scene(&mut rng);
+        let ray = Ray {
+            origin: Vec3D::from([100.0, 0.0, 100.0]),
+            direction: Vec3D::from([0.0, -1.0, 0.0]),
+            time: 0.0,
+        };
+        assert_eq!(scene.nearest(ray), Some((100, Hit::new(40.0))));
+        match scene.objects()[100] {
+            Hittable::Primitive {
+                shape: Shape::Plane(ref plane),
+                ..
+            } => assert!(plane.bounding_box().is_none()),
+            _ => panic!("expected a plane"),
+        }
+    }
+
+    #[test]
+    fn test_transparent_shadows() {
+        let sphere = |x: f64, material: Material<f64>| Hittable::Primitive {
+            shape: Shape::Sphere(Sphere {
+                center: Vec3D::from([x, 0.0, 0.0]),
+                radius: 1.0,
+            }),
+            material: material.into(),
+        };
+        let glass = Material {
+            diffuse: Color::from([0.5, 0.25, 0.0]),
+            transparency: 0.5,
+            absorption: Color::from([0.0, 0.5, 0.0]),
+            ..Material::default()
+        };
+        let mut scene = Scene::new(
+            Color::zero(),
+            vec![sphere(0.0, glass), sphere(6.0, Material::default())],
+            Vec::new(),
+            4,
+        );
+        let ray = Ray {
+            origin: Vec3D::from([-3.0, 0.0, 0.0]),
+            direction: Vec3D::from([1.0, 0.0, 0.0]),
+            time: 0.0,
+        };
+        let color = scene.transmittance(ray, 5.0);
+        assert!((color.r - 0.25).abs() < 1e-12);
+        assert!((color.g - 0.25 * (-1.0f64).exp()).abs() < 1e-12);
+        assert!(scene.transmittance(ray, 1.0) == Color::unit());
+        assert!(scene.transmittance(ray, 10.0) == Color::zero());
+
+        scene.shadows = Shadows::Colored;
+        let color = scene.transmittance(ray, 5.0);
+        assert!((color.r - 0.25).abs() < 1e-12);
+        assert!((color.g - 0.0625 * (-1.0f64).exp()).abs() < 1e-12);
+        assert_eq!(color.b, 0.0);
+
+        scene.shadows = Shadows::Opaque;
+        assert!(scene.transmittance(ray, 5.0) == Color::zero());
+    }
+
+    #[test]
+    fn test_participating_media() {
+        let mut scene = Scene::new(
+            Color::zero(),
+            Vec::new(),
+            vec![Light::point(Vec3D::from([0.0, 4.0, -5.0]), Color::unit())],
+            4,
+        );
+        let ray = Ray {
+            origin: Vec3D::from([0.0, 0.0, 0.0]),
+            direction: Vec3D::from([0.0, 0.0, -1.0]),
+            time: 0.0,
+        };
+        let (transmittance, scattered) = scene.participating_media(ray, 10.0, &Media::new());
+        assert!(transmittance == Color::unit() && scattered == Color::zero());
+
+        let fog = Fog {
+            color: Color::from([0.5, 0.5, 0.5]),
+            density: 0.1,
+        };
+        scene.fog = Some(fog);
+        let (transmittance, scattered) = scene.participating_media(ray, 10.0, &Media::new());
+        assert!((transmittance.r - (-1.0f64).exp()).abs() < 1e-12);
+        assert!((scattered.r - 0.5 * (1.0 - (-1.0f64).exp())).abs() < 1e-12);
+        // Nothing is left of what lies behind the fog at infinity.
+        let (transmittance, scattered) = scene.participating_media(ray, f64::MAX, &Media::new());
+        assert!(transmittance == Color::zero() && scattered == fog.color);
+
+        scene.volumes.push(Volume {
+            boundary: Hittable::Primitive {
+                shape: Shape::Sphere(Sphere {
+                    center: Vec3D::from([0.0, 0.0, -5.0]),
+                    radius: 1.0,
+                }),
+                material: Material::default().into(),
+            },
+            absorption: Color::zero(),
+            scattering: Color::from([0.5, 0.5, 0.0]),
+            anisotropy: 0.0,
+            steps: 8,
+        });
+        let (transmittance, scattered) = scene.participating_media(ray, f64::MAX, &Media::new());
+        assert!(transmittance == Color::zero());
+        assert!(scattered.r > fog.color.r && scattered.b == fog.color.b);
+        scene.fog = None;
+        let (transmittance, _) = scene.participating_media(ray, 10.0, &Media::new());
+        assert!((transmittance.g - (-1.0f64).exp()).abs() < 1e-12);
+        assert_eq!(transmittance.b, 1.0);
+        // Degenerate rays leave no segments to sort.
+        let broken = Ray {
+            direction: Vec3D::from([f64::NAN, 0.0, -1.0]),
+            ..ray
+        };
+        scene.participating_media(broken, 10.0, &Media::new());
+    }
 }
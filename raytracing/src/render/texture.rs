@@ -0,0 +1,276 @@
+use crate::math::color::Color;
+use crate::math::vec3d::Vec3D;
+use image::RgbImage;
+use num_traits::real::Real;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use std::sync::Arc;
+
+/// Color varying over a surface, given the texture coordinates of a point and the
+/// point itself in the space of the object.
+pub trait Texture<T: Real>: Send + Sync {
+    fn color(&self, uv: (T, T), point: Vec3D<T>) -> Color<T>;
+}
+
+pub struct Solid<T: Real> {
+    pub color: Color<T>,
+}
+
+impl<T: Real + Send + Sync> Texture<T> for Solid<T> {
+    fn color(&self, _uv: (T, T), _point: Vec3D<T>) -> Color<T> {
+        self.color
+    }
+}
+
+/// Alternating squares of two textures, `scale` squares per unit of both coordinates.
+pub struct Checker<T: Real> {
+    pub even: Arc<dyn Texture<T>>,
+    pub odd: Arc<dyn Texture<T>>,
+    pub scale: T,
+}
+
+impl<T: Real + Send + Sync> Texture<T> for Checker<T> {
+    fn color(&self, (u, v): (T, T), point: Vec3D<T>) -> Color<T> {
+        let index = (u * self.scale).floor() + (v * self.scale).floor();
+        if index.to_i64().unwrap_or(0) % 2 == 0 {
+            self.even.color((u, v), point)
+        } else {
+            self.odd.color((u, v), point)
+        }
+    }
+}
+
+/// Alternating stripes of two textures across `u`, `scale` stripes per unit.
+pub struct Stripes<T: Real> {
+    pub even: Arc<dyn Texture<T>>,
+    pub odd: Arc<dyn Texture<T>>,
+    pub scale: T,
+}
+
+impl<T: Real + Send + Sync> Texture<T> for Stripes<T> {
+    fn color(&self, (u, v): (T, T), point: Vec3D<T>) -> Color<T> {
+        if (u * self.scale).floor().to_i64().unwrap_or(0) % 2 == 0 {
+            self.even.color((u, v), point)
+        } else {
+            self.odd.color((u, v), point)
+        }
+    }
+}
+
+/// Linear blend from `start` at `v = 0` to `end` at `v = 1`.
+pub struct Gradient<T: Real> {
+    pub start: Color<T>,
+    pub end: Color<T>,
+}
+
+impl<T: Real + Send + Sync> Texture<T> for Gradient<T> {
+    fn color(&self, (_, v): (T, T), _point: Vec3D<T>) -> Color<T> {
+        let v = v.max(T::zero()).min(T::one());
+        self.start * (T::one() - v) + self.end * v
+    }
+}
+
+/// Blend of two colors by Perlin noise of the point, summed over `octaves` of
+/// doubling frequency.
+pub struct Noise<T: Real> {
+    pub low: Color<T>,
+    pub high: Color<T>,
+    pub scale: T,
+    pub octaves: usize,
+    pub perlin: Perlin,
+}
+
+impl<T: Real + Send + Sync> Texture<T> for Noise<T> {
+    fn color(&self, _uv: (T, T), point: Vec3D<T>) -> Color<T> {
+        let point = point * self.scale;
+        let point = [point.x, point.y, point.z].map(|value| value.to_f64().unwrap());
+        let mut value = 0.0;
+        let mut weight = 1.0;
+        for octave in 0..self.octaves {
+            let frequency = (1u64 << octave.min(63)) as f64;
+            value += weight * self.perlin.noise(point.map(|value| value * frequency));
+            weight *= 0.5;
+        }
+        let t = T::from((0.5 + 0.5 * value).clamp(0.0, 1.0)).unwrap();
+        self.low * (T::one() - t) + self.high * t
+    }
+}
+
+/// Improved Perlin noise with a permutation shuffled by the seed.
+pub struct Perlin {
+    permutation: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut permutation: Vec<usize> = (0..256).collect();
+        permutation.shuffle(&mut Pcg32::seed_from_u64(seed));
+        permutation.extend_from_within(..);
+        Perlin { permutation }
+    }
+
+    /// Noise value within `[-1, 1]`, zero at integer points.
+    pub fn noise(&self, point: [f64; 3]) -> f64 {
+        let cell = point.map(|value| value.floor());
+        let [x, y, z] = [0, 1, 2].map(|axis| point[axis] - cell[axis]);
+        let [xi, yi, zi] = cell.map(|value| (value as i64).rem_euclid(256) as usize);
+        let [u, v, w] = [x, y, z].map(fade);
+
+        let p = &self.permutation;
+        let a = p[xi] + yi;
+        let (aa, ab) = (p[a] + zi, p[a + 1] + zi);
+        let b = p[xi + 1] + yi;
+        let (ba, bb) = (p[b] + zi, p[b + 1] + zi);
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, gradient(p[aa], x, y, z), gradient(p[ba], x - 1.0, y, z)),
+                lerp(
+                    u,
+                    gradient(p[ab], x, y - 1.0, z),
+                    gradient(p[bb], x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(p[aa + 1], x, y, z - 1.0),
+                    gradient(p[ba + 1], x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    gradient(p[ab + 1], x, y - 1.0, z - 1.0),
+                    gradient(p[bb + 1], x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// Dot product of the offset with one of the twelve cube edge directions.
+fn gradient(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// Image wrapped around the texture coordinates, `v = 0` at its bottom row,
+/// bilinearly interpolated.
+pub struct ImageTexture {
+    pub image: RgbImage,
+}
+
+impl<T: Real> Texture<T> for ImageTexture {
+    fn color(&self, (u, v): (T, T), _point: Vec3D<T>) -> Color<T> {
+        let (width, height) = self.image.dimensions();
+        let x = u.to_f64().unwrap() * width as f64 - 0.5;
+        let y = (1.0 - v.to_f64().unwrap()) * height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let texel = |x: f64, y: f64| {
+            let x = (x as i64).rem_euclid(width as i64) as u32;
+            let y = (y as i64).rem_euclid(height as i64) as u32;
+            let [r, g, b] = self.image.get_pixel(x, y).0;
+            [r, g, b].map(|channel| channel as f64 / 255.0)
+        };
+        let (c00, c10) = (texel(x0, y0), texel(x0 + 1.0, y0));
+        let (c01, c11) = (texel(x0, y0 + 1.0), texel(x0 + 1.0, y0 + 1.0));
+        let [r, g, b] = [0, 1, 2].map(|channel| {
+            let top = lerp(fx, c00[channel], c10[channel]);
+            let bottom = lerp(fx, c01[channel], c11[channel]);
+            T::from(lerp(fy, top, bottom)).unwrap()
+        });
+        Color::from([r, g, b])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    fn solid(r: f64, g: f64, b: f64) -> Arc<dyn Texture<f64>> {
+        Arc::new(Solid {
+            color: Color::from([r, g, b]),
+        })
+    }
+
+    #[test]
+    fn test_patterns() {
+        let origin = Vec3D::default();
+        let checker = Checker {
+            even: solid(1.0, 1.0, 1.0),
+            odd: solid(0.0, 0.0, 0.0),
+            scale: 2.0,
+        };
+        assert_eq!(checker.color((0.1, 0.1), origin).r, 1.0);
+        assert_eq!(checker.color((0.6, 0.1), origin).r, 0.0);
+        assert_eq!(checker.color((0.6, 0.6), origin).r, 1.0);
+        assert_eq!(checker.color((-0.1, 0.1), origin).r, 0.0);
+
+        let stripes = Stripes {
+            even: solid(1.0, 1.0, 1.0),
+            odd: solid(0.0, 0.0, 0.0),
+            scale: 4.0,
+        };
+        assert_eq!(stripes.color((0.1, 0.9), origin).r, 1.0);
+        assert_eq!(stripes.color((0.3, 0.1), origin).r, 0.0);
+
+        let gradient = Gradient {
+            start: Color::from([0.0, 0.0, 0.0]),
+            end: Color::from([1.0, 0.5, 0.0]),
+        };
+        assert_eq!(gradient.color((0.0, 0.5), origin).g, 0.25);
+        assert_eq!(gradient.color((0.0, 2.0), origin).r, 1.0);
+    }
+
+    #[test]
+    fn test_perlin_noise() {
+        let perlin = Perlin::new(7);
+        assert_eq!(perlin.noise([1.0, 2.0, 3.0]), 0.0);
+        let value = perlin.noise([1.3, 2.7, -3.2]);
+        assert!(value.abs() <= 1.0);
+        assert_eq!(value, Perlin::new(7).noise([1.3, 2.7, -3.2]));
+        assert!((value - perlin.noise([1.31, 2.7, -3.2])).abs() < 0.1);
+        let values: Vec<f64> = (0..100)
+            .map(|index| perlin.noise([index as f64 * 0.37, 0.5, 0.25]))
+            .collect();
+        assert!(values.iter().any(|value| *value > 0.1));
+        assert!(values.iter().any(|value| *value < -0.1));
+    }
+
+    #[test]
+    fn test_image_texture() {
+        let mut image = RgbImage::new(2, 2);
+        image.put_pixel(0, 0, Rgb([255, 0, 0]));
+        image.put_pixel(1, 0, Rgb([0, 255, 0]));
+        image.put_pixel(0, 1, Rgb([0, 0, 255]));
+        image.put_pixel(1, 1, Rgb([255, 255, 255]));
+        let texture = ImageTexture { image };
+        let color: Color<f64> = texture.color((0.25, 0.75), Vec3D::default());
+        assert_eq!((color.r, color.g, color.b), (1.0, 0.0, 0.0));
+        let color: Color<f64> = texture.color((0.75, 0.25), Vec3D::default());
+        assert_eq!((color.r, color.g, color.b), (1.0, 1.0, 1.0));
+        let color: Color<f64> = texture.color((0.5, 0.75), Vec3D::default());
+        assert_eq!((color.r, color.g, color.b), (0.5, 0.5, 0.0));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::math::color::Color;
+use image::Rgb;
+use num_traits::real::Real;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Curve compressing the unbounded linear colors of a frame into `[0, 1]`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToneOperator {
+    /// Colors above one are cut off.
+    #[default]
+    Clamp,
+    /// `c / (1 + c)`, keeping dark colors and compressing bright ones smoothly.
+    Reinhard,
+    /// Approximation of the ACES filmic curve, with a slight toe and a soft shoulder.
+    Aces,
+}
+
+/// How the linear colors of a frame are turned into 8-bit pixels. High dynamic range
+/// outputs store the linear colors as they are.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToneMapping {
+    pub operator: ToneOperator,
+    /// Exposure in stops, colors are scaled by `2^exposure` before the operator.
+    pub exposure: f64,
+    /// Encodes the colors by the sRGB transfer curve instead of writing them linearly.
+    pub srgb: bool,
+}
+
+impl ToneOperator {
+    /// Maps a non-negative linear channel value into `[0, 1]`.
+    pub fn apply(&self, value: f64) -> f64 {
+        let value = value.max(0.0);
+        let mapped = match self {
+            ToneOperator::Clamp => value,
+            ToneOperator::Reinhard => value / (1.0 + value),
+            ToneOperator::Aces => {
+                (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+            }
+        };
+        mapped.min(1.0)
+    }
+}
+
+impl ToneMapping {
+    pub fn map<T: Real>(&self, color: Color<T>) -> Rgb<u8> {
+        let scale = self.exposure.exp2();
+        let channel = |value: T| {
+            let value = self.operator.apply(value.to_f64().unwrap_or(0.0) * scale);
+            let value = if self.srgb { srgb_encode(value) } else { value };
+            (value * 255.0).round() as u8
+        };
+        Rgb([channel(color.r), channel(color.g), channel(color.b)])
+    }
+}
+
+/// sRGB transfer function of a linear value in `[0, 1]`.
+fn srgb_encode(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        12.92 * value
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl FromStr for ToneOperator {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "clamp" => Ok(ToneOperator::Clamp),
+            "reinhard" => Ok(ToneOperator::Reinhard),
+            "aces" => Ok(ToneOperator::Aces),
+            _ => Err(format!(
+                "unknown tone mapping `{}`, expected `clamp`, `reinhard` or `aces`",
+                name
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone_mapping() {
+        let bright = Color::from([0.5, 1.0, 9.0]);
+        assert_eq!(ToneMapping::default().map(bright), Rgb([128, 255, 255]));
+        let reinhard = ToneMapping {
+            operator: ToneOperator::Reinhard,
+            ..ToneMapping::default()
+        };
+        assert_eq!(reinhard.map(bright), Rgb([85, 128, 230]));
+        let exposed = ToneMapping {
+            exposure: 1.0,
+            ..reinhard
+        };
+        assert_eq!(exposed.map(Color::from([0.5, 0.0, 0.0])).0[0], 128);
+
+        let aces = ToneOperator::Aces;
+        assert_eq!(aces.apply(0.0), 0.0);
+        assert!(aces.apply(0.18) > 0.2 && aces.apply(0.18) < 0.3);
+        assert!(aces.apply(2.0) < aces.apply(4.0));
+        assert_eq!(aces.apply(100.0), 1.0);
+
+        assert_eq!(srgb_encode(0.0), 0.0);
+        assert!((srgb_encode(1.0) - 1.0).abs() < 1e-12);
+        assert!((srgb_encode(0.2140) - 0.5).abs() < 1e-3);
+        let srgb = ToneMapping {
+            srgb: true,
+            ..ToneMapping::default()
+        };
+        assert_eq!(
+            srgb.map(Color::from([0.214, 1.0, 2.0])),
+            Rgb([127, 255, 255])
+        );
+    }
+}
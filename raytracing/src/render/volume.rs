@@ -0,0 +1,177 @@
+use crate::math::color::Color;
+use crate::math::ray::Ray;
+use crate::render::hittable::Hittable;
+use num_traits::real::Real;
+use serde::{Deserialize, Serialize};
+
+/// Exponential fog filling the space around all objects, fading what is seen into its
+/// color with the distance.
+#[derive(Copy, Clone, Deserialize, Serialize)]
+#[serde(
+    deny_unknown_fields,
+    bound(
+        deserialize = "T: Real + Deserialize<'de>",
+        serialize = "T: Real + Serialize"
+    )
+)]
+pub struct Fog<T: Real> {
+    pub color: Color<T>,
+    /// Fraction of the light replaced by the fog per unit of length.
+    pub density: T,
+}
+
+impl<T: Real> Fog<T> {
+    /// Fraction of the light left after traveling the distance through the fog.
+    pub fn transmittance(&self, distance: T) -> T {
+        if self.density == T::zero() {
+            T::one()
+        } else {
+            (-self.density * distance).exp()
+        }
+    }
+}
+
+/// Homogeneous participating medium filling the inside of a closed object, which
+/// itself is not seen. Light passing through is absorbed and scattered out of its way,
+/// and the light of the lights scattered once is added along the way.
+pub struct Volume<T: Real> {
+    pub boundary: Hittable<T>,
+    /// Fraction of each color channel absorbed per unit of length.
+    pub absorption: Color<T>,
+    /// Fraction of each color channel scattered per unit of length.
+    pub scattering: Color<T>,
+    /// Henyey–Greenstein asymmetry from `-1` to `1`: positive values scatter light
+    /// forward, negative ones back towards the lights, `0` evenly in all directions.
+    pub anisotropy: T,
+    /// Points the scattered light is gathered at along every ray through the volume.
+    pub steps: usize,
+}
+
+impl<T: Real> Volume<T> {
+    /// Fraction of each color channel taken out of the light per unit of length.
+    pub fn extinction(&self) -> Color<T> {
+        self.absorption + self.scattering
+    }
+
+    /// Sorted distances along the ray it enters and leaves the volume at, cut to
+    /// the ray between `0` and `max_distance`.
+    pub fn segments(&self, ray: Ray<T>, max_distance: T) -> Vec<(T, T)> {
+        self.boundary
+            .spans(ray)
+            .into_iter()
+            .map(|span| {
+                (
+                    span.enter.distance.max(T::zero()),
+                    span.exit.distance.min(max_distance),
+                )
+            })
+            .filter(|(enter, exit)| enter < exit)
+            .collect()
+    }
+
+    /// Fraction of the light left after traveling along the ray up to `max_distance`.
+    pub fn transmittance(&self, ray: Ray<T>, max_distance: T) -> Color<T> {
+        let length = self
+            .segments(ray, max_distance)
+            .into_iter()
+            .fold(T::zero(), |length, (enter, exit)| length + exit - enter);
+        extinguish(self.extinction(), length)
+    }
+
+    /// Share of the light scattered by the angle with the given cosine, relative to
+    /// scattering evenly in all directions.
+    pub fn phase(&self, cosine: T) -> T {
+        let g = self.anisotropy;
+        if g == T::zero() {
+            return T::one();
+        }
+        let one = T::one();
+        let denominator = one + g * g - (g + g) * cosine;
+        (one - g * g) / (denominator * denominator.sqrt())
+    }
+}
+
+/// Fraction of the light left after traveling the distance through a medium of the
+/// extinction, by the Beer–Lambert law.
+pub fn extinguish<T: Real>(extinction: Color<T>, distance: T) -> Color<T> {
+    let channel = |extinction: T| {
+        if extinction == T::zero() {
+            T::one()
+        } else {
+            (-extinction * distance).exp()
+        }
+    };
+    Color {
+        r: channel(extinction.r),
+        g: channel(extinction.g),
+        b: channel(extinction.b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::shape::{Shape, Sphere};
+    use crate::math::vec3d::Vec3D;
+    use crate::render::material::Material;
+
+    fn volume(anisotropy: f64) -> Volume<f64> {
+        Volume {
+            boundary: Hittable::Primitive {
+                shape: Shape::Sphere(Sphere {
+                    center: Vec3D::from([0.0, 0.0, 0.0]),
+                    radius: 1.0,
+                }),
+                material: Material::default().into(),
+            },
+            absorption: Color::from([0.5, 0.0, 0.0]),
+            scattering: Color::from([0.0, 0.25, 0.0]),
+            anisotropy,
+            steps: 8,
+        }
+    }
+
+    #[test]
+    fn test_volume_transmittance() {
+        let volume = volume(0.0);
+        let ray = Ray {
+            origin: Vec3D::from([-3.0, 0.0, 0.0]),
+            direction: Vec3D::from([1.0, 0.0, 0.0]),
+            time: 0.0,
+        };
+        assert_eq!(volume.segments(ray, 10.0), vec![(2.0, 4.0)]);
+        assert_eq!(volume.segments(ray, 3.0), vec![(2.0, 3.0)]);
+        assert!(volume.segments(ray, 1.0).is_empty());
+        let inside = Ray {
+            origin: Vec3D::from([0.0, 0.0, 0.0]),
+            ..ray
+        };
+        assert_eq!(volume.segments(inside, 10.0), vec![(0.0, 1.0)]);
+
+        let color = volume.transmittance(ray, 10.0);
+        assert!((color.r - (-1.0f64).exp()).abs() < 1e-12);
+        assert!((color.g - (-0.5f64).exp()).abs() < 1e-12);
+        assert_eq!(color.b, 1.0);
+
+        let fog = Fog {
+            color: Color::unit(),
+            density: 0.5,
+        };
+        assert!((fog.transmittance(2.0) - (-1.0f64).exp()).abs() < 1e-12);
+        assert_eq!(fog.transmittance(f64::MAX), 0.0);
+    }
+
+    #[test]
+    fn test_phase() {
+        assert_eq!(volume(0.0).phase(0.3), 1.0);
+        let forward = volume(0.6);
+        assert!(forward.phase(1.0) > 1.0 && forward.phase(-1.0) < 1.0);
+        // Averages to one over the sphere of directions.
+        let steps = 10000;
+        let average = (0..steps)
+            .map(|step| forward.phase(-1.0 + 2.0 * (step as f64 + 0.5) / steps as f64))
+            .sum::<f64>()
+            / steps as f64;
+        assert!((average - 1.0).abs() < 1e-3);
+    }
+}